@@ -0,0 +1,154 @@
+use syn::{parenthesized, parse::ParseStream, Attribute, Expr, Ident, LitStr, Token};
+
+/// The options of a struct or enum that are set with `#[rusty_value(...)]`
+#[derive(Default)]
+pub(crate) struct ContainerAttrs {
+    /// Whether a `StreamRustyValue` impl is generated as well
+    /// which requires all fields to implement it
+    pub stream: bool,
+}
+
+/// The options of a field that are set with `#[rusty_value(...)]`
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    pub validations: Vec<Validation>,
+    /// Whether the value of the field should be redacted
+    pub sensitive: bool,
+}
+
+/// A single entry of `#[rusty_value(validate(...))]`
+pub(crate) enum Validation {
+    Range {
+        min: Option<Expr>,
+        max: Option<Expr>,
+    },
+    Length {
+        min: Option<Expr>,
+        max: Option<Expr>,
+    },
+    NonEmpty,
+    Regex(LitStr),
+    Custom(syn::Path),
+    Nested,
+}
+
+impl ContainerAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut container_attrs = Self::default();
+
+        for attr in attrs.iter().filter(|a| a.path.is_ident("rusty_value")) {
+            attr.parse_args_with(|input: ParseStream| {
+                while !input.is_empty() {
+                    let key: Ident = input.parse()?;
+
+                    match key.to_string().as_str() {
+                        "stream" => container_attrs.stream = true,
+                        _ => return Err(syn::Error::new(key.span(), "unknown rusty_value option")),
+                    }
+                    if !input.is_empty() {
+                        input.parse::<Token![,]>()?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(container_attrs)
+    }
+}
+
+impl FieldAttrs {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut field_attrs = Self::default();
+
+        for attr in attrs.iter().filter(|a| a.path.is_ident("rusty_value")) {
+            attr.parse_args_with(|input: ParseStream| {
+                while !input.is_empty() {
+                    let key: Ident = input.parse()?;
+
+                    match key.to_string().as_str() {
+                        "sensitive" => field_attrs.sensitive = true,
+                        "validate" => {
+                            let content;
+                            parenthesized!(content in input);
+                            parse_validations(&content, &mut field_attrs.validations)?;
+                        }
+                        _ => return Err(syn::Error::new(key.span(), "unknown rusty_value option")),
+                    }
+                    if !input.is_empty() {
+                        input.parse::<Token![,]>()?;
+                    }
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(field_attrs)
+    }
+}
+
+fn parse_validations(input: ParseStream, validations: &mut Vec<Validation>) -> syn::Result<()> {
+    while !input.is_empty() {
+        let key: Ident = input.parse()?;
+
+        let validation = match key.to_string().as_str() {
+            "range" => {
+                let (min, max) = parse_bounds(input)?;
+                Validation::Range { min, max }
+            }
+            "length" => {
+                let (min, max) = parse_bounds(input)?;
+                Validation::Length { min, max }
+            }
+            "non_empty" => Validation::NonEmpty,
+            "nested" => Validation::Nested,
+            "regex" => {
+                input.parse::<Token![=]>()?;
+                let pattern: LitStr = input.parse()?;
+                // reports invalid patterns at compile time instead of panicking on the first check
+                if let Err(e) = regex_syntax::Parser::new().parse(&pattern.value()) {
+                    return Err(syn::Error::new(
+                        pattern.span(),
+                        format!("invalid regex pattern: {e}"),
+                    ));
+                }
+                Validation::Regex(pattern)
+            }
+            "custom" => {
+                input.parse::<Token![=]>()?;
+                let path: LitStr = input.parse()?;
+                Validation::Custom(path.parse()?)
+            }
+            _ => return Err(syn::Error::new(key.span(), "unknown validation")),
+        };
+        validations.push(validation);
+
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+        }
+    }
+    Ok(())
+}
+
+/// Parses `(min = .., max = ..)` where both bounds are optional
+fn parse_bounds(input: ParseStream) -> syn::Result<(Option<Expr>, Option<Expr>)> {
+    let content;
+    parenthesized!(content in input);
+    let (mut min, mut max) = (None, None);
+
+    while !content.is_empty() {
+        let key: Ident = content.parse()?;
+        content.parse::<Token![=]>()?;
+        let value: Expr = content.parse()?;
+
+        match key.to_string().as_str() {
+            "min" => min = Some(value),
+            "max" => max = Some(value),
+            _ => return Err(syn::Error::new(key.span(), "expected `min` or `max`")),
+        }
+        if !content.is_empty() {
+            content.parse::<Token![,]>()?;
+        }
+    }
+    Ok((min, max))
+}
@@ -1,22 +1,71 @@
+mod attr;
+
+use attr::{ContainerAttrs, FieldAttrs, Validation};
 use proc_macro::TokenStream;
 use proc_macro2::Span;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::{
     parse_macro_input, parse_quote, DataEnum, DataStruct, DeriveInput, FieldsNamed, FieldsUnnamed,
     Generics, Variant, WhereClause, WherePredicate,
 };
 
-#[proc_macro_derive(RustyValue)]
+#[proc_macro_derive(RustyValue, attributes(rusty_value))]
 pub fn derive_value(input: TokenStream) -> TokenStream {
     derive(parse_macro_input!(input as DeriveInput))
 }
 
+#[proc_macro_derive(RustyType, attributes(rusty_value))]
+pub fn derive_type(input: TokenStream) -> TokenStream {
+    derive_rusty_type(parse_macro_input!(input as DeriveInput))
+        .unwrap_or_else(|e| e.to_compile_error().into())
+}
+
 fn derive(input: DeriveInput) -> TokenStream {
-    match &input.data {
+    let container_attrs = match ContainerAttrs::from_attrs(&input.attrs) {
+        Ok(attrs) => attrs,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let mut tokens = match &input.data {
         syn::Data::Struct(s) => derive_struct(&input, s),
         syn::Data::Enum(e) => derive_enum(&input, e),
         syn::Data::Union(_) => panic!("unions are currently unsupported"),
+    };
+    if container_attrs.stream {
+        tokens.extend(derive_stream(&input));
     }
+    tokens.extend(derive_validate(&input).unwrap_or_else(|e| e.to_compile_error().into()));
+    tokens.extend(derive_sensitive_check(&input).unwrap_or_else(|e| e.to_compile_error().into()));
+
+    tokens
+}
+
+/// Fields marked with `#[rusty_value(sensitive)]` are only found through the schema
+/// so types with such fields fail to compile if they don't implement `RustyType`
+fn derive_sensitive_check(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let fields: Vec<&syn::Field> = match &input.data {
+        syn::Data::Struct(s) => s.fields.iter().collect(),
+        syn::Data::Enum(e) => e.variants.iter().flat_map(|v| &v.fields).collect(),
+        syn::Data::Union(_) => Vec::new(),
+    };
+    let mut has_sensitive = false;
+
+    for field in fields {
+        has_sensitive |= FieldAttrs::from_attrs(&field.attrs)?.sensitive;
+    }
+    if !has_sensitive {
+        return Ok(TokenStream::new());
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = add_bound(&input.generics, parse_quote!(rusty_value::RustyType));
+
+    Ok(TokenStream::from(quote! {
+        const _: () = {
+            fn assert_sensitive_fields #impl_generics () #where_clause {
+                rusty_value::assert_sensitive_fields::<#ident #ty_generics>();
+            }
+        };
+    }))
 }
 
 fn derive_struct(input: &DeriveInput, struct_data: &DataStruct) -> TokenStream {
@@ -40,11 +89,11 @@ fn derive_struct(input: &DeriveInput, struct_data: &DataStruct) -> TokenStream {
                         let mut values = std::collections::HashMap::with_capacity(#field_count);
 
                         #(
-                            values.insert(#field_names.to_string(), self.#field_idents.into_rusty_value());
+                            values.insert(std::borrow::Cow::Borrowed(#field_names), self.#field_idents.into_rusty_value());
                         )*
 
                         Value::Struct(Struct{
-                            name: #name.to_string(),
+                            name: std::borrow::Cow::Borrowed(#name),
                             fields: Fields::Named(values),
                         })
                     }
@@ -69,7 +118,7 @@ fn derive_struct(input: &DeriveInput, struct_data: &DataStruct) -> TokenStream {
                         )*
 
                         Value::Struct(Struct{
-                            name: #name.to_string(),
+                            name: std::borrow::Cow::Borrowed(#name),
                             fields: Fields::Unnamed(values),
                         })
                     }
@@ -80,7 +129,7 @@ fn derive_struct(input: &DeriveInput, struct_data: &DataStruct) -> TokenStream {
                 impl #impl_generics RustyValue for #ident #ty_generics #where_clause {
                     fn into_rusty_value(self) -> Value {
                         Value::Struct(Struct{
-                            name: #name.to_string(),
+                            name: std::borrow::Cow::Borrowed(#name),
                             fields: Fields::Unit,
                         })
                     }
@@ -129,11 +178,11 @@ fn create_enum_value_match(ident: &syn::Ident, variant: &Variant) -> proc_macro2
                 #ident::#variant_ident { #( #field_idents, )* } => {
                     let mut fields = std::collections::HashMap::with_capacity(#field_count);
                     #(
-                        fields.insert(#field_names.to_string(), #field_idents.into_rusty_value());
+                        fields.insert(std::borrow::Cow::Borrowed(#field_names), #field_idents.into_rusty_value());
                     )*
                     Enum {
-                        name: #enum_name.to_string(),
-                        variant: #variant_name.to_string(),
+                        name: std::borrow::Cow::Borrowed(#enum_name),
+                        variant: std::borrow::Cow::Borrowed(#variant_name),
                         fields: Fields::Named(fields)
                     }
                 }
@@ -154,8 +203,8 @@ fn create_enum_value_match(ident: &syn::Ident, variant: &Variant) -> proc_macro2
                         fields.push(#field_names.into_rusty_value());
                     )*
                     Enum {
-                        name: #enum_name.to_string(),
-                        variant: #variant_name.to_string(),
+                        name: std::borrow::Cow::Borrowed(#enum_name),
+                        variant: std::borrow::Cow::Borrowed(#variant_name),
                         fields: Fields::Unnamed(fields)
                     }
                 }
@@ -164,8 +213,8 @@ fn create_enum_value_match(ident: &syn::Ident, variant: &Variant) -> proc_macro2
         syn::Fields::Unit => quote! {
             #ident::#variant_ident => {
                 Enum {
-                    name: #enum_name.to_string(),
-                    variant: #variant_name.to_string(),
+                    name: std::borrow::Cow::Borrowed(#enum_name),
+                    variant: std::borrow::Cow::Borrowed(#variant_name),
                     fields: Fields::Unit
                 }
             }
@@ -173,9 +222,437 @@ fn create_enum_value_match(ident: &syn::Ident, variant: &Variant) -> proc_macro2
     }
 }
 
+/// Generates the [StreamRustyValue] impl that passes the value to a sink piece by piece
+fn derive_stream(input: &DeriveInput) -> TokenStream {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = add_bound(&input.generics, parse_quote!(rusty_value::StreamRustyValue));
+
+    let body = match &input.data {
+        syn::Data::Struct(s) => {
+            let name = ident.to_string();
+            let (kind, len) = fields_kind(&s.fields);
+            let fields = match &s.fields {
+                syn::Fields::Named(FieldsNamed { named, .. }) => {
+                    let field_idents = named.iter().map(|f| f.ident.as_ref()).collect::<Vec<_>>();
+                    let field_names = named
+                        .iter()
+                        .map(|f| f.ident.as_ref().unwrap().to_string())
+                        .collect::<Vec<_>>();
+
+                    quote! {
+                        #(
+                            sink.field(std::borrow::Cow::Borrowed(#field_names))?;
+                            rusty_value::StreamRustyValue::stream_rusty_value(&self.#field_idents, sink)?;
+                        )*
+                    }
+                }
+                syn::Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
+                    let field_indices = (0..unnamed.len()).map(syn::Index::from);
+
+                    quote! {
+                        #(
+                            rusty_value::StreamRustyValue::stream_rusty_value(&self.#field_indices, sink)?;
+                        )*
+                    }
+                }
+                syn::Fields::Unit => quote!(),
+            };
+
+            quote! {
+                sink.begin_struct(std::borrow::Cow::Borrowed(#name), #kind, #len)?;
+                #fields
+                sink.end_struct()
+            }
+        }
+        syn::Data::Enum(e) => {
+            let variant_matchers = e
+                .variants
+                .iter()
+                .map(|v| create_enum_stream_match(ident, v));
+
+            quote! {
+                match *self {
+                    #( #variant_matchers )*
+                }
+            }
+        }
+        syn::Data::Union(_) => panic!("unions are currently unsupported"),
+    };
+
+    TokenStream::from(quote! {
+        impl #impl_generics rusty_value::StreamRustyValue for #ident #ty_generics #where_clause {
+            fn stream_rusty_value<'__a, __S: rusty_value::Sink<'__a>>(&'__a self, sink: &mut __S) -> Result<(), __S::Error> {
+                #body
+            }
+        }
+    })
+}
+
+fn create_enum_stream_match(ident: &syn::Ident, variant: &Variant) -> proc_macro2::TokenStream {
+    let enum_name = ident.to_string();
+    let variant_ident = &variant.ident;
+    let variant_name = variant_ident.to_string();
+    let (kind, len) = fields_kind(&variant.fields);
+
+    let (pattern, fields) = match &variant.fields {
+        syn::Fields::Named(FieldsNamed { named, .. }) => {
+            let field_idents = named.iter().map(|f| &f.ident).collect::<Vec<_>>();
+            let field_names = named
+                .iter()
+                .map(|f| f.ident.as_ref().unwrap().to_string())
+                .collect::<Vec<_>>();
+
+            (
+                quote!({ #( ref #field_idents, )* }),
+                quote! {
+                    #(
+                        sink.field(std::borrow::Cow::Borrowed(#field_names))?;
+                        rusty_value::StreamRustyValue::stream_rusty_value(#field_idents, sink)?;
+                    )*
+                },
+            )
+        }
+        syn::Fields::Unnamed(FieldsUnnamed { unnamed, .. }) => {
+            let field_names = unnamed
+                .iter()
+                .enumerate()
+                .map(|(i, _)| syn::Ident::new(&format!("f{i}"), Span::call_site()))
+                .collect::<Vec<_>>();
+
+            (
+                quote!(( #( ref #field_names, )* )),
+                quote! {
+                    #(
+                        rusty_value::StreamRustyValue::stream_rusty_value(#field_names, sink)?;
+                    )*
+                },
+            )
+        }
+        syn::Fields::Unit => (quote!(), quote!()),
+    };
+
+    quote! {
+        #ident::#variant_ident #pattern => {
+            sink.begin_enum(
+                std::borrow::Cow::Borrowed(#enum_name),
+                std::borrow::Cow::Borrowed(#variant_name),
+                #kind,
+                #len,
+            )?;
+            #fields
+            sink.end_enum()
+        }
+    }
+}
+
+/// Returns the [FieldsKind] variant and the number of fields
+fn fields_kind(fields: &syn::Fields) -> (proc_macro2::TokenStream, usize) {
+    match fields {
+        syn::Fields::Named(n) => (quote!(rusty_value::FieldsKind::Named), n.named.len()),
+        syn::Fields::Unnamed(u) => (quote!(rusty_value::FieldsKind::Unnamed), u.unnamed.len()),
+        syn::Fields::Unit => (quote!(rusty_value::FieldsKind::Unit), 0),
+    }
+}
+
+/// Generates the [Validate] impl that checks the constraints declared with `#[rusty_value(validate(...))]`
+fn derive_validate(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = add_bound(&input.generics, parse_quote!(rusty_value::StreamRustyValue));
+    let mut has_checks = false;
+
+    let body = match &input.data {
+        syn::Data::Struct(s) => {
+            let mut checks = Vec::new();
+
+            for (i, field) in s.fields.iter().enumerate() {
+                let (value, segment) = match &field.ident {
+                    Some(field_ident) => {
+                        let name = field_ident.to_string();
+                        (
+                            quote!(&self.#field_ident),
+                            quote!(rusty_value::PathSegment::Field(String::from(#name))),
+                        )
+                    }
+                    None => {
+                        let index = syn::Index::from(i);
+                        (
+                            quote!(&self.#index),
+                            quote!(rusty_value::PathSegment::Index(#i)),
+                        )
+                    }
+                };
+                checks.extend(create_field_checks(
+                    &FieldAttrs::from_attrs(&field.attrs)?,
+                    value,
+                    segment,
+                ));
+            }
+            has_checks = !checks.is_empty();
+
+            quote!( #( #checks )* )
+        }
+        syn::Data::Enum(e) => {
+            let mut variant_matchers = Vec::new();
+
+            for variant in &e.variants {
+                let variant_ident = &variant.ident;
+                let mut checks = Vec::new();
+                let mut bindings = Vec::new();
+
+                for (i, field) in variant.fields.iter().enumerate() {
+                    let (binding, segment) = match &field.ident {
+                        Some(field_ident) => {
+                            let name = field_ident.to_string();
+                            (
+                                field_ident.clone(),
+                                quote!(rusty_value::PathSegment::Field(String::from(#name))),
+                            )
+                        }
+                        None => (
+                            syn::Ident::new(&format!("f{i}"), Span::call_site()),
+                            quote!(rusty_value::PathSegment::Index(#i)),
+                        ),
+                    };
+                    let field_checks = create_field_checks(
+                        &FieldAttrs::from_attrs(&field.attrs)?,
+                        quote!(#binding),
+                        segment,
+                    );
+                    bindings.push(field_checks.as_ref().map(|_| binding));
+                    checks.extend(field_checks);
+                }
+
+                let pattern = match &variant.fields {
+                    _ if checks.is_empty() => quote!({ .. }),
+                    syn::Fields::Named(_) => {
+                        let bindings = bindings.iter().flatten();
+                        quote!({ #( ref #bindings, )* .. })
+                    }
+                    _ => {
+                        let bindings = bindings.iter().map(|b| match b {
+                            Some(binding) => quote!(ref #binding),
+                            None => quote!(_),
+                        });
+                        quote!(( #( #bindings, )* ))
+                    }
+                };
+                has_checks |= !checks.is_empty();
+
+                variant_matchers.push(quote! {
+                    #ident::#variant_ident #pattern => {
+                        #( #checks )*
+                    }
+                });
+            }
+
+            quote! {
+                match *self {
+                    #( #variant_matchers )*
+                }
+            }
+        }
+        syn::Data::Union(_) => panic!("unions are currently unsupported"),
+    };
+
+    // types without any validations don't implement `Validate`
+    // so they don't conflict with a handwritten impl
+    if !has_checks {
+        return Ok(TokenStream::new());
+    }
+
+    Ok(TokenStream::from(quote! {
+        impl #impl_generics rusty_value::Validate for #ident #ty_generics #where_clause {
+            fn validate_at(
+                &self,
+                __path: &mut rusty_value::Path,
+                __violations: &mut Vec<rusty_value::Violation>,
+            ) {
+                #body
+            }
+        }
+    }))
+}
+
+/// Creates the checks of a single field where `value` is a reference to the field.
+/// Returns `None` if the field doesn't have any validations.
+fn create_field_checks(
+    attrs: &FieldAttrs,
+    value: proc_macro2::TokenStream,
+    segment: proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    if attrs.validations.is_empty() {
+        return None;
+    }
+    let mut constraints = Vec::new();
+    let mut checks = Vec::new();
+
+    for validation in &attrs.validations {
+        match validation {
+            Validation::Range { min, max } => {
+                let min = create_option(
+                    min.as_ref()
+                        .map(|min| quote!(rusty_value::Number::from(#min))),
+                );
+                let max = create_option(
+                    max.as_ref()
+                        .map(|max| quote!(rusty_value::Number::from(#max))),
+                );
+                constraints.push(quote!(rusty_value::Constraint::Range { min: #min, max: #max }));
+            }
+            Validation::Length { min, max } => {
+                let min = create_option(min.as_ref().map(|min| quote!(#min)));
+                let max = create_option(max.as_ref().map(|max| quote!(#max)));
+                constraints.push(quote!(rusty_value::Constraint::Length { min: #min, max: #max }));
+            }
+            Validation::NonEmpty => constraints.push(quote!(rusty_value::Constraint::NonEmpty)),
+            // fails to compile with a clear error at the pattern if the `regex` feature is disabled
+            Validation::Regex(pattern) => constraints.push(quote_spanned! {pattern.span()=>
+                rusty_value::__regex_constraint!(#pattern)
+            }),
+            Validation::Custom(function) => checks.push(quote! {
+                if let Err(e) = #function(#value) {
+                    __violations.push(rusty_value::Violation {
+                        path: __path.clone(),
+                        kind: rusty_value::ViolationKind::Custom(e.to_string()),
+                    });
+                }
+            }),
+            Validation::Nested => checks.push(quote! {
+                rusty_value::Validate::validate_at(#value, __path, __violations);
+            }),
+        }
+    }
+
+    let constraints = (!constraints.is_empty()).then(|| {
+        quote! {
+            static CONSTRAINTS: std::sync::OnceLock<Vec<rusty_value::Constraint>> =
+                std::sync::OnceLock::new();
+            let constraints = CONSTRAINTS.get_or_init(|| vec![ #( #constraints, )* ]);
+            rusty_value::Constraint::check_field(constraints, #value, __path, __violations);
+        }
+    });
+
+    Some(quote! {
+        __path.push(#segment);
+        {
+            #constraints
+            #( #checks )*
+        }
+        __path.pop();
+    })
+}
+
+fn create_option(value: Option<proc_macro2::TokenStream>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote!(Some(#value)),
+        None => quote!(None),
+    }
+}
+
+/// Generates the [RustyType] impl that describes the shape of the type
+fn derive_rusty_type(input: DeriveInput) -> syn::Result<TokenStream> {
+    let ident = &input.ident;
+    let name = ident.to_string();
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let where_clause = add_bound(&input.generics, parse_quote!(rusty_value::RustyType));
+
+    let schema = match &input.data {
+        syn::Data::Struct(s) => {
+            let fields = create_fields_schema(&s.fields)?;
+
+            quote! {
+                TypeSchema::Struct(StructSchema {
+                    name: #name,
+                    type_name: std::any::type_name::<Self>(),
+                    fields: #fields,
+                })
+            }
+        }
+        syn::Data::Enum(e) => {
+            let variant_names = e.variants.iter().map(|v| v.ident.to_string());
+            let variant_fields = e
+                .variants
+                .iter()
+                .map(|v| create_fields_schema(&v.fields))
+                .collect::<syn::Result<Vec<_>>>()?;
+
+            quote! {
+                TypeSchema::Enum(EnumSchema {
+                    name: #name,
+                    type_name: std::any::type_name::<Self>(),
+                    variants: vec![
+                        #(
+                            VariantSchema {
+                                name: #variant_names,
+                                fields: #variant_fields,
+                            },
+                        )*
+                    ],
+                })
+            }
+        }
+        syn::Data::Union(_) => panic!("unions are currently unsupported"),
+    };
+
+    Ok(TokenStream::from(quote! {
+        impl #impl_generics RustyType for #ident #ty_generics #where_clause {
+            fn rusty_type() -> TypeSchema {
+                TypeSchema::named::<Self>(#name, || #schema)
+            }
+        }
+    }))
+}
+
+fn create_fields_schema(fields: &syn::Fields) -> syn::Result<proc_macro2::TokenStream> {
+    let field_schemas = fields
+        .iter()
+        .map(create_field_schema)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let schema = match fields {
+        syn::Fields::Named(FieldsNamed { named, .. }) => {
+            let field_names = named.iter().map(|f| f.ident.as_ref().unwrap().to_string());
+
+            quote! {
+                FieldsSchema::Named(vec![
+                    #(
+                        FieldSchema {
+                            name: #field_names,
+                            schema: #field_schemas,
+                        },
+                    )*
+                ])
+            }
+        }
+        syn::Fields::Unnamed(_) => quote! {
+            FieldsSchema::Unnamed(vec![ #( #field_schemas, )* ])
+        },
+        syn::Fields::Unit => quote!(FieldsSchema::Unit),
+    };
+
+    Ok(schema)
+}
+
+/// Creates the schema of the field type that is wrapped in `TypeSchema::Sensitive`
+/// if the field is marked with `#[rusty_value(sensitive)]`
+fn create_field_schema(field: &syn::Field) -> syn::Result<proc_macro2::TokenStream> {
+    let field_type = &field.ty;
+    let schema = quote!(<#field_type as RustyType>::rusty_type());
+
+    if FieldAttrs::from_attrs(&field.attrs)?.sensitive {
+        Ok(quote!(TypeSchema::Sensitive(Box::new(#schema))))
+    } else {
+        Ok(schema)
+    }
+}
+
 fn add_rusty_bound(generics: &Generics) -> WhereClause {
-    let trait_bound: proc_macro2::TokenStream = parse_quote!(rusty_value::RustyValue);
+    add_bound(generics, parse_quote!(rusty_value::RustyValue))
+}
 
+fn add_bound(generics: &Generics, trait_bound: proc_macro2::TokenStream) -> WhereClause {
     let new_predicates = generics.type_params().map::<WherePredicate, _>(|param| {
         let param = &param.ident;
         parse_quote!(#param : #trait_bound)
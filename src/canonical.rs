@@ -0,0 +1,437 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
+
+use crate::{Fields, Float, HashablePrimitive, HashableValue, Integer, Primitive, Value};
+
+/// A [Value] that is compared, ordered and hashed by its content so that it
+/// can be sorted, deduplicated or used as the key of a hash map.
+///
+/// The order is a total order that works like a derived one with a few differences:
+/// floats are compared with [f64::total_cmp] so `NaN` equals itself and `-0.0` is less than `0.0`,
+/// and named fields and map entries are compared in the order of their names and keys.
+/// Integers of different types are never equal.
+#[derive(Clone, Debug)]
+pub struct CanonicalValue(pub Value);
+
+impl From<Value> for CanonicalValue {
+    fn from(value: Value) -> Self {
+        Self(value)
+    }
+}
+
+impl PartialEq for CanonicalValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for CanonicalValue {}
+
+impl PartialOrd for CanonicalValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CanonicalValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.canonical_cmp(&other.0)
+    }
+}
+
+/// Only writes byte slices into the hasher so that the hash is the same on every platform
+/// if the hasher is deterministic
+impl Hash for CanonicalValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        write_value(&self.0, state)
+    }
+}
+
+impl Value {
+    /// Compares the value with another one using the total order of [CanonicalValue]
+    pub fn canonical_cmp(&self, other: &Value) -> Ordering {
+        match (self, other) {
+            (Value::Primitive(a), Value::Primitive(b)) => cmp_primitives(a, b),
+            (Value::Struct(a), Value::Struct(b)) => a
+                .name
+                .cmp(&b.name)
+                .then_with(|| cmp_fields(&a.fields, &b.fields)),
+            (Value::Enum(a), Value::Enum(b)) => a
+                .name
+                .cmp(&b.name)
+                .then_with(|| a.variant.cmp(&b.variant))
+                .then_with(|| cmp_fields(&a.fields, &b.fields)),
+            (Value::Map(a), Value::Map(b)) => {
+                cmp_slices(&sorted_entries(a), &sorted_entries(b), |a, b| {
+                    a.0.cmp(b.0).then_with(|| a.1.canonical_cmp(b.1))
+                })
+            }
+            (Value::List(a), Value::List(b)) => cmp_slices(a, b, Value::canonical_cmp),
+            (a, b) => value_rank(a).cmp(&value_rank(b)),
+        }
+    }
+
+    /// Returns a hash of the content of the value that is the same on every platform,
+    /// across program runs and across versions of this crate.
+    /// Values that are equal as [CanonicalValue]s have the same fingerprint.
+    ///
+    /// The fingerprint is a 64 bit FNV-1a hash which is fast but not cryptographically secure.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        write_value(self, &mut hasher);
+
+        hasher.finish()
+    }
+}
+
+fn value_rank(value: &Value) -> u8 {
+    match value {
+        Value::Primitive(_) => 0,
+        Value::Struct(_) => 1,
+        Value::Enum(_) => 2,
+        Value::Map(_) => 3,
+        Value::List(_) => 4,
+        Value::None => 5,
+    }
+}
+
+fn cmp_primitives(a: &Primitive, b: &Primitive) -> Ordering {
+    match (a, b) {
+        (Primitive::Integer(a), Primitive::Integer(b)) => a.cmp(b),
+        (Primitive::Float(Float::F32(a)), Primitive::Float(Float::F32(b))) => a.total_cmp(b),
+        (Primitive::Float(Float::F64(a)), Primitive::Float(Float::F64(b))) => a.total_cmp(b),
+        (Primitive::Float(a), Primitive::Float(b)) => float_rank(a).cmp(&float_rank(b)),
+        (Primitive::String(a), Primitive::String(b)) => a.cmp(b),
+        (Primitive::OsString(a), Primitive::OsString(b)) => a.cmp(b),
+        (Primitive::Char(a), Primitive::Char(b)) => a.cmp(b),
+        (Primitive::Bool(a), Primitive::Bool(b)) => a.cmp(b),
+        (a, b) => primitive_rank(a).cmp(&primitive_rank(b)),
+    }
+}
+
+fn primitive_rank(primitive: &Primitive) -> u8 {
+    match primitive {
+        Primitive::Integer(_) => 0,
+        Primitive::Float(_) => 1,
+        Primitive::String(_) => 2,
+        Primitive::OsString(_) => 3,
+        Primitive::Char(_) => 4,
+        Primitive::Bool(_) => 5,
+    }
+}
+
+fn float_rank(float: &Float) -> u8 {
+    match float {
+        Float::F32(_) => 0,
+        Float::F64(_) => 1,
+    }
+}
+
+fn cmp_fields(a: &Fields, b: &Fields) -> Ordering {
+    match (a, b) {
+        (Fields::Named(a), Fields::Named(b)) => {
+            cmp_slices(&sorted_entries(a), &sorted_entries(b), |a, b| {
+                a.0.cmp(b.0).then_with(|| a.1.canonical_cmp(b.1))
+            })
+        }
+        (Fields::Unnamed(a), Fields::Unnamed(b)) => cmp_slices(a, b, Value::canonical_cmp),
+        (a, b) => fields_rank(a).cmp(&fields_rank(b)),
+    }
+}
+
+fn fields_rank(fields: &Fields) -> u8 {
+    match fields {
+        Fields::Named(_) => 0,
+        Fields::Unnamed(_) => 1,
+        Fields::Unit => 2,
+    }
+}
+
+/// Compares the slices item by item like a derived order
+fn cmp_slices<T, F: Fn(&T, &T) -> Ordering>(a: &[T], b: &[T], cmp: F) -> Ordering {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| cmp(a, b))
+        .find(|o| o.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+fn sorted_entries<K: Ord, V>(map: &HashMap<K, V>) -> Vec<(&K, &V)> {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    entries
+}
+
+fn write_value<H: Hasher>(value: &Value, state: &mut H) {
+    state.write(&[value_rank(value)]);
+
+    match value {
+        Value::Primitive(p) => write_primitive(p, state),
+        Value::Struct(s) => {
+            write_bytes(s.name.as_bytes(), state);
+            write_fields(&s.fields, state);
+        }
+        Value::Enum(e) => {
+            write_bytes(e.name.as_bytes(), state);
+            write_bytes(e.variant.as_bytes(), state);
+            write_fields(&e.fields, state);
+        }
+        Value::Map(m) => {
+            write_len(m.len(), state);
+            for (key, value) in sorted_entries(m) {
+                write_hashable(key, state);
+                write_value(value, state);
+            }
+        }
+        Value::List(l) => {
+            write_len(l.len(), state);
+            for value in l {
+                write_value(value, state);
+            }
+        }
+        Value::None => {}
+    }
+}
+
+fn write_fields<H: Hasher>(fields: &Fields, state: &mut H) {
+    state.write(&[fields_rank(fields)]);
+
+    match fields {
+        Fields::Named(named) => {
+            write_len(named.len(), state);
+            for (name, value) in sorted_entries(named) {
+                write_bytes(name.as_bytes(), state);
+                write_value(value, state);
+            }
+        }
+        Fields::Unnamed(unnamed) => {
+            write_len(unnamed.len(), state);
+            for value in unnamed {
+                write_value(value, state);
+            }
+        }
+        Fields::Unit => {}
+    }
+}
+
+fn write_primitive<H: Hasher>(primitive: &Primitive, state: &mut H) {
+    state.write(&[primitive_rank(primitive)]);
+
+    match primitive {
+        Primitive::Integer(i) => write_integer(i, state),
+        Primitive::Float(f) => {
+            state.write(&[float_rank(f)]);
+            match f {
+                Float::F32(f) => state.write(&f.to_bits().to_le_bytes()),
+                Float::F64(f) => state.write(&f.to_bits().to_le_bytes()),
+            }
+        }
+        Primitive::String(s) => write_bytes(s.as_bytes(), state),
+        Primitive::OsString(o) => write_bytes(o.as_encoded_bytes(), state),
+        Primitive::Char(c) => state.write(&u32::from(*c).to_le_bytes()),
+        Primitive::Bool(b) => state.write(&[u8::from(*b)]),
+    }
+}
+
+fn write_hashable<H: Hasher>(value: &HashableValue, state: &mut H) {
+    match value {
+        HashableValue::Primitive(p) => {
+            state.write(&[0]);
+            match p {
+                HashablePrimitive::Integer(i) => {
+                    state.write(&[0]);
+                    write_integer(i, state);
+                }
+                HashablePrimitive::String(s) => {
+                    state.write(&[1]);
+                    write_bytes(s.as_bytes(), state);
+                }
+                HashablePrimitive::OsString(o) => {
+                    state.write(&[2]);
+                    write_bytes(o.as_encoded_bytes(), state);
+                }
+                HashablePrimitive::Char(c) => {
+                    state.write(&[3]);
+                    state.write(&u32::from(*c).to_le_bytes());
+                }
+                HashablePrimitive::Bool(b) => state.write(&[4, u8::from(*b)]),
+            }
+        }
+        HashableValue::List(l) => {
+            state.write(&[1]);
+            write_len(l.len(), state);
+            for value in l {
+                write_hashable(value, state);
+            }
+        }
+        HashableValue::None => state.write(&[2]),
+    }
+}
+
+/// Writes the type of the integer followed by its little endian bytes.
+/// Pointer sized integers are widened to 64 bits.
+fn write_integer<H: Hasher>(integer: &Integer, state: &mut H) {
+    match *integer {
+        Integer::USize(n) => {
+            state.write(&[0]);
+            state.write(&(n as u64).to_le_bytes());
+        }
+        Integer::ISize(n) => {
+            state.write(&[1]);
+            state.write(&(n as i64).to_le_bytes());
+        }
+        Integer::U8(n) => state.write(&[2, n]),
+        Integer::I8(n) => state.write(&[3, n as u8]),
+        Integer::U16(n) => {
+            state.write(&[4]);
+            state.write(&n.to_le_bytes());
+        }
+        Integer::I16(n) => {
+            state.write(&[5]);
+            state.write(&n.to_le_bytes());
+        }
+        Integer::U32(n) => {
+            state.write(&[6]);
+            state.write(&n.to_le_bytes());
+        }
+        Integer::I32(n) => {
+            state.write(&[7]);
+            state.write(&n.to_le_bytes());
+        }
+        Integer::U64(n) => {
+            state.write(&[8]);
+            state.write(&n.to_le_bytes());
+        }
+        Integer::I64(n) => {
+            state.write(&[9]);
+            state.write(&n.to_le_bytes());
+        }
+        Integer::U128(n) => {
+            state.write(&[10]);
+            state.write(&n.to_le_bytes());
+        }
+        Integer::I128(n) => {
+            state.write(&[11]);
+            state.write(&n.to_le_bytes());
+        }
+    }
+}
+
+/// Writes the length as 64 bits so that it doesn't depend on the size of `usize`
+fn write_len<H: Hasher>(len: usize, state: &mut H) {
+    state.write(&(len as u64).to_le_bytes());
+}
+
+fn write_bytes<H: Hasher>(bytes: &[u8], state: &mut H) {
+    write_len(bytes.len(), state);
+    state.write(bytes);
+}
+
+/// The 64 bit FNV-1a hash
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+
+    use crate::*;
+
+    #[test]
+    fn it_orders_values_totally() {
+        let mut values = vec![
+            rusty_value!(None),
+            rusty_value!([1u8, 2u8]),
+            rusty_value!(f64::NAN),
+            rusty_value!(0.0f64),
+            rusty_value!(-0.0f64),
+            rusty_value!("b"),
+            rusty_value!(2u8),
+            rusty_value!(struct A { b: 1u8 }),
+            rusty_value!([1u8]),
+        ]
+        .into_iter()
+        .map(CanonicalValue)
+        .collect::<Vec<_>>();
+        values.sort();
+
+        assert_eq!(
+            values.iter().map(|v| v.0.to_string()).collect::<Vec<_>>(),
+            vec![
+                "2u8",
+                "-0.0f64",
+                "0.0f64",
+                "NaNf64",
+                r#""b""#,
+                "A { b: 1u8 }",
+                "[1u8]",
+                "[1u8, 2u8]",
+                "None",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_deduplicates_values() {
+        let mut first = HashMap::new();
+        let mut second = HashMap::new();
+        for i in 0..32u8 {
+            first.insert(i.into_hashable_rusty_value(), rusty_value!(f32::NAN));
+            second.insert((31 - i).into_hashable_rusty_value(), rusty_value!(f32::NAN));
+        }
+
+        let set = [
+            Value::Map(first),
+            Value::Map(second),
+            rusty_value!(1u8),
+            rusty_value!(1u16),
+            rusty_value!(1u8),
+        ]
+        .into_iter()
+        .map(CanonicalValue)
+        .collect::<HashSet<_>>();
+
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn it_creates_stable_fingerprints() {
+        let value = rusty_value!(struct Server {
+            host: "localhost",
+            ports: [80u16, 443u16],
+            weights: { "a" => 0.5f64 },
+            mode: enum Mode::Fast,
+        });
+
+        assert_eq!(value.fingerprint(), value.clone().fingerprint());
+        assert_ne!(value.fingerprint(), rusty_value!(None).fingerprint());
+        assert_ne!(
+            rusty_value!(0.0f64).fingerprint(),
+            rusty_value!(-0.0f64).fingerprint()
+        );
+        // changing the fingerprint of a value invalidates caches that depend on it
+        assert_eq!(value.fingerprint(), 16813823717620667720);
+    }
+}
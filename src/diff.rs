@@ -0,0 +1,300 @@
+use std::{collections::BTreeSet, fmt::Display};
+
+use crate::{Fields, Path, PathSegment, Value};
+
+/// A single difference between two values
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// A field, list item or map entry only exists in the new value
+    Added { path: Path, value: Value },
+    /// A field, list item or map entry only exists in the old value
+    Removed { path: Path, value: Value },
+    /// A primitive changed or the value was replaced with a value of a different kind
+    Changed { path: Path, old: Value, new: Value },
+    /// The value was replaced with a struct or enum of a different type
+    TypeChanged { path: Path, old: Value, new: Value },
+    /// The enum changed to a different variant
+    VariantChanged { path: Path, old: Value, new: Value },
+}
+
+impl Change {
+    /// Returns the path of the value that changed
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. }
+            | Change::TypeChanged { path, .. }
+            | Change::VariantChanged { path, .. } => path,
+        }
+    }
+}
+
+/// Writes the change on a single line, prefixed with `+` for added,
+/// `-` for removed and `~` for changed values
+impl Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = self.path();
+        let path = if path.is_empty() {
+            String::from("$")
+        } else {
+            path.to_string()
+        };
+
+        match self {
+            Change::Added { value, .. } => write!(f, "+ {path}: {value}"),
+            Change::Removed { value, .. } => write!(f, "- {path}: {value}"),
+            Change::Changed { old, new, .. }
+            | Change::TypeChanged { old, new, .. }
+            | Change::VariantChanged { old, new, .. } => write!(f, "~ {path}: {old} -> {new}"),
+        }
+    }
+}
+
+/// Returns the changes that turn the old value into the new value.
+/// Changes are ordered by their paths with named fields and map keys sorted.
+/// Primitives are compared like [crate::CanonicalValue]s so an unchanged `NaN` is not a change.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_values(&mut Path::new(), old, new, &mut changes);
+
+    changes
+}
+
+/// Renders the changes in a human readable way with one change per line
+pub fn render_diff(changes: &[Change]) -> String {
+    changes.iter().map(|c| format!("{c}\n")).collect::<String>()
+}
+
+impl Value {
+    /// Returns the changes that turn this value into the other value
+    pub fn diff(&self, other: &Value) -> Vec<Change> {
+        diff(self, other)
+    }
+}
+
+fn diff_values(path: &mut Path, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Struct(o), Value::Struct(n)) if o.name != n.name => {
+            changes.push(Change::TypeChanged {
+                path: path.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            })
+        }
+        (Value::Struct(o), Value::Struct(n)) => {
+            diff_fields(path, &o.fields, &n.fields, old, new, changes)
+        }
+        (Value::Enum(o), Value::Enum(n)) if o.name != n.name => changes.push(Change::TypeChanged {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        (Value::Enum(o), Value::Enum(n)) if o.variant != n.variant => {
+            changes.push(Change::VariantChanged {
+                path: path.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            })
+        }
+        (Value::Enum(o), Value::Enum(n)) => {
+            diff_fields(path, &o.fields, &n.fields, old, new, changes)
+        }
+        (Value::Map(o), Value::Map(n)) => {
+            let keys = o.keys().chain(n.keys()).collect::<BTreeSet<_>>();
+
+            for key in keys {
+                path.push(PathSegment::Key(key.clone()));
+                diff_entries(path, o.get(key), n.get(key), changes);
+                path.pop();
+            }
+        }
+        (Value::List(o), Value::List(n)) => diff_lists(path, o, n, changes),
+        _ if old.canonical_cmp(new).is_eq() => {}
+        _ => changes.push(Change::Changed {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
+
+fn diff_fields(
+    path: &mut Path,
+    old_fields: &Fields,
+    new_fields: &Fields,
+    old: &Value,
+    new: &Value,
+    changes: &mut Vec<Change>,
+) {
+    match (old_fields, new_fields) {
+        (Fields::Named(o), Fields::Named(n)) => {
+            let names = o.keys().chain(n.keys()).collect::<BTreeSet<_>>();
+
+            for name in names {
+                path.push(PathSegment::Field(name.to_string()));
+                diff_entries(path, o.get(name), n.get(name), changes);
+                path.pop();
+            }
+        }
+        (Fields::Unnamed(o), Fields::Unnamed(n)) => diff_lists(path, o, n, changes),
+        (Fields::Unit, Fields::Unit) => {}
+        _ => changes.push(Change::Changed {
+            path: path.clone(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+    }
+}
+
+fn diff_lists(path: &mut Path, old: &[Value], new: &[Value], changes: &mut Vec<Change>) {
+    for i in 0..old.len().max(new.len()) {
+        path.push(PathSegment::Index(i));
+        diff_entries(path, old.get(i), new.get(i), changes);
+        path.pop();
+    }
+}
+
+fn diff_entries(
+    path: &mut Path,
+    old: Option<&Value>,
+    new: Option<&Value>,
+    changes: &mut Vec<Change>,
+) {
+    match (old, new) {
+        (Some(old), Some(new)) => diff_values(path, old, new, changes),
+        (Some(old), None) => changes.push(Change::Removed {
+            path: path.clone(),
+            value: old.clone(),
+        }),
+        (None, Some(new)) => changes.push(Change::Added {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+        (None, None) => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    fn config(port: u16, mode: Value, hosts: Value) -> Value {
+        let mut config = rusty_value!(struct Config {
+            port: port,
+            mode: None,
+            hosts: None,
+            limits: { "cpu" => 2u8 },
+        });
+        config.set(&"mode".parse().unwrap(), mode).unwrap();
+        config.set(&"hosts".parse().unwrap(), hosts).unwrap();
+
+        config
+    }
+
+    #[test]
+    fn it_finds_no_changes() {
+        let value = config(80, rusty_value!(enum Mode::Fast), rusty_value!(["a"]));
+        assert_eq!(diff(&value, &value.clone()), Vec::new());
+
+        let value = rusty_value!([f64::NAN, f32::NAN, { "cpu" => f64::NAN }]);
+        assert_eq!(diff(&value, &value.clone()), Vec::new());
+        assert_eq!(diff(&rusty_value!(0.0f64), &rusty_value!(0.0f32)).len(), 1);
+    }
+
+    #[test]
+    fn it_finds_changes() {
+        let old = config(
+            80,
+            rusty_value!(enum Mode::Slow { delay: 1u32 }),
+            rusty_value!(["a", "b"]),
+        );
+        let mut new = config(
+            8080,
+            rusty_value!(enum Mode::Slow { delay: 2u32 }),
+            rusty_value!(["a"]),
+        );
+        new.set(&"limits.memory".parse().unwrap(), rusty_value!(512u32))
+            .unwrap();
+
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                Change::Removed {
+                    path: "hosts[1]".parse().unwrap(),
+                    value: rusty_value!("b"),
+                },
+                Change::Added {
+                    path: Path::new().field("limits").key("memory"),
+                    value: rusty_value!(512u32),
+                },
+                Change::Changed {
+                    path: "mode.delay".parse().unwrap(),
+                    old: rusty_value!(1u32),
+                    new: rusty_value!(2u32),
+                },
+                Change::Changed {
+                    path: "port".parse().unwrap(),
+                    old: rusty_value!(80u16),
+                    new: rusty_value!(8080u16),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_finds_variant_and_type_changes() {
+        let old = config(80, rusty_value!(enum Mode::Fast), rusty_value!(None));
+        let new = config(
+            80,
+            rusty_value!(enum Mode::Slow { delay: 1u32 }),
+            rusty_value!(struct Hosts),
+        );
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                Change::Changed {
+                    path: "hosts".parse().unwrap(),
+                    old: Value::None,
+                    new: rusty_value!(struct Hosts),
+                },
+                Change::VariantChanged {
+                    path: "mode".parse().unwrap(),
+                    old: rusty_value!(enum Mode::Fast),
+                    new: rusty_value!(enum Mode::Slow { delay: 1u32 }),
+                },
+            ]
+        );
+        assert_eq!(
+            diff(&rusty_value!(struct A(1u8)), &rusty_value!(struct B(1u8))),
+            vec![Change::TypeChanged {
+                path: Path::new(),
+                old: rusty_value!(struct A(1u8)),
+                new: rusty_value!(struct B(1u8)),
+            }]
+        );
+    }
+
+    #[test]
+    fn it_renders_changes() {
+        let old = config(80, rusty_value!(enum Mode::Fast), rusty_value!(["a", "b"]));
+        let new = config(
+            81,
+            rusty_value!(enum Mode::Slow { delay: 1u32 }),
+            rusty_value!(["a"]),
+        );
+
+        assert_eq!(
+            render_diff(&diff(&old, &new)),
+            r#"- hosts[1]: "b"
+~ mode: Mode::Fast -> Mode::Slow { delay: 1u32 }
+~ port: 80u16 -> 81u16
+"#
+        );
+        assert_eq!(
+            diff(&rusty_value!(1u8), &rusty_value!(2u8))[0].to_string(),
+            "~ $: 1u8 -> 2u8"
+        );
+    }
+}
@@ -0,0 +1,764 @@
+//! A compact self-describing binary encoding for [crate::Value].
+//!
+//! Every value is prefixed with a tag byte that describes its kind so that
+//! integer and float widths, chars, os strings and the names of structs and enums
+//! survive a round trip. Lengths and integers wider than a byte are encoded as
+//! LEB128 varints, signed integers are zigzag encoded first.
+//!
+//! Os strings are prefixed with a byte that describes their representation.
+//! Strings that are valid unicode are written as UTF-8 so they can be decoded on
+//! every platform. Other strings are written as raw bytes on unix and as UTF-16
+//! code units on windows. Those can only be decoded on the same kind of platform.
+//! On any other platform they are written as UTF-8 with invalid parts replaced.
+use crate::*;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    fmt::Display,
+    io::{self, Read, Write},
+};
+
+const MAGIC: &[u8; 4] = b"RVAL";
+const VERSION: u8 = 1;
+
+/// The maximum nesting depth that is accepted when decoding
+const MAX_DEPTH: usize = 512;
+
+mod tag {
+    pub const NONE: u8 = 0x00;
+    pub const USIZE: u8 = 0x01;
+    pub const ISIZE: u8 = 0x02;
+    pub const U8: u8 = 0x03;
+    pub const I8: u8 = 0x04;
+    pub const U16: u8 = 0x05;
+    pub const I16: u8 = 0x06;
+    pub const U32: u8 = 0x07;
+    pub const I32: u8 = 0x08;
+    pub const U64: u8 = 0x09;
+    pub const I64: u8 = 0x0A;
+    pub const U128: u8 = 0x0B;
+    pub const I128: u8 = 0x0C;
+    pub const F32: u8 = 0x10;
+    pub const F64: u8 = 0x11;
+    pub const STRING: u8 = 0x12;
+    pub const OS_STRING: u8 = 0x13;
+    pub const CHAR: u8 = 0x14;
+    pub const FALSE: u8 = 0x15;
+    pub const TRUE: u8 = 0x16;
+    pub const STRUCT: u8 = 0x20;
+    pub const ENUM: u8 = 0x21;
+    pub const MAP: u8 = 0x22;
+    pub const LIST: u8 = 0x23;
+
+    pub const FIELDS_NAMED: u8 = 0x00;
+    pub const FIELDS_UNNAMED: u8 = 0x01;
+    pub const FIELDS_UNIT: u8 = 0x02;
+
+    pub const OS_UTF8: u8 = 0x00;
+    pub const OS_UNIX: u8 = 0x01;
+    pub const OS_WINDOWS: u8 = 0x02;
+}
+
+/// An error that occurred while decoding a value
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(io::Error),
+    /// The data doesn't start with the expected header or was written by an unsupported version
+    InvalidHeader,
+    /// The data contains a tag that doesn't describe any value
+    UnknownTag(u8),
+    /// A string doesn't contain valid UTF-8
+    InvalidUtf8,
+    /// An os string can't be represented on this platform
+    InvalidOsString,
+    /// The code point isn't a valid char
+    InvalidChar(u32),
+    /// A varint doesn't fit into the integer type it's decoded as
+    IntegerOverflow,
+    /// A map key contains a value that can't be used as a key
+    UnhashableKey(u8),
+    /// The value is nested deeper than the decoder allows
+    TooDeep,
+    /// There are bytes left after the value has been decoded
+    TrailingBytes,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Io(e) => e.fmt(f),
+            DecodeError::InvalidHeader => write!(f, "invalid header"),
+            DecodeError::UnknownTag(t) => write!(f, "unknown tag {t:#04x}"),
+            DecodeError::InvalidUtf8 => write!(f, "string is not valid utf-8"),
+            DecodeError::InvalidOsString => write!(f, "invalid os string"),
+            DecodeError::InvalidChar(c) => write!(f, "{c:#x} is not a valid char"),
+            DecodeError::IntegerOverflow => write!(f, "integer overflow"),
+            DecodeError::UnhashableKey(t) => write!(f, "tag {t:#04x} can't be used as a map key"),
+            DecodeError::TooDeep => write!(f, "value is nested too deeply"),
+            DecodeError::TrailingBytes => write!(f, "trailing bytes after value"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Encodes the value into a byte vector
+pub fn encode(value: &Value) -> Vec<u8> {
+    let mut buf = Vec::new();
+    to_writer(value, &mut buf).expect("writing to a vec cannot fail");
+
+    buf
+}
+
+/// Writes the encoded value into the given writer
+pub fn to_writer<W: Write>(value: &Value, mut writer: W) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+
+    Encoder { writer }.write_value(value)
+}
+
+/// Decodes a value from the given bytes.
+/// The bytes must contain exactly one encoded value.
+pub fn decode(mut bytes: &[u8]) -> Result<Value, DecodeError> {
+    let value = from_reader(&mut bytes)?;
+
+    if bytes.is_empty() {
+        Ok(value)
+    } else {
+        Err(DecodeError::TrailingBytes)
+    }
+}
+
+/// Reads an encoded value from the given reader
+pub fn from_reader<R: Read>(mut reader: R) -> Result<Value, DecodeError> {
+    let mut header = [0u8; 5];
+    reader.read_exact(&mut header)?;
+
+    if &header[..4] != MAGIC || header[4] != VERSION {
+        return Err(DecodeError::InvalidHeader);
+    }
+
+    Decoder { reader, depth: 0 }.read_value()
+}
+
+struct Encoder<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Encoder<W> {
+    fn write_value(&mut self, value: &Value) -> io::Result<()> {
+        match value {
+            Value::Primitive(p) => self.write_primitive(p),
+            Value::Struct(s) => {
+                self.write_tag(tag::STRUCT)?;
+                self.write_str(&s.name)?;
+                self.write_fields(&s.fields)
+            }
+            Value::Enum(e) => {
+                self.write_tag(tag::ENUM)?;
+                self.write_str(&e.name)?;
+                self.write_str(&e.variant)?;
+                self.write_fields(&e.fields)
+            }
+            Value::Map(m) => {
+                self.write_tag(tag::MAP)?;
+                self.write_varint(m.len() as u128)?;
+                for (key, value) in m {
+                    self.write_hashable(key)?;
+                    self.write_value(value)?;
+                }
+                Ok(())
+            }
+            Value::List(l) => {
+                self.write_tag(tag::LIST)?;
+                self.write_varint(l.len() as u128)?;
+                l.iter().try_for_each(|v| self.write_value(v))
+            }
+            Value::None => self.write_tag(tag::NONE),
+        }
+    }
+
+    fn write_fields(&mut self, fields: &Fields) -> io::Result<()> {
+        match fields {
+            Fields::Named(n) => {
+                self.write_tag(tag::FIELDS_NAMED)?;
+                self.write_varint(n.len() as u128)?;
+                for (name, value) in n {
+                    self.write_str(name)?;
+                    self.write_value(value)?;
+                }
+                Ok(())
+            }
+            Fields::Unnamed(u) => {
+                self.write_tag(tag::FIELDS_UNNAMED)?;
+                self.write_varint(u.len() as u128)?;
+                u.iter().try_for_each(|v| self.write_value(v))
+            }
+            Fields::Unit => self.write_tag(tag::FIELDS_UNIT),
+        }
+    }
+
+    fn write_hashable(&mut self, value: &HashableValue) -> io::Result<()> {
+        match value {
+            HashableValue::Primitive(p) => match p {
+                HashablePrimitive::Integer(i) => self.write_integer(i),
+                HashablePrimitive::String(s) => {
+                    self.write_tag(tag::STRING)?;
+                    self.write_str(s)
+                }
+                HashablePrimitive::OsString(o) => self.write_os_str(o),
+                HashablePrimitive::Char(c) => {
+                    self.write_tag(tag::CHAR)?;
+                    self.write_varint(*c as u128)
+                }
+                HashablePrimitive::Bool(b) => {
+                    self.write_tag(if *b { tag::TRUE } else { tag::FALSE })
+                }
+            },
+            HashableValue::List(l) => {
+                self.write_tag(tag::LIST)?;
+                self.write_varint(l.len() as u128)?;
+                l.iter().try_for_each(|v| self.write_hashable(v))
+            }
+            HashableValue::None => self.write_tag(tag::NONE),
+        }
+    }
+
+    fn write_primitive(&mut self, primitive: &Primitive) -> io::Result<()> {
+        match primitive {
+            Primitive::Integer(i) => self.write_integer(i),
+            Primitive::Float(Float::F32(f)) => {
+                self.write_tag(tag::F32)?;
+                self.writer.write_all(&f.to_bits().to_le_bytes())
+            }
+            Primitive::Float(Float::F64(f)) => {
+                self.write_tag(tag::F64)?;
+                self.writer.write_all(&f.to_bits().to_le_bytes())
+            }
+            Primitive::String(s) => {
+                self.write_tag(tag::STRING)?;
+                self.write_str(s)
+            }
+            Primitive::OsString(o) => self.write_os_str(o),
+            Primitive::Char(c) => {
+                self.write_tag(tag::CHAR)?;
+                self.write_varint(*c as u128)
+            }
+            Primitive::Bool(b) => self.write_tag(if *b { tag::TRUE } else { tag::FALSE }),
+        }
+    }
+
+    fn write_integer(&mut self, integer: &Integer) -> io::Result<()> {
+        match *integer {
+            Integer::USize(n) => self.write_tagged_varint(tag::USIZE, n as u128),
+            Integer::ISize(n) => self.write_tagged_varint(tag::ISIZE, zigzag(n as i128)),
+            Integer::U8(n) => self.writer.write_all(&[tag::U8, n]),
+            Integer::I8(n) => self.writer.write_all(&[tag::I8, n as u8]),
+            Integer::U16(n) => self.write_tagged_varint(tag::U16, n as u128),
+            Integer::I16(n) => self.write_tagged_varint(tag::I16, zigzag(n as i128)),
+            Integer::U32(n) => self.write_tagged_varint(tag::U32, n as u128),
+            Integer::I32(n) => self.write_tagged_varint(tag::I32, zigzag(n as i128)),
+            Integer::U64(n) => self.write_tagged_varint(tag::U64, n as u128),
+            Integer::I64(n) => self.write_tagged_varint(tag::I64, zigzag(n as i128)),
+            Integer::U128(n) => self.write_tagged_varint(tag::U128, n),
+            Integer::I128(n) => self.write_tagged_varint(tag::I128, zigzag(n)),
+        }
+    }
+
+    fn write_tagged_varint(&mut self, tag: u8, n: u128) -> io::Result<()> {
+        self.write_tag(tag)?;
+        self.write_varint(n)
+    }
+
+    fn write_tag(&mut self, tag: u8) -> io::Result<()> {
+        self.writer.write_all(&[tag])
+    }
+
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.write_bytes(s.as_bytes())
+    }
+
+    fn write_os_str(&mut self, s: &OsStr) -> io::Result<()> {
+        self.write_tag(tag::OS_STRING)?;
+
+        if let Some(s) = s.to_str() {
+            self.write_tag(tag::OS_UTF8)?;
+            return self.write_str(s);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+
+            self.write_tag(tag::OS_UNIX)?;
+            self.write_bytes(s.as_bytes())
+        }
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStrExt;
+
+            let units = s.encode_wide().collect::<Vec<_>>();
+            self.write_tag(tag::OS_WINDOWS)?;
+            self.write_varint(units.len() as u128)?;
+            units
+                .iter()
+                .try_for_each(|unit| self.writer.write_all(&unit.to_le_bytes()))
+        }
+        #[cfg(not(any(unix, windows)))]
+        {
+            self.write_tag(tag::OS_UTF8)?;
+            self.write_str(&s.to_string_lossy())
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_varint(bytes.len() as u128)?;
+        self.writer.write_all(bytes)
+    }
+
+    fn write_varint(&mut self, mut n: u128) -> io::Result<()> {
+        let mut buf = [0u8; 19];
+        let mut len = 0;
+
+        loop {
+            let byte = (n & 0x7F) as u8;
+            n >>= 7;
+            if n == 0 {
+                buf[len] = byte;
+                len += 1;
+                break;
+            }
+            buf[len] = byte | 0x80;
+            len += 1;
+        }
+
+        self.writer.write_all(&buf[..len])
+    }
+}
+
+struct Decoder<R: Read> {
+    reader: R,
+    depth: usize,
+}
+
+impl<R: Read> Decoder<R> {
+    fn read_value(&mut self) -> Result<Value, DecodeError> {
+        let tag = self.read_u8()?;
+        self.read_value_with_tag(tag)
+    }
+
+    fn read_value_with_tag(&mut self, tag: u8) -> Result<Value, DecodeError> {
+        let value = match tag {
+            tag::NONE => Value::None,
+            tag::F32 => {
+                let mut buf = [0u8; 4];
+                self.reader.read_exact(&mut buf)?;
+                Value::Primitive(Primitive::Float(Float::F32(f32::from_bits(
+                    u32::from_le_bytes(buf),
+                ))))
+            }
+            tag::F64 => {
+                let mut buf = [0u8; 8];
+                self.reader.read_exact(&mut buf)?;
+                Value::Primitive(Primitive::Float(Float::F64(f64::from_bits(
+                    u64::from_le_bytes(buf),
+                ))))
+            }
+            tag::STRUCT => {
+                self.enter()?;
+                let name = self.read_string()?;
+                let fields = self.read_fields()?;
+                self.depth -= 1;
+                Value::Struct(Struct {
+                    name: Cow::Owned(name),
+                    fields,
+                })
+            }
+            tag::ENUM => {
+                self.enter()?;
+                let name = self.read_string()?;
+                let variant = self.read_string()?;
+                let fields = self.read_fields()?;
+                self.depth -= 1;
+                Value::Enum(Enum {
+                    name: Cow::Owned(name),
+                    variant: Cow::Owned(variant),
+                    fields,
+                })
+            }
+            tag::MAP => {
+                self.enter()?;
+                let len = self.read_len()?;
+                let mut map = HashMap::with_capacity(len.min(1024));
+                for _ in 0..len {
+                    let key = self.read_hashable()?;
+                    let value = self.read_value()?;
+                    map.insert(key, value);
+                }
+                self.depth -= 1;
+                Value::Map(map)
+            }
+            tag::LIST => {
+                self.enter()?;
+                let len = self.read_len()?;
+                let mut list = Vec::with_capacity(len.min(1024));
+                for _ in 0..len {
+                    list.push(self.read_value()?);
+                }
+                self.depth -= 1;
+                Value::List(list)
+            }
+            tag => match self.read_hashable_primitive(tag)? {
+                HashablePrimitive::Integer(i) => Value::Primitive(Primitive::Integer(i)),
+                HashablePrimitive::String(s) => Value::Primitive(Primitive::String(s)),
+                HashablePrimitive::OsString(o) => Value::Primitive(Primitive::OsString(o)),
+                HashablePrimitive::Char(c) => Value::Primitive(Primitive::Char(c)),
+                HashablePrimitive::Bool(b) => Value::Primitive(Primitive::Bool(b)),
+            },
+        };
+
+        Ok(value)
+    }
+
+    fn read_fields(&mut self) -> Result<Fields, DecodeError> {
+        match self.read_u8()? {
+            tag::FIELDS_NAMED => {
+                let len = self.read_len()?;
+                let mut fields = HashMap::with_capacity(len.min(1024));
+                for _ in 0..len {
+                    let name = self.read_string()?;
+                    let value = self.read_value()?;
+                    fields.insert(Cow::Owned(name), value);
+                }
+                Ok(Fields::Named(fields))
+            }
+            tag::FIELDS_UNNAMED => {
+                let len = self.read_len()?;
+                let mut fields = Vec::with_capacity(len.min(1024));
+                for _ in 0..len {
+                    fields.push(self.read_value()?);
+                }
+                Ok(Fields::Unnamed(fields))
+            }
+            tag::FIELDS_UNIT => Ok(Fields::Unit),
+            tag => Err(DecodeError::UnknownTag(tag)),
+        }
+    }
+
+    fn read_hashable(&mut self) -> Result<HashableValue, DecodeError> {
+        match self.read_u8()? {
+            tag::NONE => Ok(HashableValue::None),
+            tag::LIST => {
+                self.enter()?;
+                let len = self.read_len()?;
+                let mut list = Vec::with_capacity(len.min(1024));
+                for _ in 0..len {
+                    list.push(self.read_hashable()?);
+                }
+                self.depth -= 1;
+                Ok(HashableValue::List(list))
+            }
+            tag @ (tag::F32 | tag::F64 | tag::STRUCT | tag::ENUM | tag::MAP) => {
+                Err(DecodeError::UnhashableKey(tag))
+            }
+            tag => Ok(HashableValue::Primitive(self.read_hashable_primitive(tag)?)),
+        }
+    }
+
+    fn read_hashable_primitive(&mut self, tag: u8) -> Result<HashablePrimitive, DecodeError> {
+        let integer = match tag {
+            tag::USIZE => Integer::USize(self.read_varint()?),
+            tag::ISIZE => Integer::ISize(self.read_zigzag()?),
+            tag::U8 => Integer::U8(self.read_u8()?),
+            tag::I8 => Integer::I8(self.read_u8()? as i8),
+            tag::U16 => Integer::U16(self.read_varint()?),
+            tag::I16 => Integer::I16(self.read_zigzag()?),
+            tag::U32 => Integer::U32(self.read_varint()?),
+            tag::I32 => Integer::I32(self.read_zigzag()?),
+            tag::U64 => Integer::U64(self.read_varint()?),
+            tag::I64 => Integer::I64(self.read_zigzag()?),
+            tag::U128 => Integer::U128(self.read_varint()?),
+            tag::I128 => Integer::I128(self.read_zigzag()?),
+            tag::STRING => return Ok(HashablePrimitive::String(self.read_string()?)),
+            tag::OS_STRING => return Ok(HashablePrimitive::OsString(self.read_os_string()?)),
+            tag::CHAR => {
+                let code: u32 = self.read_varint()?;
+                let c = char::from_u32(code).ok_or(DecodeError::InvalidChar(code))?;
+                return Ok(HashablePrimitive::Char(c));
+            }
+            tag::FALSE => return Ok(HashablePrimitive::Bool(false)),
+            tag::TRUE => return Ok(HashablePrimitive::Bool(true)),
+            tag => return Err(DecodeError::UnknownTag(tag)),
+        };
+
+        Ok(HashablePrimitive::Integer(integer))
+    }
+
+    fn enter(&mut self) -> Result<(), DecodeError> {
+        self.depth += 1;
+
+        if self.depth > MAX_DEPTH {
+            Err(DecodeError::TooDeep)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let mut buf = [0u8; 1];
+        self.reader.read_exact(&mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    fn read_len(&mut self) -> Result<usize, DecodeError> {
+        self.read_varint()
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>, DecodeError> {
+        let len = self.read_len()?;
+        let mut bytes = Vec::with_capacity(len.min(4096));
+        let read = (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+
+        if read < len {
+            Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+        } else {
+            Ok(bytes)
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, DecodeError> {
+        String::from_utf8(self.read_bytes()?).map_err(|_| DecodeError::InvalidUtf8)
+    }
+
+    fn read_os_string(&mut self) -> Result<OsString, DecodeError> {
+        match self.read_u8()? {
+            tag::OS_UTF8 => Ok(OsString::from(self.read_string()?)),
+            tag::OS_UNIX => {
+                let bytes = self.read_bytes()?;
+                #[cfg(unix)]
+                {
+                    use std::os::unix::ffi::OsStringExt;
+
+                    Ok(OsString::from_vec(bytes))
+                }
+                #[cfg(not(unix))]
+                {
+                    String::from_utf8(bytes)
+                        .map(OsString::from)
+                        .map_err(|_| DecodeError::InvalidOsString)
+                }
+            }
+            tag::OS_WINDOWS => {
+                let len = self.read_len()?;
+                let mut units = Vec::with_capacity(len.min(4096));
+                for _ in 0..len {
+                    let mut buf = [0u8; 2];
+                    self.reader.read_exact(&mut buf)?;
+                    units.push(u16::from_le_bytes(buf));
+                }
+                #[cfg(windows)]
+                {
+                    use std::os::windows::ffi::OsStringExt;
+
+                    Ok(OsString::from_wide(&units))
+                }
+                #[cfg(not(windows))]
+                {
+                    String::from_utf16(&units)
+                        .map(OsString::from)
+                        .map_err(|_| DecodeError::InvalidOsString)
+                }
+            }
+            tag => Err(DecodeError::UnknownTag(tag)),
+        }
+    }
+
+    fn read_varint<T: TryFrom<u128>>(&mut self) -> Result<T, DecodeError> {
+        let mut n = 0u128;
+        let mut shift = 0;
+
+        loop {
+            let byte = self.read_u8()?;
+            if shift >= 128 || (shift == 126 && byte > 0x03) {
+                return Err(DecodeError::IntegerOverflow);
+            }
+            n |= ((byte & 0x7F) as u128) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+
+        T::try_from(n).map_err(|_| DecodeError::IntegerOverflow)
+    }
+
+    fn read_zigzag<T: TryFrom<i128>>(&mut self) -> Result<T, DecodeError> {
+        let n: u128 = self.read_varint()?;
+        let n = (n >> 1) as i128 ^ -((n & 1) as i128);
+
+        T::try_from(n).map_err(|_| DecodeError::IntegerOverflow)
+    }
+}
+
+fn zigzag(n: i128) -> u128 {
+    ((n << 1) ^ (n >> 127)) as u128
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    use crate as rusty_value;
+    use crate::binary::{self, DecodeError};
+    use crate::*;
+
+    #[derive(RustyValue)]
+    struct TestStruct {
+        foo: String,
+        bar: Vec<TestEnum>,
+        path: PathBuf,
+        map: HashMap<String, Option<f32>>,
+        counts: Vec<i64>,
+    }
+
+    #[derive(RustyValue)]
+    enum TestEnum {
+        Foo,
+        Bar(f64, bool),
+        Baz { value: String },
+    }
+
+    fn roundtrip(value: Value) {
+        let bytes = binary::encode(&value);
+        assert_eq!(binary::decode(&bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn it_roundtrips_primitives() {
+        roundtrip(u8::MAX.into_rusty_value());
+        roundtrip(i8::MIN.into_rusty_value());
+        roundtrip(u16::MAX.into_rusty_value());
+        roundtrip(i16::MIN.into_rusty_value());
+        roundtrip(u32::MAX.into_rusty_value());
+        roundtrip(i32::MIN.into_rusty_value());
+        roundtrip(u64::MAX.into_rusty_value());
+        roundtrip(i64::MIN.into_rusty_value());
+        roundtrip(u128::MAX.into_rusty_value());
+        roundtrip(i128::MIN.into_rusty_value());
+        roundtrip(i128::MAX.into_rusty_value());
+        roundtrip(usize::MAX.into_rusty_value());
+        roundtrip(isize::MIN.into_rusty_value());
+        roundtrip(f32::MIN_POSITIVE.into_rusty_value());
+        roundtrip(f64::INFINITY.into_rusty_value());
+        roundtrip(Value::Primitive(Primitive::Char('ß')));
+        roundtrip(OsString::from("os string").into_rusty_value());
+        roundtrip(true.into_rusty_value());
+        roundtrip(().into_rusty_value());
+    }
+
+    #[test]
+    fn it_writes_os_strings_portably() {
+        let os_string = |s: &str| Value::Primitive(Primitive::OsString(OsString::from(s)));
+
+        assert_eq!(
+            binary::encode(&os_string("ab")),
+            b"RVAL\x01\x13\x00\x02ab".to_vec()
+        );
+        assert_eq!(
+            binary::decode(b"RVAL\x01\x13\x02\x02a\x00b\x00").unwrap(),
+            os_string("ab")
+        );
+        assert_eq!(
+            binary::decode(b"RVAL\x01\x13\x01\x02ab").unwrap(),
+            os_string("ab")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_roundtrips_non_unicode_os_strings() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let value = OsString::from_vec(vec![b'a', 0xFF]).into_rusty_value();
+        assert_eq!(
+            binary::encode(&value),
+            b"RVAL\x01\x13\x01\x02a\xFF".to_vec()
+        );
+        roundtrip(value);
+    }
+
+    #[test]
+    fn it_keeps_integer_widths() {
+        let value = binary::decode(&binary::encode(&1u16.into_rusty_value())).unwrap();
+
+        assert_eq!(value, Value::Primitive(Primitive::Integer(Integer::U16(1))));
+        assert_ne!(value, 1u64.into_rusty_value());
+    }
+
+    #[test]
+    fn it_roundtrips_nested_values() {
+        let make = || TestStruct {
+            foo: String::from("hello"),
+            bar: vec![
+                TestEnum::Foo,
+                TestEnum::Bar(-1.5, true),
+                TestEnum::Baz {
+                    value: String::from("world"),
+                },
+            ],
+            path: PathBuf::from("/tmp"),
+            map: HashMap::from([(String::from("a"), Some(0.5)), (String::new(), None)]),
+            counts: vec![i64::MIN, -1, 0, 1, i64::MAX],
+        };
+
+        roundtrip(make().into_rusty_value());
+        roundtrip(Value::Map(HashMap::from([
+            (
+                HashableValue::List(vec![
+                    1u8.into_hashable_rusty_value(),
+                    OsString::from("key").into_hashable_rusty_value(),
+                    HashableValue::Primitive(HashablePrimitive::Char('k')),
+                ]),
+                Value::List(vec![]),
+            ),
+            (HashableValue::None, Value::None),
+        ])));
+    }
+
+    #[test]
+    fn it_rejects_invalid_data() {
+        assert!(matches!(
+            binary::decode(b"JSON\x01\x00"),
+            Err(DecodeError::InvalidHeader)
+        ));
+        assert!(matches!(
+            binary::decode(b"RVAL\x01\xFF"),
+            Err(DecodeError::UnknownTag(0xFF))
+        ));
+        assert!(matches!(
+            binary::decode(b"RVAL\x01\x00\x00"),
+            Err(DecodeError::TrailingBytes)
+        ));
+        assert!(matches!(
+            binary::decode(b"RVAL\x01\x12\x05abc"),
+            Err(DecodeError::Io(_))
+        ));
+        assert!(matches!(
+            binary::decode(b"RVAL\x01\x22\x01\x10"),
+            Err(DecodeError::UnhashableKey(0x10))
+        ));
+    }
+}
@@ -0,0 +1,631 @@
+use super::packed::{decoded_tagged, emit_value, into_hashable, Emitter, TypeTag, MAX_DEPTH};
+use crate::json::EnumRepr;
+use crate::*;
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+pub use super::packed::DecodeError;
+
+/// The tag of a positive bignum, used for u128 values and positive i128 values
+pub const TAG_POSITIVE_BIGNUM: u64 = 2;
+/// The tag of a negative bignum, used for negative i128 values
+pub const TAG_NEGATIVE_BIGNUM: u64 = 3;
+/// An unassigned tag that wraps the [TAG_POSITIVE_BIGNUM] of a positive i128 value
+/// so that it isn't decoded as a u128. Decoders that don't know it read the bignum.
+pub const TAG_I128: u64 = 0x7269_3132;
+/// The tag of a language independent object with a type name and constructor arguments.
+/// It is used for enums that keep their names as `[name, variant, fields]`.
+pub const TAG_OBJECT: u64 = 27;
+/// An unassigned tag of structs that keep their names as `[name, fields]`
+pub const TAG_STRUCT: u64 = 0x7276_7374;
+/// An unassigned tag of chars which wraps a string with the char
+pub const TAG_CHAR: u64 = 0x7276_6368;
+/// An unassigned tag of os strings which wraps a string or a byte string
+/// with the raw bytes of an os string that isn't valid unicode
+pub const TAG_OS_STRING: u64 = 0x7276_6f73;
+
+const MAJOR_UNSIGNED: u8 = 0;
+const MAJOR_NEGATIVE: u8 = 1;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+
+/// Options for how to represent certain rust types
+/// as CBOR
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub struct CborOptions {
+    pub enum_repr: EnumRepr,
+    /// Encodes enums as a [TAG_OBJECT] containing the enum name, the variant name and the fields
+    /// instead of using the `enum_repr`, and structs, chars and os strings with the tags
+    /// [TAG_STRUCT], [TAG_CHAR] and [TAG_OS_STRING].
+    /// Values encoded this way are decoded back into the same kind of value.
+    pub tag_types: bool,
+}
+
+/// Encodes the value as CBOR
+pub fn encode(value: &Value, options: &CborOptions) -> Vec<u8> {
+    let mut buf = Vec::new();
+    to_writer(value, &mut buf, options).expect("writing to a vec cannot fail");
+
+    buf
+}
+
+/// Writes the value as CBOR into the given writer
+pub fn to_writer<W: Write>(value: &Value, writer: W, options: &CborOptions) -> io::Result<()> {
+    emit_value(
+        &mut CborEmitter { writer },
+        value,
+        &options.enum_repr,
+        options.tag_types,
+    )
+}
+
+/// Decodes a value from the given CBOR bytes.
+/// The bytes must contain exactly one value.
+pub fn decode(mut bytes: &[u8]) -> Result<Value, DecodeError> {
+    let value = from_reader(&mut bytes)?;
+
+    if bytes.is_empty() {
+        Ok(value)
+    } else {
+        Err(DecodeError::TrailingBytes)
+    }
+}
+
+/// Reads a CBOR value from the given reader.
+/// Unsigned integers are decoded as [crate::Integer::U64], negative integers as [crate::Integer::I64]
+/// or [crate::Integer::I128] if they don't fit. Bignums are decoded as [crate::Integer::U128]
+/// unless they are negative or wrapped in a [TAG_I128]. Tags that aren't known are ignored.
+pub fn from_reader<R: Read>(reader: R) -> Result<Value, DecodeError> {
+    CborDecoder { reader, depth: 0 }.read_value()
+}
+
+struct CborEmitter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> CborEmitter<W> {
+    fn write_head(&mut self, major: u8, n: u64) -> io::Result<()> {
+        let major = major << 5;
+
+        if n < 24 {
+            self.writer.write_all(&[major | n as u8])
+        } else if n <= u8::MAX as u64 {
+            self.writer.write_all(&[major | 24, n as u8])
+        } else if n <= u16::MAX as u64 {
+            self.writer.write_all(&[major | 25])?;
+            self.writer.write_all(&(n as u16).to_be_bytes())
+        } else if n <= u32::MAX as u64 {
+            self.writer.write_all(&[major | 26])?;
+            self.writer.write_all(&(n as u32).to_be_bytes())
+        } else {
+            self.writer.write_all(&[major | 27])?;
+            self.writer.write_all(&n.to_be_bytes())
+        }
+    }
+
+    fn write_bignum(&mut self, tag: u64, n: u128) -> io::Result<()> {
+        let bytes = n.to_be_bytes();
+        let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+
+        self.write_head(MAJOR_TAG, tag)?;
+        self.write_head(MAJOR_BYTES, (bytes.len() - start) as u64)?;
+        self.writer.write_all(&bytes[start..])
+    }
+}
+
+impl<W: Write> Emitter for CborEmitter<W> {
+    fn nil(&mut self) -> io::Result<()> {
+        self.writer.write_all(&[0xf6])
+    }
+
+    fn bool(&mut self, b: bool) -> io::Result<()> {
+        self.writer.write_all(&[if b { 0xf5 } else { 0xf4 }])
+    }
+
+    fn uint(&mut self, n: u64) -> io::Result<()> {
+        self.write_head(MAJOR_UNSIGNED, n)
+    }
+
+    fn int(&mut self, n: i64) -> io::Result<()> {
+        if n >= 0 {
+            self.write_head(MAJOR_UNSIGNED, n as u64)
+        } else {
+            self.write_head(MAJOR_NEGATIVE, !n as u64)
+        }
+    }
+
+    fn u128(&mut self, n: u128) -> io::Result<()> {
+        self.write_bignum(TAG_POSITIVE_BIGNUM, n)
+    }
+
+    fn i128(&mut self, n: i128) -> io::Result<()> {
+        if n >= 0 {
+            self.write_head(MAJOR_TAG, TAG_I128)?;
+            self.write_bignum(TAG_POSITIVE_BIGNUM, n as u128)
+        } else {
+            self.write_bignum(TAG_NEGATIVE_BIGNUM, !n as u128)
+        }
+    }
+
+    fn f32(&mut self, f: f32) -> io::Result<()> {
+        self.writer.write_all(&[0xfa])?;
+        self.writer.write_all(&f.to_be_bytes())
+    }
+
+    fn f64(&mut self, f: f64) -> io::Result<()> {
+        self.writer.write_all(&[0xfb])?;
+        self.writer.write_all(&f.to_be_bytes())
+    }
+
+    fn str(&mut self, s: &str) -> io::Result<()> {
+        self.write_head(MAJOR_TEXT, s.len() as u64)?;
+        self.writer.write_all(s.as_bytes())
+    }
+
+    fn array(&mut self, len: usize) -> io::Result<()> {
+        self.write_head(MAJOR_ARRAY, len as u64)
+    }
+
+    fn map(&mut self, len: usize) -> io::Result<()> {
+        self.write_head(MAJOR_MAP, len as u64)
+    }
+
+    fn bytes(&mut self, b: &[u8]) -> io::Result<()> {
+        self.write_head(MAJOR_BYTES, b.len() as u64)?;
+        self.writer.write_all(b)
+    }
+
+    fn begin_tag(&mut self, tag: TypeTag) -> io::Result<()> {
+        let tag = match tag {
+            TypeTag::Struct => TAG_STRUCT,
+            TypeTag::Enum => TAG_OBJECT,
+            TypeTag::Char => TAG_CHAR,
+            TypeTag::OsString => TAG_OS_STRING,
+        };
+        self.write_head(MAJOR_TAG, tag)
+    }
+
+    fn end_tag(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The break marker that ends items of indefinite length
+const BREAK: u8 = 0xff;
+
+struct CborDecoder<R: Read> {
+    reader: R,
+    depth: usize,
+}
+
+impl<R: Read> CborDecoder<R> {
+    fn read_value(&mut self) -> Result<Value, DecodeError> {
+        let initial = self.read_u8()?;
+        self.read_value_with_initial(initial)
+    }
+
+    fn read_value_with_initial(&mut self, initial: u8) -> Result<Value, DecodeError> {
+        let major = initial >> 5;
+        let info = initial & 0x1f;
+
+        let value = match major {
+            MAJOR_UNSIGNED => {
+                Value::Primitive(Primitive::Integer(Integer::U64(self.read_argument(info)?)))
+            }
+            MAJOR_NEGATIVE => {
+                let n = -1 - self.read_argument(info)? as i128;
+                let integer = i64::try_from(n)
+                    .map(Integer::I64)
+                    .unwrap_or(Integer::I128(n));
+                Value::Primitive(Primitive::Integer(integer))
+            }
+            MAJOR_BYTES => {
+                let bytes = self.read_chunks(MAJOR_BYTES, info)?;
+                Value::List(bytes.into_iter().map(|b| b.into_rusty_value()).collect())
+            }
+            MAJOR_TEXT => {
+                let bytes = self.read_chunks(MAJOR_TEXT, info)?;
+                let s = String::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)?;
+                Value::Primitive(Primitive::String(s))
+            }
+            MAJOR_ARRAY => {
+                self.enter()?;
+                let mut list = Vec::new();
+                match self.read_length(info)? {
+                    Some(len) => {
+                        list.reserve(len.min(1024));
+                        for _ in 0..len {
+                            list.push(self.read_value()?);
+                        }
+                    }
+                    None => loop {
+                        match self.read_u8()? {
+                            BREAK => break,
+                            initial => list.push(self.read_value_with_initial(initial)?),
+                        }
+                    },
+                }
+                self.depth -= 1;
+                Value::List(list)
+            }
+            MAJOR_MAP => {
+                self.enter()?;
+                let mut map = HashMap::new();
+                match self.read_length(info)? {
+                    Some(len) => {
+                        map.reserve(len.min(1024));
+                        for _ in 0..len {
+                            let key = into_hashable(self.read_value()?)?;
+                            map.insert(key, self.read_value()?);
+                        }
+                    }
+                    None => loop {
+                        match self.read_u8()? {
+                            BREAK => break,
+                            initial => {
+                                let key = into_hashable(self.read_value_with_initial(initial)?)?;
+                                map.insert(key, self.read_value()?);
+                            }
+                        }
+                    },
+                }
+                self.depth -= 1;
+                Value::Map(map)
+            }
+            MAJOR_TAG => {
+                let tag = self.read_argument(info)?;
+                self.enter()?;
+                let value = self.read_tagged(tag)?;
+                self.depth -= 1;
+                value
+            }
+            // simple values and floats
+            _ => match info {
+                20 => Value::Primitive(Primitive::Bool(false)),
+                21 => Value::Primitive(Primitive::Bool(true)),
+                22 | 23 => Value::None,
+                25 => {
+                    let bits = self.read_uint(2)? as u16;
+                    Value::Primitive(Primitive::Float(Float::F32(f16_to_f32(bits))))
+                }
+                26 => Value::Primitive(Primitive::Float(Float::F32(f32::from_bits(
+                    self.read_uint(4)? as u32,
+                )))),
+                27 => Value::Primitive(Primitive::Float(Float::F64(f64::from_bits(
+                    self.read_uint(8)?,
+                )))),
+                _ => return Err(DecodeError::UnknownMarker(initial)),
+            },
+        };
+
+        Ok(value)
+    }
+
+    fn read_tagged(&mut self, tag: u64) -> Result<Value, DecodeError> {
+        let value = match tag {
+            TAG_POSITIVE_BIGNUM | TAG_NEGATIVE_BIGNUM => {
+                let bytes = match self.read_value()? {
+                    Value::List(bytes) => bytes,
+                    _ => return Err(DecodeError::UnknownExtension(tag as i64)),
+                };
+                if bytes.len() > 16 {
+                    return Err(DecodeError::IntegerOverflow);
+                }
+                let mut n = 0u128;
+                for byte in bytes {
+                    let Value::Primitive(Primitive::Integer(Integer::U8(byte))) = byte else {
+                        return Err(DecodeError::UnknownExtension(tag as i64));
+                    };
+                    n = n << 8 | byte as u128;
+                }
+                let integer = if tag == TAG_POSITIVE_BIGNUM {
+                    Integer::U128(n)
+                } else {
+                    let n = i128::try_from(n).map_err(|_| DecodeError::IntegerOverflow)?;
+                    Integer::I128(-1 - n)
+                };
+                Value::Primitive(Primitive::Integer(integer))
+            }
+            TAG_I128 => {
+                let integer = match self.read_value()? {
+                    Value::Primitive(Primitive::Integer(Integer::U128(n))) => {
+                        i128::try_from(n).map_err(|_| DecodeError::IntegerOverflow)?
+                    }
+                    Value::Primitive(Primitive::Integer(Integer::I128(n))) => n,
+                    _ => return Err(DecodeError::UnknownExtension(tag as i64)),
+                };
+                Value::Primitive(Primitive::Integer(Integer::I128(integer)))
+            }
+            TAG_OBJECT => decoded_tagged(TypeTag::Enum, self.read_value()?)?,
+            TAG_STRUCT => decoded_tagged(TypeTag::Struct, self.read_value()?)?,
+            TAG_CHAR => decoded_tagged(TypeTag::Char, self.read_value()?)?,
+            TAG_OS_STRING => decoded_tagged(TypeTag::OsString, self.read_value()?)?,
+            _ => self.read_value()?,
+        };
+
+        Ok(value)
+    }
+
+    /// Reads the content of a byte or text string which might be split into chunks
+    fn read_chunks(&mut self, major: u8, info: u8) -> Result<Vec<u8>, DecodeError> {
+        match self.read_length(info)? {
+            Some(len) => self.read_bytes(len),
+            None => {
+                let mut bytes = Vec::new();
+                loop {
+                    let initial = self.read_u8()?;
+                    if initial == BREAK {
+                        break;
+                    }
+                    if initial >> 5 != major {
+                        return Err(DecodeError::UnknownMarker(initial));
+                    }
+                    let len = self
+                        .read_length(initial & 0x1f)?
+                        .ok_or(DecodeError::UnknownMarker(initial))?;
+                    bytes.append(&mut self.read_bytes(len)?);
+                }
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Reads the length of an item. Returns `None` for items of indefinite length
+    fn read_length(&mut self, info: u8) -> Result<Option<usize>, DecodeError> {
+        if info == 31 {
+            return Ok(None);
+        }
+        let len = self.read_argument(info)?;
+
+        usize::try_from(len)
+            .map(Some)
+            .map_err(|_| DecodeError::IntegerOverflow)
+    }
+
+    fn read_argument(&mut self, info: u8) -> Result<u64, DecodeError> {
+        match info {
+            0..=23 => Ok(info as u64),
+            24 => self.read_uint(1),
+            25 => self.read_uint(2),
+            26 => self.read_uint(4),
+            27 => self.read_uint(8),
+            info => Err(DecodeError::UnknownMarker(info)),
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), DecodeError> {
+        self.depth += 1;
+
+        if self.depth > MAX_DEPTH {
+            Err(DecodeError::TooDeep)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let mut buf = [0u8; 1];
+        self.reader.read_exact(&mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    /// Reads a big endian unsigned integer of the given size
+    fn read_uint(&mut self, size: usize) -> Result<u64, DecodeError> {
+        let mut buf = [0u8; 8];
+        self.reader.read_exact(&mut buf[8 - size..])?;
+
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>, DecodeError> {
+        let mut bytes = Vec::with_capacity(len.min(4096));
+        let read = (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+
+        if read < len {
+            Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+        } else {
+            Ok(bytes)
+        }
+    }
+}
+
+/// Converts the bits of a half precision float into an f32
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        31 if mantissa == 0.0 => f32::INFINITY,
+        31 => f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::cbor::{self, CborOptions, DecodeError};
+    use crate::json::EnumRepr;
+    use crate::*;
+
+    #[derive(RustyValue)]
+    enum TestEnum {
+        Foo,
+        Bar(u64, String),
+        Baz { value: i64 },
+    }
+
+    fn roundtrip(value: Value, options: &CborOptions) -> Value {
+        cbor::decode(&cbor::encode(&value, options)).unwrap()
+    }
+
+    #[test]
+    fn it_encodes_primitives() {
+        let opt = CborOptions::default();
+
+        assert_eq!(cbor::encode(&10u8.into_rusty_value(), &opt), [0x0a]);
+        assert_eq!(
+            cbor::encode(&500u16.into_rusty_value(), &opt),
+            [0x19, 0x01, 0xf4]
+        );
+        assert_eq!(
+            cbor::encode(&(-100i32).into_rusty_value(), &opt),
+            [0x38, 0x63]
+        );
+        assert_eq!(cbor::encode(&"a".into_rusty_value(), &opt), [0x61, b'a']);
+        assert_eq!(cbor::encode(&false.into_rusty_value(), &opt), [0xf4]);
+        assert_eq!(cbor::encode(&().into_rusty_value(), &opt), [0xf6]);
+        assert_eq!(
+            cbor::encode(&(u64::MAX as u128 + 1).into_rusty_value(), &opt),
+            [0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn it_roundtrips_values() {
+        let opt = CborOptions::default();
+
+        assert_eq!(
+            roundtrip(u64::MAX.into_rusty_value(), &opt),
+            u64::MAX.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(i64::MIN.into_rusty_value(), &opt),
+            i64::MIN.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(u128::MAX.into_rusty_value(), &opt),
+            u128::MAX.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(i128::MIN.into_rusty_value(), &opt),
+            i128::MIN.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(i128::MAX.into_rusty_value(), &opt),
+            i128::MAX.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(1i128.into_rusty_value(), &opt),
+            1i128.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(0.25f32.into_rusty_value(), &opt),
+            0.25f32.into_rusty_value()
+        );
+
+        let map = HashMap::from([(1u64, vec![String::from("a")]), (2u64, vec![])]);
+        assert_eq!(
+            roundtrip(map.clone().into_rusty_value(), &opt),
+            map.into_rusty_value()
+        );
+    }
+
+    #[test]
+    fn it_uses_the_enum_repr() {
+        let opt = CborOptions {
+            enum_repr: EnumRepr::Untagged,
+            ..Default::default()
+        };
+        let value = roundtrip(TestEnum::Bar(1, String::from("a")).into_rusty_value(), &opt);
+
+        assert_eq!(
+            value,
+            Value::List(vec![1u64.into_rusty_value(), "a".into_rusty_value()])
+        );
+    }
+
+    #[test]
+    fn it_roundtrips_tagged_types() {
+        let opt = CborOptions {
+            tag_types: true,
+            ..Default::default()
+        };
+
+        for value in [
+            TestEnum::Foo,
+            TestEnum::Bar(1, String::from("a")),
+            TestEnum::Baz { value: i64::MIN },
+        ] {
+            let value = value.into_rusty_value();
+            assert_eq!(roundtrip(value.clone(), &opt), value);
+        }
+        let char = |c| Value::Primitive(Primitive::Char(c));
+        let char_key = HashableValue::Primitive(HashablePrimitive::Char('k'));
+        for value in [
+            rusty_value!(struct Point { x: 1u64, y: None }),
+            rusty_value!(struct Wrapper(-1i64)),
+            rusty_value!(struct Marker),
+            rusty_value!([enum TestEnum::Foo, "a", None]),
+            Value::Map(HashMap::from([(char_key, rusty_value!(struct Marker))])),
+            Value::Primitive(Primitive::OsString("path".into())),
+            char('ü'),
+        ] {
+            assert_eq!(roundtrip(value.clone(), &opt), value);
+        }
+        assert_eq!(
+            roundtrip(char('c'), &Default::default()),
+            "c".into_rusty_value()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_roundtrips_non_unicode_os_strings() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let opt = CborOptions {
+            tag_types: true,
+            ..Default::default()
+        };
+        let value = std::ffi::OsString::from_vec(vec![b'a', 0xff]).into_rusty_value();
+        assert_eq!(roundtrip(value.clone(), &opt), value);
+    }
+
+    #[test]
+    fn it_decodes_indefinite_lengths_and_half_floats() {
+        let value = cbor::decode(&[
+            0x9f, 0x7f, 0x61, b'a', 0x61, b'b', 0xff, 0xf9, 0x3c, 0x00, 0xff,
+        ])
+        .unwrap();
+
+        assert_eq!(
+            value,
+            Value::List(vec!["ab".into_rusty_value(), 1.0f32.into_rusty_value()])
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_data() {
+        assert!(matches!(
+            cbor::decode(&[0x1c]),
+            Err(DecodeError::UnknownMarker(28))
+        ));
+        assert!(matches!(
+            cbor::decode(&[0x62, b'a']),
+            Err(DecodeError::Io(_))
+        ));
+        assert!(matches!(
+            cbor::decode(&[0xf6, 0xf6]),
+            Err(DecodeError::TrailingBytes)
+        ));
+        assert!(matches!(
+            cbor::decode(&[0xa1, 0xf9, 0x3c, 0x00, 0xf6]),
+            Err(DecodeError::UnhashableKey)
+        ));
+    }
+}
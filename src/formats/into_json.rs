@@ -1,34 +1,13 @@
+use super::json::hashable_to_string;
 use crate::*;
 use serde_json::Number;
-use std::string::ToString;
+use std::{borrow::Cow, collections::HashMap};
 
-/// Options for how to represent certain rust types
-/// as JSON
-#[derive(Clone, Debug, RustyValue, Default)]
-pub struct IntoJsonOptions {
-    pub enum_repr: EnumRepr,
-}
-
-/// Controls how enums should be represented
-/// This works similarly to serde except that internal tagging isn't supported
-/// except that internal tagging isn't supported
-#[derive(Clone, Debug, RustyValue)]
-pub enum EnumRepr {
-    Untagged,
-    ExternallyTagged,
-    AdjacentlyTagged {
-        type_field: String,
-        value_field: String,
-    },
-}
-
-impl Default for EnumRepr {
-    fn default() -> Self {
-        Self::ExternallyTagged
-    }
-}
+pub use super::json::{EnumRepr, IntoJsonOptions, UnitStructRepr};
 
-/// Trait to convert a value into a json value
+/// Trait to convert a value into a json value.
+/// Map keys that are represented by the same string, e.g. `1u8` and `"1"`, replace
+/// each other. Use [crate::json::to_writer] to get an error in that case instead.
 pub trait IntoJson {
     /// Converts the value into a json value with default options
     fn into_json(self) -> serde_json::Value;
@@ -36,34 +15,26 @@ pub trait IntoJson {
     fn into_json_with_options(self, options: &IntoJsonOptions) -> serde_json::Value;
 }
 
+/// Converts values that were already redacted
 trait RustyIntoJson {
-    /// Converts the value into a json value with default options
-    fn into_json(self) -> serde_json::Value;
-    /// Converts the value into a json value with the given options
-    fn into_json_with_options(self, options: &IntoJsonOptions) -> serde_json::Value;
+    /// Converts the value into a json value with the given options without redacting it
+    fn into_json_value(self, options: &IntoJsonOptions) -> serde_json::Value;
 }
 
 impl RustyIntoJson for crate::Value {
-    #[inline]
-    fn into_json(self) -> serde_json::Value {
-        self.into_json_with_options(&IntoJsonOptions::default())
-    }
-
-    fn into_json_with_options(self, opt: &IntoJsonOptions) -> serde_json::Value {
+    fn into_json_value(self, opt: &IntoJsonOptions) -> serde_json::Value {
         match self {
-            crate::Value::Primitive(p) => p.into_json_with_options(opt),
-            crate::Value::Struct(s) => s.into_json_with_options(opt),
-            crate::Value::Enum(e) => e.into_json_with_options(opt),
+            crate::Value::Primitive(p) => p.into_json(),
+            crate::Value::Struct(s) => s.into_json_value(opt),
+            crate::Value::Enum(e) => e.into_json_value(opt),
             crate::Value::Map(m) => serde_json::Value::Object(
                 m.into_iter()
-                    .map(|(k, v)| (hashable_to_string(k), v.into_json_with_options(opt)))
-                    .collect(),
-            ),
-            crate::Value::List(l) => serde_json::Value::Array(
-                l.into_iter()
-                    .map(|v| v.into_json_with_options(opt))
+                    .map(|(k, v)| (hashable_to_string(&k), v.into_json_value(opt)))
                     .collect(),
             ),
+            crate::Value::List(l) => {
+                serde_json::Value::Array(l.into_iter().map(|v| v.into_json_value(opt)).collect())
+            }
             crate::Value::None => serde_json::Value::Null,
         }
     }
@@ -118,31 +89,33 @@ impl IntoJson for Enum {
     fn into_json(self) -> serde_json::Value {
         self.into_json_with_options(&IntoJsonOptions::default())
     }
+
+    #[inline]
     fn into_json_with_options(self, opt: &IntoJsonOptions) -> serde_json::Value {
+        redacted_into_json(crate::Value::Enum(self), opt)
+    }
+}
+
+impl RustyIntoJson for Enum {
+    fn into_json_value(self, opt: &IntoJsonOptions) -> serde_json::Value {
         let value = match self.fields {
-            crate::Fields::Named(n) => serde_json::Value::Object(
-                n.into_iter()
-                    .map(|(k, v)| (k, v.into_json_with_options(opt)))
-                    .collect(),
-            ),
+            crate::Fields::Named(n) => serde_json::Value::Object(named_fields_into_json(n, opt)),
             crate::Fields::Unnamed(mut u) => {
                 if u.len() == 1 {
-                    u.remove(0).into_json_with_options(opt)
+                    u.remove(0).into_json_value(opt)
                 } else {
                     serde_json::Value::Array(
-                        u.into_iter()
-                            .map(|v| v.into_json_with_options(opt))
-                            .collect(),
+                        u.into_iter().map(|v| v.into_json_value(opt)).collect(),
                     )
                 }
             }
-            crate::Fields::Unit => serde_json::Value::String(self.variant.clone()),
+            crate::Fields::Unit => serde_json::Value::String(self.variant.to_string()),
         };
         match &opt.enum_repr {
             EnumRepr::Untagged => value,
-            EnumRepr::ExternallyTagged => {
-                serde_json::Value::Object([(self.variant, value)].into_iter().collect())
-            }
+            EnumRepr::ExternallyTagged => serde_json::Value::Object(
+                [(self.variant.into_owned(), value)].into_iter().collect(),
+            ),
             EnumRepr::AdjacentlyTagged {
                 type_field,
                 value_field,
@@ -150,7 +123,7 @@ impl IntoJson for Enum {
                 [
                     (
                         type_field.to_owned(),
-                        serde_json::Value::String(self.variant),
+                        serde_json::Value::String(self.variant.into_owned()),
                     ),
                     (value_field.to_owned(), value),
                 ]
@@ -166,26 +139,40 @@ impl IntoJson for Struct {
     fn into_json(self) -> serde_json::Value {
         self.into_json_with_options(&IntoJsonOptions::default())
     }
+
+    #[inline]
     fn into_json_with_options(self, opt: &IntoJsonOptions) -> serde_json::Value {
-        match self.fields {
-            crate::Fields::Named(n) => serde_json::Value::Object(
-                n.into_iter()
-                    .map(|(k, v)| (k, v.into_json_with_options(opt)))
-                    .collect(),
-            ),
+        redacted_into_json(crate::Value::Struct(self), opt)
+    }
+}
+
+impl RustyIntoJson for Struct {
+    fn into_json_value(self, opt: &IntoJsonOptions) -> serde_json::Value {
+        let mut object = match self.fields {
+            crate::Fields::Named(n) => named_fields_into_json(n, opt),
             crate::Fields::Unnamed(mut u) => {
-                if u.len() == 1 {
-                    u.remove(0).into_json_with_options(opt)
+                return if u.len() == 1 && !opt.wrap_newtypes {
+                    u.remove(0).into_json_value(opt)
                 } else {
                     serde_json::Value::Array(
-                        u.into_iter()
-                            .map(|v| v.into_json_with_options(opt))
-                            .collect(),
+                        u.into_iter().map(|v| v.into_json_value(opt)).collect(),
                     )
-                }
+                };
             }
-            crate::Fields::Unit => serde_json::Value::String(self.name),
+            crate::Fields::Unit => match opt.unit_struct_repr {
+                UnitStructRepr::Name => return serde_json::Value::String(self.name.into_owned()),
+                UnitStructRepr::Null => return serde_json::Value::Null,
+                UnitStructRepr::EmptyObject => serde_json::Map::new(),
+            },
+        };
+        // a field of the struct with the same name is kept
+        if let Some(type_field) = &opt.struct_type_field {
+            object
+                .entry(type_field.to_owned())
+                .or_insert_with(|| serde_json::Value::String(self.name.into_owned()));
         }
+
+        serde_json::Value::Object(object)
     }
 }
 
@@ -197,23 +184,30 @@ impl<R: RustyValue> IntoJson for R {
 
     #[inline]
     fn into_json_with_options(self, opt: &IntoJsonOptions) -> serde_json::Value {
-        self.into_rusty_value().into_json_with_options(opt)
+        redacted_into_json(self.into_rusty_value(), opt)
     }
 }
 
-fn hashable_to_string(hashable: HashableValue) -> String {
-    match hashable {
-        HashableValue::Primitive(p) => p.to_string(),
-        HashableValue::List(l) => l
-            .into_iter()
-            .map(hashable_to_string)
-            .collect::<Vec<_>>()
-            .join(","),
-        HashableValue::None => String::new(),
+/// Redacts the value if it's enabled in the options and converts it
+fn redacted_into_json(mut value: crate::Value, opt: &IntoJsonOptions) -> serde_json::Value {
+    if let Some(redact) = &opt.redact {
+        value.redact(redact);
     }
+    value.into_json_value(opt)
 }
 
-#[cfg(test)]
+fn named_fields_into_json(
+    fields: HashMap<Cow<'static, str>, crate::Value>,
+    opt: &IntoJsonOptions,
+) -> serde_json::Map<String, serde_json::Value> {
+    fields
+        .into_iter()
+        .filter(|(_, v)| !(opt.skip_none_fields && matches!(v, crate::Value::None)))
+        .map(|(k, v)| (k.into_owned(), v.into_json_value(opt)))
+        .collect()
+}
+
+#[cfg(all(test, feature = "derive"))]
 mod test {
     #![allow(unused)]
     use serde_json::json;
@@ -263,6 +257,7 @@ mod test {
         let val = TestEnum::Foo;
         let value = val.into_json_with_options(&IntoJsonOptions {
             enum_repr: rusty_value::into_json::EnumRepr::Untagged,
+            ..Default::default()
         });
 
         assert!(value.is_string());
@@ -277,8 +272,9 @@ mod test {
                 type_field: "type".into(),
                 value_field: "value".into(),
             },
+            ..Default::default()
         });
-        println!("{}", value.to_string());
+        println!("{}", value);
 
         assert!(value.is_object());
         assert_eq!(value.get("type").unwrap().as_str(), Some("Bar"));
@@ -290,6 +286,7 @@ mod test {
         let val = TestEnum::Bar(String::new());
         let value = val.into_json_with_options(&IntoJsonOptions {
             enum_repr: rusty_value::into_json::EnumRepr::Untagged,
+            ..Default::default()
         });
 
         assert!(value.is_string());
@@ -301,9 +298,117 @@ mod test {
         let val = TestEnum::Bar(String::new());
         let value = val.into_json_with_options(&IntoJsonOptions {
             enum_repr: rusty_value::into_json::EnumRepr::ExternallyTagged,
+            ..Default::default()
         });
 
         assert!(value.is_object());
         assert!(value.get("Bar").unwrap().is_string());
     }
+
+    #[derive(RustyValue)]
+    struct TestUnitStruct;
+
+    #[derive(RustyValue)]
+    struct TestNewtype(u8);
+
+    #[derive(RustyValue)]
+    struct TestOptionalStruct {
+        foo: Option<u8>,
+        bar: u8,
+    }
+
+    #[test]
+    fn it_serializes_unit_structs() {
+        assert_eq!(TestUnitStruct.into_json(), json!("TestUnitStruct"));
+        let value = TestUnitStruct.into_json_with_options(&IntoJsonOptions {
+            unit_struct_repr: rusty_value::into_json::UnitStructRepr::Null,
+            ..Default::default()
+        });
+        assert_eq!(value, json!(null));
+        let value = TestUnitStruct.into_json_with_options(&IntoJsonOptions {
+            unit_struct_repr: rusty_value::into_json::UnitStructRepr::EmptyObject,
+            ..Default::default()
+        });
+        assert_eq!(value, json!({}));
+    }
+
+    #[test]
+    fn it_serializes_struct_type_fields() {
+        let value = TestStruct::default().into_json_with_options(&IntoJsonOptions {
+            struct_type_field: Some("$type".into()),
+            ..Default::default()
+        });
+
+        assert_eq!(value, json!({"$type": "TestStruct", "foo": "", "bar": 0}));
+
+        let value = TestStruct::default().into_json_with_options(&IntoJsonOptions {
+            struct_type_field: Some("foo".into()),
+            ..Default::default()
+        });
+        assert_eq!(value, json!({"foo": "", "bar": 0}));
+    }
+
+    #[test]
+    fn it_serializes_newtypes() {
+        assert_eq!(TestNewtype(1).into_json(), json!(1));
+        let value = TestNewtype(1).into_json_with_options(&IntoJsonOptions {
+            wrap_newtypes: true,
+            ..Default::default()
+        });
+        assert_eq!(value, json!([1]));
+    }
+
+    #[test]
+    fn it_skips_none_fields() {
+        let val = TestOptionalStruct { foo: None, bar: 1 };
+        let value = val.into_json_with_options(&IntoJsonOptions {
+            skip_none_fields: true,
+            ..Default::default()
+        });
+
+        assert_eq!(value, json!({"bar": 1}));
+    }
+
+    #[derive(RustyValue, RustyType)]
+    struct Login {
+        user: String,
+        #[rusty_value(sensitive)]
+        password: String,
+    }
+
+    #[derive(RustyValue, RustyType)]
+    enum Auth {
+        Password {
+            #[rusty_value(sensitive)]
+            password: String,
+        },
+    }
+
+    #[test]
+    fn it_redacts_structs_and_enums() {
+        let Value::Struct(login) = Login {
+            user: String::from("bob"),
+            password: String::from("hunter2"),
+        }
+        .into_rusty_value() else {
+            panic!("the value isn't a struct");
+        };
+        let value = login.into_json_with_options(&IntoJsonOptions {
+            redact: Some(RedactOptions::for_type::<Login>()),
+            ..Default::default()
+        });
+        assert_eq!(value, json!({"user": "bob", "password": "<redacted>"}));
+
+        let Value::Enum(auth) = Auth::Password {
+            password: String::from("hunter2"),
+        }
+        .into_rusty_value() else {
+            panic!("the value isn't an enum");
+        };
+        let value = auth.into_json_with_options(&IntoJsonOptions {
+            redact: Some(RedactOptions::for_type::<Auth>()),
+            ..Default::default()
+        });
+        assert_eq!(value, json!({"Password": {"password": "<redacted>"}}));
+    }
 }
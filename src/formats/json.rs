@@ -0,0 +1,1126 @@
+use crate::*;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    io,
+};
+
+/// Options for how to represent certain rust types
+/// as JSON
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub struct IntoJsonOptions {
+    pub enum_repr: EnumRepr,
+    /// If set, a field with this name containing the name of the struct
+    /// is added to every struct that is represented as an object.
+    /// Writing a struct that has a field with the same name returns an error,
+    /// [crate::into_json::IntoJson] keeps the field of the struct instead.
+    pub struct_type_field: Option<String>,
+    /// How structs without any fields are represented
+    pub unit_struct_repr: UnitStructRepr,
+    /// Keeps tuple structs with a single field wrapped in an array
+    /// instead of representing them as their inner value
+    pub wrap_newtypes: bool,
+    /// Skips named fields of structs and enum variants
+    /// that have a value of [crate::Value::None]
+    pub skip_none_fields: bool,
+    /// Replaces sensitive values with a marker before they are written.
+    /// Streamed values are converted into a [crate::Value] first if this is set.
+    pub redact: Option<RedactOptions>,
+}
+
+/// Controls how enums should be represented
+/// This works similarly to serde except that internal tagging isn't supported
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub enum EnumRepr {
+    Untagged,
+    #[default]
+    ExternallyTagged,
+    AdjacentlyTagged {
+        type_field: String,
+        value_field: String,
+    },
+}
+
+/// Controls how unit structs should be represented
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub enum UnitStructRepr {
+    /// The name of the struct as a string
+    #[default]
+    Name,
+    /// `null`
+    Null,
+    /// An empty object
+    EmptyObject,
+}
+
+/// Writes the value as compact JSON into the given writer.
+/// Map keys are written as strings, so an error is returned if two keys
+/// of a map are written the same way, e.g. `1u8` and `"1"`.
+pub fn to_writer<W: io::Write>(
+    value: &Value,
+    writer: W,
+    options: &IntoJsonOptions,
+) -> io::Result<()> {
+    JsonWriter::new(writer, options, None).write_value(&redact(value, options))
+}
+
+/// Writes the value as pretty printed JSON into the given writer
+pub fn to_writer_pretty<W: io::Write>(
+    value: &Value,
+    writer: W,
+    options: &IntoJsonOptions,
+) -> io::Result<()> {
+    JsonWriter::new(writer, options, Some("  ")).write_value(&redact(value, options))
+}
+
+/// Converts the value into a compact JSON string
+pub fn to_string(value: &Value, options: &IntoJsonOptions) -> io::Result<String> {
+    let mut buf = Vec::new();
+    to_writer(value, &mut buf, options)?;
+
+    Ok(String::from_utf8(buf).expect("the json writer only emits valid utf-8"))
+}
+
+/// Converts the value into a pretty printed JSON string
+pub fn to_string_pretty(value: &Value, options: &IntoJsonOptions) -> io::Result<String> {
+    let mut buf = Vec::new();
+    to_writer_pretty(value, &mut buf, options)?;
+
+    Ok(String::from_utf8(buf).expect("the json writer only emits valid utf-8"))
+}
+
+/// Writes a streamed value as compact JSON into the given writer
+/// without converting it into a [crate::Value] first.
+/// Named fields and map entries are written in the order they are streamed.
+pub fn stream_to_writer<T: StreamRustyValue + ?Sized, W: io::Write>(
+    value: &T,
+    writer: W,
+    options: &IntoJsonOptions,
+) -> io::Result<()> {
+    if options.redact.is_some() {
+        let value = Value::from_stream(value).map_err(invalid_stream)?;
+        return to_writer(&value, writer, options);
+    }
+    value.stream_rusty_value(&mut JsonSink::new(JsonWriter::new(writer, options, None)))
+}
+
+/// Writes a streamed value as pretty printed JSON into the given writer
+/// without converting it into a [crate::Value] first
+pub fn stream_to_writer_pretty<T: StreamRustyValue + ?Sized, W: io::Write>(
+    value: &T,
+    writer: W,
+    options: &IntoJsonOptions,
+) -> io::Result<()> {
+    if options.redact.is_some() {
+        let value = Value::from_stream(value).map_err(invalid_stream)?;
+        return to_writer_pretty(&value, writer, options);
+    }
+    value.stream_rusty_value(&mut JsonSink::new(JsonWriter::new(
+        writer,
+        options,
+        Some("  "),
+    )))
+}
+
+/// Converts a streamed value into a compact JSON string
+pub fn stream_to_string<T: StreamRustyValue + ?Sized>(
+    value: &T,
+    options: &IntoJsonOptions,
+) -> io::Result<String> {
+    let mut buf = Vec::new();
+    stream_to_writer(value, &mut buf, options)?;
+
+    Ok(String::from_utf8(buf).expect("the json writer only emits valid utf-8"))
+}
+
+/// Converts a streamed value into a pretty printed JSON string
+pub fn stream_to_string_pretty<T: StreamRustyValue + ?Sized>(
+    value: &T,
+    options: &IntoJsonOptions,
+) -> io::Result<String> {
+    let mut buf = Vec::new();
+    stream_to_writer_pretty(value, &mut buf, options)?;
+
+    Ok(String::from_utf8(buf).expect("the json writer only emits valid utf-8"))
+}
+
+/// Returns the value with its sensitive values replaced if redaction is enabled
+fn redact<'v>(value: &'v Value, options: &IntoJsonOptions) -> Cow<'v, Value> {
+    match &options.redact {
+        Some(redact) => Cow::Owned(value.redacted(redact)),
+        None => Cow::Borrowed(value),
+    }
+}
+
+/// Streams a [crate::Value] as JSON text without building an intermediate tree
+struct JsonWriter<'a, W: io::Write> {
+    writer: W,
+    options: &'a IntoJsonOptions,
+    indent: Option<&'static str>,
+    /// Whether the containers that are currently open already contain an element
+    open: Vec<bool>,
+}
+
+impl<'a, W: io::Write> JsonWriter<'a, W> {
+    fn new(writer: W, options: &'a IntoJsonOptions, indent: Option<&'static str>) -> Self {
+        Self {
+            writer,
+            options,
+            indent,
+            open: Vec::new(),
+        }
+    }
+
+    fn write_value(&mut self, value: &Value) -> io::Result<()> {
+        match value {
+            Value::Primitive(p) => self.write_primitive(p),
+            Value::Struct(s) => self.write_struct(s),
+            Value::Enum(e) => self.write_enum(e),
+            Value::Map(m) => {
+                let mut entries = m
+                    .iter()
+                    .map(|(k, v)| (hashable_to_string(k), v))
+                    .collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                    return Err(duplicate_key(&pair[0].0));
+                }
+
+                self.begin('{')?;
+                for (key, value) in entries {
+                    self.write_key(&key)?;
+                    self.write_value(value)?;
+                }
+                self.end('}')
+            }
+            Value::List(l) => self.write_list(l),
+            Value::None => self.writer.write_all(b"null"),
+        }
+    }
+
+    fn write_primitive(&mut self, primitive: &Primitive) -> io::Result<()> {
+        match primitive {
+            Primitive::Integer(Integer::U128(n)) => {
+                let (high, low) = ((n >> 64) as u64, (n & 0xFFFFFFFFFFFFFFFF) as u64);
+                write!(self.writer, "[{high},{low}]")
+            }
+            Primitive::Integer(Integer::I128(n)) => {
+                let (high, low) = ((n >> 64) as i64, (n & 0xFFFFFFFFFFFFFFFF) as u64);
+                write!(self.writer, "[{high},{low}]")
+            }
+            Primitive::Integer(i) => write!(self.writer, "{i}"),
+            Primitive::Float(f) => {
+                let f = match f {
+                    Float::F32(f) => *f as f64,
+                    Float::F64(f) => *f,
+                };
+                if f.is_finite() {
+                    write!(self.writer, "{f:?}")
+                } else {
+                    self.writer.write_all(b"null")
+                }
+            }
+            Primitive::String(s) => self.write_str(s),
+            Primitive::OsString(o) => self.write_str(&o.to_string_lossy()),
+            Primitive::Char(c) => self.write_str(c.encode_utf8(&mut [0; 4])),
+            Primitive::Bool(b) => write!(self.writer, "{b}"),
+        }
+    }
+
+    fn write_struct(&mut self, s: &Struct) -> io::Result<()> {
+        let opt = self.options;
+
+        match &s.fields {
+            Fields::Named(n) => {
+                self.begin('{')?;
+                if let Some(type_field) = &opt.struct_type_field {
+                    if n.contains_key(type_field.as_str()) {
+                        return Err(type_field_conflict(type_field));
+                    }
+                    self.write_key(type_field)?;
+                    self.write_str(&s.name)?;
+                }
+                self.write_named_fields(n)?;
+                self.end('}')
+            }
+            Fields::Unnamed(u) if u.len() == 1 && !opt.wrap_newtypes => self.write_value(&u[0]),
+            Fields::Unnamed(u) => self.write_list(u),
+            Fields::Unit => match opt.unit_struct_repr {
+                UnitStructRepr::Name => self.write_str(&s.name),
+                UnitStructRepr::Null => self.writer.write_all(b"null"),
+                UnitStructRepr::EmptyObject => {
+                    self.begin('{')?;
+                    if let Some(type_field) = &opt.struct_type_field {
+                        self.write_key(type_field)?;
+                        self.write_str(&s.name)?;
+                    }
+                    self.end('}')
+                }
+            },
+        }
+    }
+
+    fn write_enum(&mut self, e: &Enum) -> io::Result<()> {
+        match &self.options.enum_repr {
+            EnumRepr::Untagged => self.write_enum_fields(e),
+            EnumRepr::ExternallyTagged => {
+                self.begin('{')?;
+                self.write_key(&e.variant)?;
+                self.write_enum_fields(e)?;
+                self.end('}')
+            }
+            EnumRepr::AdjacentlyTagged {
+                type_field,
+                value_field,
+            } => {
+                self.begin('{')?;
+                self.write_key(type_field)?;
+                self.write_str(&e.variant)?;
+                self.write_key(value_field)?;
+                self.write_enum_fields(e)?;
+                self.end('}')
+            }
+        }
+    }
+
+    fn write_enum_fields(&mut self, e: &Enum) -> io::Result<()> {
+        match &e.fields {
+            Fields::Named(n) => {
+                self.begin('{')?;
+                self.write_named_fields(n)?;
+                self.end('}')
+            }
+            Fields::Unnamed(u) if u.len() == 1 => self.write_value(&u[0]),
+            Fields::Unnamed(u) => self.write_list(u),
+            Fields::Unit => self.write_str(&e.variant),
+        }
+    }
+
+    fn write_named_fields(&mut self, fields: &HashMap<Cow<'static, str>, Value>) -> io::Result<()> {
+        let mut fields = fields
+            .iter()
+            .filter(|(_, v)| !(self.options.skip_none_fields && matches!(v, Value::None)))
+            .collect::<Vec<_>>();
+        fields.sort_by_key(|(k, _)| *k);
+
+        for (key, value) in fields {
+            self.write_key(key)?;
+            self.write_value(value)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_list(&mut self, list: &[Value]) -> io::Result<()> {
+        self.begin('[')?;
+        for value in list {
+            self.next_element()?;
+            self.write_value(value)?;
+        }
+        self.end(']')
+    }
+
+    fn begin(&mut self, bracket: char) -> io::Result<()> {
+        self.open.push(false);
+        write!(self.writer, "{bracket}")
+    }
+
+    fn end(&mut self, bracket: char) -> io::Result<()> {
+        if self.open.pop() == Some(true) {
+            self.write_newline()?;
+        }
+        write!(self.writer, "{bracket}")
+    }
+
+    /// Writes the separator and indentation for the next element of the current container
+    fn next_element(&mut self) -> io::Result<()> {
+        if let Some(has_elements) = self.open.last_mut() {
+            if *has_elements {
+                self.writer.write_all(b",")?;
+            }
+            *has_elements = true;
+        }
+        self.write_newline()
+    }
+
+    fn write_key(&mut self, key: &str) -> io::Result<()> {
+        self.next_element()?;
+        self.write_str(key)?;
+
+        if self.indent.is_some() {
+            self.writer.write_all(b": ")
+        } else {
+            self.writer.write_all(b":")
+        }
+    }
+
+    fn write_newline(&mut self) -> io::Result<()> {
+        if let Some(indent) = self.indent {
+            self.writer.write_all(b"\n")?;
+            for _ in 0..self.open.len() {
+                self.writer.write_all(indent.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.writer.write_all(b"\"")?;
+        let mut start = 0;
+
+        for (i, c) in s.char_indices() {
+            let escaped = match c {
+                '"' => "\\\"",
+                '\\' => "\\\\",
+                '\n' => "\\n",
+                '\r' => "\\r",
+                '\t' => "\\t",
+                '\u{08}' => "\\b",
+                '\u{0C}' => "\\f",
+                c if (c as u32) < 0x20 => "",
+                _ => continue,
+            };
+            self.writer.write_all(&s.as_bytes()[start..i])?;
+            if escaped.is_empty() {
+                write!(self.writer, "\\u{:04x}", c as u32)?;
+            } else {
+                self.writer.write_all(escaped.as_bytes())?;
+            }
+            start = i + c.len_utf8();
+        }
+        self.writer.write_all(&s.as_bytes()[start..])?;
+
+        self.writer.write_all(b"\"")
+    }
+}
+
+/// Writes the pieces of a streamed value with a [JsonWriter]
+struct JsonSink<'a, 'o, W: io::Write> {
+    json: JsonWriter<'o, W>,
+    frames: Vec<SinkFrame>,
+    /// The named field that is written once its value isn't skipped
+    field: Option<Cow<'static, str>>,
+    /// The map key that is currently streamed. It's collected because
+    /// JSON keys need to be strings.
+    key: Option<ValueSink<'a>>,
+}
+
+struct SinkFrame {
+    /// The kind of value that is ended with the frame
+    ends: FrameKind,
+    elements: SinkElements,
+    /// The brackets that are written when the frame ends
+    close: [Option<char>; 2],
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    Struct,
+    Enum,
+    List,
+    Map,
+}
+
+enum SinkElements {
+    /// Elements are separated like the items of an array
+    Array,
+    /// Elements are introduced by a named field.
+    /// `tagged` is set if the object starts with the struct type field.
+    Object { tagged: bool },
+    /// Entries are introduced by a streamed key
+    Map {
+        key_next: bool,
+        /// The keys that were already written
+        keys: HashSet<String>,
+    },
+    /// A single value that is written as is
+    Transparent,
+}
+
+impl<'a, 'o, W: io::Write> JsonSink<'a, 'o, W> {
+    fn new(json: JsonWriter<'o, W>) -> Self {
+        Self {
+            json,
+            frames: Vec::new(),
+            field: None,
+            key: None,
+        }
+    }
+
+    /// Passes the piece to the map key that is currently streamed.
+    /// Returns whether the piece belonged to a key.
+    fn capture_key<F>(&mut self, f: F) -> io::Result<bool>
+    where
+        F: FnOnce(&mut ValueSink<'a>) -> Result<(), StreamError>,
+    {
+        if self.key.is_none() {
+            match self.frames.last_mut() {
+                Some(SinkFrame {
+                    elements: SinkElements::Map { key_next, .. },
+                    ..
+                }) if *key_next => {
+                    *key_next = false;
+                    self.key = Some(ValueSink::new());
+                }
+                _ => return Ok(false),
+            }
+        }
+        let key = self.key.as_mut().expect("the key was just set");
+        f(key).map_err(invalid_stream)?;
+
+        if key.is_complete() {
+            let key = self.key.take().expect("the key was just set");
+            let key = key
+                .finish()
+                .and_then(stream::into_hashable)
+                .map_err(invalid_stream)?;
+            let key = hashable_to_string(&key);
+
+            if let Some(SinkFrame {
+                elements: SinkElements::Map { keys, .. },
+                ..
+            }) = self.frames.last_mut()
+            {
+                if !keys.insert(key.clone()) {
+                    return Err(duplicate_key(&key));
+                }
+            }
+            self.json.write_key(&key)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Writes the separator or key that comes before the next value
+    fn begin_value(&mut self) -> io::Result<()> {
+        match self.frames.last_mut().map(|f| &mut f.elements) {
+            Some(SinkElements::Array) => self.json.next_element(),
+            Some(SinkElements::Object { .. }) => match self.field.take() {
+                Some(field) => self.json.write_key(&field),
+                None => Err(invalid_stream(StreamError::UnexpectedValue)),
+            },
+            Some(SinkElements::Map { key_next, .. }) => {
+                *key_next = true;
+                Ok(())
+            }
+            Some(SinkElements::Transparent) | None => Ok(()),
+        }
+    }
+
+    fn push_frame(&mut self, ends: FrameKind, elements: SinkElements, close: [Option<char>; 2]) {
+        self.frames.push(SinkFrame {
+            ends,
+            elements,
+            close,
+        });
+    }
+
+    /// Ends the innermost frame if it belongs to the kind of value that is ended
+    /// and no field or map key is waiting for its value
+    fn end_frame(&mut self, ends: FrameKind) -> io::Result<()> {
+        match self.frames.last() {
+            Some(frame) if frame.ends == ends => match frame.elements {
+                _ if self.field.is_some() => return Err(invalid_stream(StreamError::MissingValue)),
+                SinkElements::Map {
+                    key_next: false, ..
+                } => return Err(invalid_stream(StreamError::MissingValue)),
+                _ => {}
+            },
+            _ => return Err(invalid_stream(StreamError::UnexpectedEnd)),
+        }
+        let frame = self.frames.pop().expect("the frame was just checked");
+
+        for bracket in frame.close.into_iter().flatten() {
+            self.json.end(bracket)?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts writing the fields of a struct or enum variant
+    fn begin_fields(
+        &mut self,
+        ends: FrameKind,
+        kind: FieldsKind,
+        len: usize,
+        wrap_newtype: bool,
+        tag: Option<char>,
+    ) -> io::Result<()> {
+        match kind {
+            FieldsKind::Named => {
+                self.json.begin('{')?;
+                self.push_frame(
+                    ends,
+                    SinkElements::Object { tagged: false },
+                    [Some('}'), tag],
+                );
+            }
+            FieldsKind::Unnamed if len == 1 && !wrap_newtype => {
+                self.push_frame(ends, SinkElements::Transparent, [None, tag])
+            }
+            FieldsKind::Unnamed => {
+                self.json.begin('[')?;
+                self.push_frame(ends, SinkElements::Array, [Some(']'), tag]);
+            }
+            FieldsKind::Unit => self.push_frame(ends, SinkElements::Transparent, [None, tag]),
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, W: io::Write> Sink<'a> for JsonSink<'a, '_, W> {
+    type Error = io::Error;
+
+    fn integer(&mut self, value: Integer) -> io::Result<()> {
+        if self.capture_key(|k| k.integer(value.clone()))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.write_primitive(&Primitive::Integer(value))
+    }
+
+    fn float(&mut self, value: Float) -> io::Result<()> {
+        if self.capture_key(|k| k.float(value.clone()))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.write_primitive(&Primitive::Float(value))
+    }
+
+    fn str(&mut self, value: &'a str) -> io::Result<()> {
+        if self.capture_key(|k| k.str(value))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.write_str(value)
+    }
+
+    fn os_str(&mut self, value: &'a OsStr) -> io::Result<()> {
+        if self.capture_key(|k| k.os_str(value))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.write_str(&value.to_string_lossy())
+    }
+
+    fn char(&mut self, value: char) -> io::Result<()> {
+        if self.capture_key(|k| k.char(value))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.write_primitive(&Primitive::Char(value))
+    }
+
+    fn bool(&mut self, value: bool) -> io::Result<()> {
+        if self.capture_key(|k| k.bool(value))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.write_primitive(&Primitive::Bool(value))
+    }
+
+    fn none(&mut self) -> io::Result<()> {
+        if self.capture_key(|k| k.none())? {
+            return Ok(());
+        }
+        if self.json.options.skip_none_fields && self.field.is_some() {
+            self.field = None;
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.writer.write_all(b"null")
+    }
+
+    fn begin_struct(
+        &mut self,
+        name: Cow<'static, str>,
+        kind: FieldsKind,
+        len: usize,
+    ) -> io::Result<()> {
+        if self.capture_key(|k| k.begin_struct(name.clone(), kind, len))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        let opt = self.json.options;
+
+        match kind {
+            FieldsKind::Named => {
+                self.json.begin('{')?;
+                if let Some(type_field) = &opt.struct_type_field {
+                    self.json.write_key(type_field)?;
+                    self.json.write_str(&name)?;
+                }
+                let tagged = opt.struct_type_field.is_some();
+                self.push_frame(
+                    FrameKind::Struct,
+                    SinkElements::Object { tagged },
+                    [Some('}'), None],
+                );
+                Ok(())
+            }
+            FieldsKind::Unnamed => {
+                self.begin_fields(FrameKind::Struct, kind, len, opt.wrap_newtypes, None)
+            }
+            FieldsKind::Unit => {
+                match opt.unit_struct_repr {
+                    UnitStructRepr::Name => self.json.write_str(&name)?,
+                    UnitStructRepr::Null => self.json.writer.write_all(b"null")?,
+                    UnitStructRepr::EmptyObject => {
+                        self.json.begin('{')?;
+                        if let Some(type_field) = &opt.struct_type_field {
+                            self.json.write_key(type_field)?;
+                            self.json.write_str(&name)?;
+                        }
+                        self.json.end('}')?;
+                    }
+                }
+                self.begin_fields(FrameKind::Struct, kind, len, false, None)
+            }
+        }
+    }
+
+    fn field(&mut self, name: Cow<'static, str>) -> io::Result<()> {
+        if self.capture_key(|k| k.field(name.clone()))? {
+            return Ok(());
+        }
+        match self.frames.last() {
+            Some(SinkFrame {
+                elements: SinkElements::Object { tagged },
+                ..
+            }) if self.field.is_none() => {
+                let type_field = self.json.options.struct_type_field.as_deref();
+                if *tagged && type_field == Some(&*name) {
+                    return Err(type_field_conflict(&name));
+                }
+                self.field = Some(name);
+                Ok(())
+            }
+            _ => Err(invalid_stream(StreamError::UnexpectedField)),
+        }
+    }
+
+    fn end_struct(&mut self) -> io::Result<()> {
+        if self.capture_key(|k| k.end_struct())? {
+            return Ok(());
+        }
+        self.end_frame(FrameKind::Struct)
+    }
+
+    fn begin_enum(
+        &mut self,
+        name: Cow<'static, str>,
+        variant: Cow<'static, str>,
+        kind: FieldsKind,
+        len: usize,
+    ) -> io::Result<()> {
+        if self.capture_key(|k| k.begin_enum(name.clone(), variant.clone(), kind, len))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+
+        let tag = match &self.json.options.enum_repr {
+            EnumRepr::Untagged => None,
+            EnumRepr::ExternallyTagged => {
+                self.json.begin('{')?;
+                self.json.write_key(&variant)?;
+                Some('}')
+            }
+            EnumRepr::AdjacentlyTagged {
+                type_field,
+                value_field,
+            } => {
+                self.json.begin('{')?;
+                self.json.write_key(type_field)?;
+                self.json.write_str(&variant)?;
+                self.json.write_key(value_field)?;
+                Some('}')
+            }
+        };
+        if kind == FieldsKind::Unit {
+            self.json.write_str(&variant)?;
+        }
+
+        self.begin_fields(FrameKind::Enum, kind, len, false, tag)
+    }
+
+    fn end_enum(&mut self) -> io::Result<()> {
+        if self.capture_key(|k| k.end_enum())? {
+            return Ok(());
+        }
+        self.end_frame(FrameKind::Enum)
+    }
+
+    fn begin_list(&mut self, len: usize) -> io::Result<()> {
+        if self.capture_key(|k| k.begin_list(len))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.begin('[')?;
+        self.push_frame(FrameKind::List, SinkElements::Array, [Some(']'), None]);
+
+        Ok(())
+    }
+
+    fn end_list(&mut self) -> io::Result<()> {
+        if self.capture_key(|k| k.end_list())? {
+            return Ok(());
+        }
+        self.end_frame(FrameKind::List)
+    }
+
+    fn begin_map(&mut self, len: usize) -> io::Result<()> {
+        if self.capture_key(|k| k.begin_map(len))? {
+            return Ok(());
+        }
+        self.begin_value()?;
+        self.json.begin('{')?;
+        self.push_frame(
+            FrameKind::Map,
+            SinkElements::Map {
+                key_next: true,
+                keys: HashSet::new(),
+            },
+            [Some('}'), None],
+        );
+
+        Ok(())
+    }
+
+    fn end_map(&mut self) -> io::Result<()> {
+        if self.key.is_some() && self.capture_key(|k| k.end_map())? {
+            return Ok(());
+        }
+        self.end_frame(FrameKind::Map)
+    }
+}
+
+fn invalid_stream(e: StreamError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+fn type_field_conflict(field: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("the struct type field `{field}` is also a field of the struct"),
+    )
+}
+
+fn duplicate_key(key: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("multiple keys of the map are written as `{key}`"),
+    )
+}
+
+pub(crate) fn hashable_to_string(hashable: &HashableValue) -> String {
+    match hashable {
+        HashableValue::Primitive(p) => p.to_string(),
+        HashableValue::List(l) => l
+            .iter()
+            .map(hashable_to_string)
+            .collect::<Vec<_>>()
+            .join(","),
+        HashableValue::None => String::new(),
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::json::{self, EnumRepr, IntoJsonOptions};
+    use crate::*;
+
+    #[derive(RustyValue)]
+    #[rusty_value(stream)]
+    struct TestStruct {
+        foo: String,
+        bar: Vec<u8>,
+    }
+
+    #[derive(RustyValue)]
+    #[rusty_value(stream)]
+    enum TestEnum {
+        Foo,
+        Bar(String),
+    }
+
+    #[test]
+    fn it_writes_primitives() {
+        let opt = IntoJsonOptions::default();
+
+        assert_eq!(
+            json::to_string(&u8::MAX.into_rusty_value(), &opt).unwrap(),
+            "255"
+        );
+        assert_eq!(
+            json::to_string(&1.5f64.into_rusty_value(), &opt).unwrap(),
+            "1.5"
+        );
+        assert_eq!(
+            json::to_string(&f64::NAN.into_rusty_value(), &opt).unwrap(),
+            "null"
+        );
+        assert_eq!(
+            json::to_string(&u128::MAX.into_rusty_value(), &opt).unwrap(),
+            format!("[{},{}]", u64::MAX, u64::MAX)
+        );
+        assert_eq!(
+            json::to_string(&String::from("a\"b\n\u{1}").into_rusty_value(), &opt).unwrap(),
+            r#""a\"b\n\u0001""#
+        );
+    }
+
+    #[test]
+    fn it_writes_compact_structs() {
+        let value = TestStruct {
+            foo: String::from("hello"),
+            bar: vec![1, 2],
+        }
+        .into_rusty_value();
+
+        assert_eq!(
+            json::to_string(&value, &IntoJsonOptions::default()).unwrap(),
+            r#"{"bar":[1,2],"foo":"hello"}"#
+        );
+    }
+
+    #[test]
+    fn it_writes_pretty_structs() {
+        let value = TestStruct {
+            foo: String::from("hello"),
+            bar: vec![],
+        }
+        .into_rusty_value();
+
+        assert_eq!(
+            json::to_string_pretty(&value, &IntoJsonOptions::default()).unwrap(),
+            "{\n  \"bar\": [],\n  \"foo\": \"hello\"\n}"
+        );
+    }
+
+    #[test]
+    fn it_writes_enums() {
+        let value = TestEnum::Bar(String::from("baz")).into_rusty_value();
+        let opt = IntoJsonOptions {
+            enum_repr: EnumRepr::AdjacentlyTagged {
+                type_field: "type".into(),
+                value_field: "value".into(),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            json::to_string(&value, &opt).unwrap(),
+            r#"{"type":"Bar","value":"baz"}"#
+        );
+        assert_eq!(
+            json::to_string(&TestEnum::Foo.into_rusty_value(), &Default::default()).unwrap(),
+            r#"{"Foo":"Foo"}"#
+        );
+    }
+
+    #[test]
+    fn it_rejects_keys_that_are_written_the_same() {
+        let value = rusty_value!({ 1u8 => "a", "1" => "b" });
+        assert!(json::to_string(&value, &Default::default()).is_err());
+
+        let mut map = HashMap::new();
+        map.insert(None, 1u8);
+        map.insert(Some(String::new()), 2u8);
+        assert!(json::stream_to_string(&map, &Default::default()).is_err());
+    }
+
+    #[test]
+    fn it_rejects_type_fields_that_are_struct_fields() {
+        let opt = IntoJsonOptions {
+            struct_type_field: Some(String::from("foo")),
+            ..Default::default()
+        };
+        let make = || TestStruct {
+            foo: String::from("hello"),
+            bar: vec![1],
+        };
+
+        assert!(json::to_string(&make().into_rusty_value(), &opt).is_err());
+        assert!(json::stream_to_string(&make(), &opt).is_err());
+        assert_eq!(
+            json::stream_to_string(&TestEnum::Bar(String::from("a")), &opt).unwrap(),
+            r#"{"Bar":"a"}"#
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn it_matches_into_json() {
+        use crate::into_json::IntoJson;
+
+        let make = || {
+            let mut map = HashMap::new();
+            map.insert(String::from("key"), TestEnum::Foo);
+            map.insert(String::from("other"), TestEnum::Bar(String::from("\t")));
+            (
+                TestStruct {
+                    foo: String::from("hello"),
+                    bar: vec![1, 2, 3],
+                },
+                map,
+                i128::MIN,
+            )
+        };
+        let opt = IntoJsonOptions {
+            struct_type_field: Some(String::from("$type")),
+            ..Default::default()
+        };
+        let (s, map, n) = make();
+        let written = json::to_string_pretty(
+            &Value::List(vec![
+                s.into_rusty_value(),
+                map.into_rusty_value(),
+                n.into_rusty_value(),
+            ]),
+            &opt,
+        )
+        .unwrap();
+        let (s, map, n) = make();
+        let expected = serde_json::Value::Array(vec![
+            s.into_json_with_options(&opt),
+            map.into_json_with_options(&opt),
+            n.into_json_with_options(&opt),
+        ]);
+
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&written).unwrap(),
+            expected
+        );
+    }
+
+    #[derive(RustyValue)]
+    #[rusty_value(stream)]
+    struct StreamStruct {
+        name: String,
+        missing: Option<u8>,
+        inner: TestEnum,
+        wrapped: Wrapper,
+        map: HashMap<u8, Vec<TestEnum>>,
+    }
+
+    #[derive(RustyValue)]
+    #[rusty_value(stream)]
+    struct Wrapper(u8);
+
+    fn stream_struct() -> StreamStruct {
+        let mut map = HashMap::new();
+        map.insert(1, vec![TestEnum::Foo, TestEnum::Bar(String::from("a"))]);
+
+        StreamStruct {
+            name: String::from("x"),
+            missing: None,
+            inner: TestEnum::Foo,
+            wrapped: Wrapper(2),
+            map,
+        }
+    }
+
+    #[test]
+    fn it_streams_values() {
+        let opt = IntoJsonOptions {
+            skip_none_fields: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            json::stream_to_string(&stream_struct(), &opt).unwrap(),
+            r#"{"name":"x","inner":{"Foo":"Foo"},"wrapped":2,"map":{"1":[{"Foo":"Foo"},{"Bar":"a"}]}}"#
+        );
+        assert_eq!(
+            json::stream_to_string_pretty(&vec![Wrapper(1)], &Default::default()).unwrap(),
+            "[\n  1\n]"
+        );
+    }
+
+    /// Streams pieces in an invalid order
+    enum InvalidStream {
+        MissingFieldValue,
+        MissingMapValue,
+        MismatchedEnd,
+    }
+
+    impl StreamRustyValue for InvalidStream {
+        fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+            match self {
+                InvalidStream::MissingFieldValue => {
+                    sink.begin_struct("A".into(), FieldsKind::Named, 1)?;
+                    sink.field("a".into())?;
+                    sink.end_struct()
+                }
+                InvalidStream::MissingMapValue => {
+                    sink.begin_map(1)?;
+                    sink.str("a")?;
+                    sink.end_map()
+                }
+                InvalidStream::MismatchedEnd => {
+                    sink.begin_list(0)?;
+                    sink.end_struct()
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn it_rejects_invalid_streams() {
+        let invalid = [
+            (InvalidStream::MissingFieldValue, StreamError::MissingValue),
+            (InvalidStream::MissingMapValue, StreamError::MissingValue),
+            (InvalidStream::MismatchedEnd, StreamError::UnexpectedEnd),
+        ];
+
+        for (stream, error) in invalid {
+            let e = json::stream_to_string(&stream, &Default::default()).unwrap_err();
+            assert_eq!(e.to_string(), error.to_string());
+            assert_eq!(Value::from_stream(&stream), Err(error));
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn it_streams_like_the_value_writer() {
+        let options = [
+            IntoJsonOptions::default(),
+            IntoJsonOptions {
+                enum_repr: EnumRepr::Untagged,
+                struct_type_field: Some(String::from("$type")),
+                unit_struct_repr: json::UnitStructRepr::EmptyObject,
+                wrap_newtypes: true,
+                skip_none_fields: true,
+                redact: None,
+            },
+            IntoJsonOptions {
+                enum_repr: EnumRepr::AdjacentlyTagged {
+                    type_field: String::from("t"),
+                    value_field: String::from("c"),
+                },
+                unit_struct_repr: json::UnitStructRepr::Null,
+                ..Default::default()
+            },
+        ];
+
+        for opt in options {
+            let streamed = json::stream_to_string_pretty(&stream_struct(), &opt).unwrap();
+            let written = json::to_string(&stream_struct().into_rusty_value(), &opt).unwrap();
+
+            assert_eq!(
+                serde_json::from_str::<serde_json::Value>(&streamed).unwrap(),
+                serde_json::from_str::<serde_json::Value>(&written).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,571 @@
+use crate::json::{EnumRepr, IntoJsonOptions, UnitStructRepr};
+use crate::*;
+use serde_json::{json, Map};
+use std::collections::BTreeMap;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Returns the JSON Schema (draft 2020-12) of the JSON that
+/// [crate::into_json::IntoJson] produces for the type with the given options
+pub fn schema_for<T: RustyType>(options: &IntoJsonOptions) -> serde_json::Value {
+    from_type_schema(&T::rusty_type(), options)
+}
+
+/// Converts the type schema into a JSON Schema (draft 2020-12) that matches
+/// the output of [crate::into_json::IntoJson] with the given options.
+/// Values that might be redacted also match the marker, i.e. sensitive fields,
+/// named fields that match a field pattern and the values of maps with string keys.
+/// Recursive types are added to `$defs` under their full type name and referenced with `$ref`,
+/// so different instances of a generic type get their own definitions.
+///
+/// # Panics
+///
+/// Panics if the redact options contain queries that aren't the sensitive queries
+/// of the schema, e.g. from [crate::RedactOptions::for_type] of another type,
+/// since the values they select can't be told from the schema.
+pub fn from_type_schema(schema: &TypeSchema, options: &IntoJsonOptions) -> serde_json::Value {
+    if let Some(redact) = &options.redact {
+        let sensitive = schema.sensitive_queries();
+        assert!(
+            redact.queries.iter().all(|query| sensitive.contains(query)),
+            "redact queries other than the sensitive queries of the type aren't supported in JSON schemas"
+        );
+    }
+
+    let mut root = match type_schema(schema, options) {
+        serde_json::Value::Object(o) => o,
+        serde_json::Value::Bool(true) => Map::new(),
+        other => return other,
+    };
+    root.insert(String::from("$schema"), json!(DRAFT));
+
+    let mut referenced = BTreeMap::new();
+    collect_referenced(schema, &mut Vec::new(), &mut referenced);
+    if !referenced.is_empty() {
+        let defs = referenced
+            .into_iter()
+            .map(|(name, schema)| (name.to_owned(), type_schema(schema, options)))
+            .collect::<Map<_, _>>();
+        root.insert(String::from("$defs"), serde_json::Value::Object(defs));
+    }
+
+    serde_json::Value::Object(root)
+}
+
+fn type_schema(schema: &TypeSchema, opt: &IntoJsonOptions) -> serde_json::Value {
+    match schema {
+        TypeSchema::Primitive(p) => primitive_schema(*p),
+        TypeSchema::Struct(s) => struct_schema(s, opt),
+        TypeSchema::Enum(e) => enum_schema(e, opt),
+        TypeSchema::Option(inner) => json!({
+            "anyOf": [type_schema(inner, opt), { "type": "null" }]
+        }),
+        TypeSchema::List(inner) => json!({
+            "type": "array",
+            "items": type_schema(inner, opt),
+        }),
+        TypeSchema::Map { key, value } => {
+            let mut value_schema = type_schema(value, opt);
+            // entries with string keys are redacted if the key matches a field pattern
+            if matches!(**key, TypeSchema::Primitive(PrimitiveKind::String))
+                && opt
+                    .redact
+                    .as_ref()
+                    .is_some_and(|r| !r.field_patterns.is_empty())
+            {
+                value_schema = redactable(value_schema, opt);
+            }
+            json!({
+                "type": "object",
+                "additionalProperties": value_schema,
+            })
+        }
+        TypeSchema::Unit => json!({ "type": "null" }),
+        TypeSchema::Any => json!(true),
+        TypeSchema::Ref { type_name, .. } => json!({ "$ref": definition_ref(type_name) }),
+        TypeSchema::Sensitive(inner) => redactable(type_schema(inner, opt), opt),
+    }
+}
+
+/// Allows the marker next to the schema if redaction is enabled
+fn redactable(schema: serde_json::Value, opt: &IntoJsonOptions) -> serde_json::Value {
+    match &opt.redact {
+        Some(redact) => json!({ "anyOf": [schema, { "const": redact.marker }] }),
+        None => schema,
+    }
+}
+
+/// Returns the `$ref` of the definition of the type with the given full name.
+/// The name is escaped as a JSON pointer and percent-encoded as a URI fragment
+/// since type names contain characters like `<`, `>` and spaces.
+fn definition_ref(type_name: &str) -> String {
+    let pointer = type_name.replace('~', "~0").replace('/', "~1");
+    let mut reference = String::from("#/$defs/");
+
+    for byte in pointer.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&byte) {
+            reference.push(byte as char);
+        } else {
+            reference.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    reference
+}
+
+/// Collects the schemas of the structs and enums that are referenced by a [TypeSchema::Ref]
+/// by their full type name. `types` contains the enclosing structs and enums.
+fn collect_referenced<'s>(
+    schema: &'s TypeSchema,
+    types: &mut Vec<&'s TypeSchema>,
+    referenced: &mut BTreeMap<&'static str, &'s TypeSchema>,
+) {
+    let fields = |fields: &'s FieldsSchema| match fields {
+        FieldsSchema::Named(fields) => fields.iter().map(|f| &f.schema).collect(),
+        FieldsSchema::Unnamed(fields) => fields.iter().collect(),
+        FieldsSchema::Unit => Vec::new(),
+    };
+    let children: Vec<&TypeSchema> = match schema {
+        TypeSchema::Struct(s) => fields(&s.fields),
+        TypeSchema::Enum(e) => e.variants.iter().flat_map(|v| fields(&v.fields)).collect(),
+        TypeSchema::Option(inner) | TypeSchema::List(inner) | TypeSchema::Sensitive(inner) => {
+            vec![inner]
+        }
+        TypeSchema::Map { key, value } => vec![key, value],
+        TypeSchema::Ref { type_name, .. } => {
+            if let Some(target) = types
+                .iter()
+                .rev()
+                .find(|t| t.type_name() == Some(type_name))
+            {
+                referenced.entry(type_name).or_insert(target);
+            }
+            Vec::new()
+        }
+        TypeSchema::Primitive(_) | TypeSchema::Unit | TypeSchema::Any => Vec::new(),
+    };
+
+    types.push(schema);
+    for child in children {
+        collect_referenced(child, types, referenced);
+    }
+    types.pop();
+}
+
+fn primitive_schema(kind: PrimitiveKind) -> serde_json::Value {
+    match kind {
+        PrimitiveKind::USize => integer_schema(usize::MIN, usize::MAX),
+        PrimitiveKind::ISize => integer_schema(isize::MIN, isize::MAX),
+        PrimitiveKind::U8 => integer_schema(u8::MIN, u8::MAX),
+        PrimitiveKind::I8 => integer_schema(i8::MIN, i8::MAX),
+        PrimitiveKind::U16 => integer_schema(u16::MIN, u16::MAX),
+        PrimitiveKind::I16 => integer_schema(i16::MIN, i16::MAX),
+        PrimitiveKind::U32 => integer_schema(u32::MIN, u32::MAX),
+        PrimitiveKind::I32 => integer_schema(i32::MIN, i32::MAX),
+        PrimitiveKind::U64 => integer_schema(u64::MIN, u64::MAX),
+        PrimitiveKind::I64 => integer_schema(i64::MIN, i64::MAX),
+        // 128 bit integers are written as an array of the high and low 64 bits
+        PrimitiveKind::U128 => tuple_schema(vec![
+            integer_schema(u64::MIN, u64::MAX),
+            integer_schema(u64::MIN, u64::MAX),
+        ]),
+        PrimitiveKind::I128 => tuple_schema(vec![
+            integer_schema(i64::MIN, i64::MAX),
+            integer_schema(u64::MIN, u64::MAX),
+        ]),
+        // non-finite floats are written as null
+        PrimitiveKind::F32 | PrimitiveKind::F64 => json!({ "type": ["number", "null"] }),
+        PrimitiveKind::String | PrimitiveKind::OsString => json!({ "type": "string" }),
+        PrimitiveKind::Char => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        PrimitiveKind::Bool => json!({ "type": "boolean" }),
+    }
+}
+
+fn integer_schema<N: Into<serde_json::Value>>(min: N, max: N) -> serde_json::Value {
+    json!({
+        "type": "integer",
+        "minimum": min.into(),
+        "maximum": max.into(),
+    })
+}
+
+fn tuple_schema(items: Vec<serde_json::Value>) -> serde_json::Value {
+    json!({
+        "type": "array",
+        "prefixItems": items,
+        "minItems": items.len(),
+        "items": false,
+    })
+}
+
+fn struct_schema(s: &StructSchema, opt: &IntoJsonOptions) -> serde_json::Value {
+    let type_field = opt
+        .struct_type_field
+        .as_ref()
+        .map(|field| (field.as_str(), s.name));
+
+    match &s.fields {
+        FieldsSchema::Named(fields) => object_schema(fields, type_field, opt),
+        FieldsSchema::Unnamed(fields) if fields.len() == 1 && !opt.wrap_newtypes => {
+            type_schema(&fields[0], opt)
+        }
+        FieldsSchema::Unnamed(fields) => {
+            tuple_schema(fields.iter().map(|f| type_schema(f, opt)).collect())
+        }
+        FieldsSchema::Unit => match opt.unit_struct_repr {
+            UnitStructRepr::Name => json!({ "const": s.name }),
+            UnitStructRepr::Null => json!({ "type": "null" }),
+            UnitStructRepr::EmptyObject => object_schema(&[], type_field, opt),
+        },
+    }
+}
+
+/// Creates the schema of an object with the given fields and an optional field containing a type name
+fn object_schema(
+    fields: &[FieldSchema],
+    type_field: Option<(&str, &str)>,
+    opt: &IntoJsonOptions,
+) -> serde_json::Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    // a field of the struct with the same name replaces the type field
+    if let Some((field, name)) = type_field.filter(|(t, _)| !fields.iter().any(|f| f.name == *t)) {
+        properties.insert(field.to_owned(), json!({ "const": name }));
+        required.push(field);
+    }
+    for field in fields {
+        let mut schema = type_schema(&field.schema, opt);
+        // sensitive fields already allow the marker
+        if !matches!(field.schema, TypeSchema::Sensitive(_))
+            && opt
+                .redact
+                .as_ref()
+                .is_some_and(|r| r.matches_field(field.name))
+        {
+            schema = redactable(schema, opt);
+        }
+        properties.insert(field.name.to_owned(), schema);
+
+        if !(opt.skip_none_fields && can_be_none(&field.schema)) {
+            required.push(field.name);
+        }
+    }
+
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// Returns whether a value of the schema can be [crate::Value::None] and might be skipped
+fn can_be_none(schema: &TypeSchema) -> bool {
+    match schema {
+        TypeSchema::Sensitive(inner) => can_be_none(inner),
+        schema => matches!(
+            schema,
+            TypeSchema::Option(_) | TypeSchema::Unit | TypeSchema::Any
+        ),
+    }
+}
+
+fn enum_schema(e: &EnumSchema, opt: &IntoJsonOptions) -> serde_json::Value {
+    let variants = e
+        .variants
+        .iter()
+        .map(|v| variant_schema(v, opt))
+        .collect::<Vec<_>>();
+
+    match opt.enum_repr {
+        // variants without a tag can't be told apart so more than one of them may match
+        EnumRepr::Untagged => json!({ "anyOf": variants }),
+        _ => json!({ "oneOf": variants }),
+    }
+}
+
+fn variant_schema(variant: &VariantSchema, opt: &IntoJsonOptions) -> serde_json::Value {
+    let value = match &variant.fields {
+        FieldsSchema::Named(fields) => object_schema(fields, None, opt),
+        FieldsSchema::Unnamed(fields) if fields.len() == 1 => type_schema(&fields[0], opt),
+        FieldsSchema::Unnamed(fields) => {
+            tuple_schema(fields.iter().map(|f| type_schema(f, opt)).collect())
+        }
+        FieldsSchema::Unit => json!({ "const": variant.name }),
+    };
+
+    match &opt.enum_repr {
+        EnumRepr::Untagged => value,
+        EnumRepr::ExternallyTagged => json!({
+            "type": "object",
+            "properties": { variant.name: value },
+            "required": [variant.name],
+            "additionalProperties": false,
+        }),
+        EnumRepr::AdjacentlyTagged {
+            type_field,
+            value_field,
+        } => json!({
+            "type": "object",
+            "properties": {
+                type_field: { "const": variant.name },
+                value_field: value,
+            },
+            "required": [type_field, value_field],
+            "additionalProperties": false,
+        }),
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use serde_json::json;
+
+    use crate as rusty_value;
+    use crate::json::{EnumRepr, IntoJsonOptions, UnitStructRepr};
+    use crate::json_schema;
+    use crate::*;
+
+    #[derive(RustyType)]
+    struct Server {
+        port: u16,
+        name: Option<String>,
+    }
+
+    #[derive(RustyType)]
+    enum Mode {
+        Fast,
+        Slow(u32),
+    }
+
+    #[derive(RustyType)]
+    struct Id(u128);
+
+    #[derive(RustyType)]
+    struct Marker;
+
+    #[derive(RustyType)]
+    struct Tree {
+        value: u8,
+        children: Vec<Tree>,
+    }
+
+    #[derive(RustyType)]
+    struct Node<T> {
+        value: T,
+        children: Vec<Node<T>>,
+    }
+
+    #[derive(RustyType)]
+    struct Pair {
+        a: Node<u8>,
+        b: Node<String>,
+    }
+
+    #[derive(RustyType)]
+    struct Login {
+        #[rusty_value(sensitive)]
+        password: String,
+        #[rusty_value(sensitive)]
+        token: Option<String>,
+    }
+
+    #[test]
+    fn it_describes_structs() {
+        let schema = json_schema::schema_for::<Server>(&IntoJsonOptions {
+            skip_none_fields: true,
+            struct_type_field: Some(String::from("$type")),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "$type": { "const": "Server" },
+                    "port": { "type": "integer", "minimum": 0, "maximum": 65535 },
+                    "name": { "anyOf": [{ "type": "string" }, { "type": "null" }] },
+                },
+                "required": ["$type", "port"],
+                "additionalProperties": false,
+            })
+        );
+    }
+
+    #[test]
+    fn it_describes_enums() {
+        let opt = IntoJsonOptions {
+            enum_repr: EnumRepr::AdjacentlyTagged {
+                type_field: String::from("t"),
+                value_field: String::from("c"),
+            },
+            ..Default::default()
+        };
+
+        assert_eq!(
+            json_schema::schema_for::<Mode>(&opt)["oneOf"][1],
+            json!({
+                "type": "object",
+                "properties": {
+                    "t": { "const": "Slow" },
+                    "c": { "type": "integer", "minimum": 0, "maximum": u32::MAX },
+                },
+                "required": ["t", "c"],
+                "additionalProperties": false,
+            })
+        );
+        assert_eq!(
+            json_schema::schema_for::<Mode>(&IntoJsonOptions::default())["oneOf"][0],
+            json!({
+                "type": "object",
+                "properties": { "Fast": { "const": "Fast" } },
+                "required": ["Fast"],
+                "additionalProperties": false,
+            })
+        );
+    }
+
+    #[test]
+    fn it_describes_newtypes_and_wide_integers() {
+        let schema = json_schema::schema_for::<Id>(&IntoJsonOptions::default());
+        assert_eq!(schema["type"], "array");
+        assert_eq!(schema["prefixItems"][0]["maximum"], json!(u64::MAX));
+
+        let schema = json_schema::schema_for::<Id>(&IntoJsonOptions {
+            wrap_newtypes: true,
+            ..Default::default()
+        });
+        assert_eq!(schema["prefixItems"][0]["type"], "array");
+
+        let schema = json_schema::schema_for::<Marker>(&IntoJsonOptions {
+            unit_struct_repr: UnitStructRepr::Null,
+            ..Default::default()
+        });
+        assert_eq!(schema["type"], "null");
+        assert_eq!(
+            json_schema::schema_for::<Value>(&IntoJsonOptions::default()),
+            json!({ "$schema": "https://json-schema.org/draft/2020-12/schema" })
+        );
+    }
+
+    #[derive(RustyType)]
+    struct Settings {
+        api_key: String,
+        port: u16,
+        env: HashMap<String, String>,
+    }
+
+    #[test]
+    fn it_allows_values_redacted_by_field_patterns() {
+        let schema = json_schema::schema_for::<Settings>(&IntoJsonOptions {
+            redact: Some(RedactOptions {
+                field_patterns: vec![String::from("*key*")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+
+        assert_eq!(
+            schema["properties"]["api_key"],
+            json!({ "anyOf": [{ "type": "string" }, { "const": "<redacted>" }] })
+        );
+        assert_eq!(schema["properties"]["port"]["type"], json!("integer"));
+        assert_eq!(
+            schema["properties"]["env"]["additionalProperties"],
+            json!({ "anyOf": [{ "type": "string" }, { "const": "<redacted>" }] })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "redact queries")]
+    fn it_rejects_queries_of_other_types() {
+        json_schema::schema_for::<Server>(&IntoJsonOptions {
+            redact: Some(RedactOptions::for_type::<Login>()),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn it_allows_redacted_values() {
+        assert_eq!(
+            json_schema::schema_for::<Login>(&IntoJsonOptions::default())["properties"]["password"],
+            json!({ "type": "string" })
+        );
+        assert_eq!(
+            json_schema::schema_for::<Login>(&IntoJsonOptions {
+                redact: Some(RedactOptions::for_type::<Login>()),
+                ..Default::default()
+            })["properties"]["password"],
+            json!({ "anyOf": [{ "type": "string" }, { "const": "<redacted>" }] })
+        );
+        assert_eq!(
+            json_schema::schema_for::<Login>(&IntoJsonOptions {
+                skip_none_fields: true,
+                ..Default::default()
+            })["required"],
+            json!(["password"])
+        );
+    }
+
+    #[test]
+    fn it_references_recursive_types() {
+        let tree = std::any::type_name::<Tree>();
+        let tree_ref = super::definition_ref(tree);
+
+        assert_eq!(
+            json_schema::schema_for::<Tree>(&IntoJsonOptions::default()),
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "value": { "type": "integer", "minimum": 0, "maximum": 255 },
+                    "children": { "type": "array", "items": { "$ref": tree_ref } },
+                },
+                "required": ["value", "children"],
+                "additionalProperties": false,
+                "$defs": {
+                    tree: {
+                        "type": "object",
+                        "properties": {
+                            "value": { "type": "integer", "minimum": 0, "maximum": 255 },
+                            "children": { "type": "array", "items": { "$ref": tree_ref } },
+                        },
+                        "required": ["value", "children"],
+                        "additionalProperties": false,
+                    },
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn it_defines_each_instance_of_generic_types() {
+        let schema = json_schema::schema_for::<Pair>(&IntoJsonOptions::default());
+        let defs = schema["$defs"].as_object().unwrap();
+
+        assert_eq!(defs.len(), 2);
+        for (field, value, node) in [
+            ("a", json!("integer"), std::any::type_name::<Node<u8>>()),
+            ("b", json!("string"), std::any::type_name::<Node<String>>()),
+        ] {
+            let children =
+                json!({ "type": "array", "items": { "$ref": super::definition_ref(node) } });
+
+            assert_eq!(
+                schema["properties"][field]["properties"]["children"],
+                children
+            );
+            assert_eq!(defs[node]["properties"]["value"]["type"], value);
+            assert_eq!(defs[node]["properties"]["children"], children);
+        }
+        assert_eq!(
+            super::definition_ref("a::Node<alloc::string::String>"),
+            "#/$defs/a::Node%3Calloc::string::String%3E"
+        );
+    }
+}
@@ -2,3 +2,54 @@
 #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
 /// Implements converting the [crate::Value] into a [serde_json::Value].
 pub mod into_json;
+
+#[cfg(feature = "json-writer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json-writer")))]
+/// Implements writing the [crate::Value] as JSON text without depending on serde_json.
+pub mod json;
+
+#[cfg(feature = "json-schema")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json-schema")))]
+/// Implements generating a JSON Schema from a [crate::TypeSchema] that matches the output of [into_json].
+pub mod json_schema;
+
+#[cfg(feature = "yaml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
+/// Implements writing the [crate::Value] as YAML text.
+pub mod yaml;
+
+#[cfg(feature = "toml")]
+#[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+/// Implements writing the [crate::Value] as a TOML document.
+pub mod toml;
+
+#[cfg(feature = "ron")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ron")))]
+/// Implements writing the [crate::Value] as RON text which keeps struct and variant names.
+pub mod ron;
+
+#[cfg(feature = "binary")]
+#[cfg_attr(docsrs, doc(cfg(feature = "binary")))]
+/// Implements a compact binary encoding that round-trips every [crate::Value].
+pub mod binary;
+
+#[cfg(any(feature = "msgpack", feature = "cbor"))]
+mod packed;
+
+#[cfg(feature = "msgpack")]
+#[cfg_attr(docsrs, doc(cfg(feature = "msgpack")))]
+/// Implements encoding the [crate::Value] as MessagePack and decoding it back.
+pub mod msgpack;
+
+#[cfg(feature = "cbor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+/// Implements encoding the [crate::Value] as CBOR and decoding it back.
+pub mod cbor;
+
+/// Implements printing the [crate::Value] in a human readable way.
+pub mod pretty;
+
+#[cfg(feature = "tree")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tree")))]
+/// Implements rendering the [crate::Value] as a tree for inspecting it in a terminal.
+pub mod tree;
@@ -0,0 +1,610 @@
+use super::packed::{decoded_tagged, emit_value, into_hashable, Emitter, TypeTag, MAX_DEPTH};
+use crate::json::EnumRepr;
+use crate::*;
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+pub use super::packed::DecodeError;
+
+/// The extension type used for u128 values
+pub const EXT_U128: i8 = 1;
+/// The extension type used for i128 values
+pub const EXT_I128: i8 = 2;
+/// The extension type of enums that keep their names.
+/// The data of the extension is the MessagePack encoding of an array with the name of the enum,
+/// the variant name and the fields.
+pub const EXT_ENUM: i8 = 3;
+/// The extension type of structs that keep their names.
+/// The data of the extension is the MessagePack encoding of an array with the name of the struct
+/// and the fields.
+pub const EXT_STRUCT: i8 = 4;
+/// The extension type of chars. The data is the MessagePack encoding of a string with the char.
+pub const EXT_CHAR: i8 = 5;
+/// The extension type of os strings. The data is the MessagePack encoding of a string
+/// or of binary data with the raw bytes of an os string that isn't valid unicode.
+pub const EXT_OS_STRING: i8 = 6;
+
+/// Options for how to represent certain rust types
+/// as MessagePack
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub struct MsgPackOptions {
+    pub enum_repr: EnumRepr,
+    /// Encodes enums, structs, chars and os strings as the extensions [EXT_ENUM], [EXT_STRUCT],
+    /// [EXT_CHAR] and [EXT_OS_STRING] instead of using the `enum_repr`.
+    /// Values encoded this way are decoded back into the same kind of value.
+    pub tag_types: bool,
+}
+
+/// Encodes the value as MessagePack
+pub fn encode(value: &Value, options: &MsgPackOptions) -> Vec<u8> {
+    let mut buf = Vec::new();
+    to_writer(value, &mut buf, options).expect("writing to a vec cannot fail");
+
+    buf
+}
+
+/// Writes the value as MessagePack into the given writer
+pub fn to_writer<W: Write>(value: &Value, writer: W, options: &MsgPackOptions) -> io::Result<()> {
+    emit_value(
+        &mut MsgPackEmitter {
+            writer,
+            tagged: Vec::new(),
+        },
+        value,
+        &options.enum_repr,
+        options.tag_types,
+    )
+}
+
+/// Decodes a value from the given MessagePack bytes.
+/// The bytes must contain exactly one value.
+pub fn decode(mut bytes: &[u8]) -> Result<Value, DecodeError> {
+    let value = from_reader(&mut bytes)?;
+
+    if bytes.is_empty() {
+        Ok(value)
+    } else {
+        Err(DecodeError::TrailingBytes)
+    }
+}
+
+/// Reads a MessagePack value from the given reader.
+/// Unsigned integers are decoded as [crate::Integer::U64] and signed integers as [crate::Integer::I64].
+/// Binary data is decoded as a list of [crate::Integer::U8].
+pub fn from_reader<R: Read>(reader: R) -> Result<Value, DecodeError> {
+    MsgPackDecoder { reader, depth: 0 }.read_value()
+}
+
+struct MsgPackEmitter<W: Write> {
+    writer: W,
+    /// The extension types and encoded data of the tagged values that are being emitted
+    tagged: Vec<(i8, Vec<u8>)>,
+}
+
+impl<W: Write> MsgPackEmitter<W> {
+    /// Writes into the innermost tagged value or the writer
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self.tagged.last_mut() {
+            Some((_, data)) => {
+                data.extend_from_slice(bytes);
+                Ok(())
+            }
+            None => self.writer.write_all(bytes),
+        }
+    }
+
+    fn write_len(&mut self, len: usize, fix: (u8, usize), markers: [u8; 3]) -> io::Result<()> {
+        let (fix_marker, fix_max) = fix;
+
+        if len <= fix_max {
+            self.write_all(&[fix_marker | len as u8])
+        } else if len <= u8::MAX as usize && markers[0] != 0 {
+            self.write_all(&[markers[0], len as u8])
+        } else if len <= u16::MAX as usize {
+            self.write_all(&[markers[1]])?;
+            self.write_all(&(len as u16).to_be_bytes())
+        } else {
+            self.write_all(&[markers[2]])?;
+            self.write_all(&(len as u32).to_be_bytes())
+        }
+    }
+}
+
+impl<W: Write> Emitter for MsgPackEmitter<W> {
+    fn nil(&mut self) -> io::Result<()> {
+        self.write_all(&[0xc0])
+    }
+
+    fn bool(&mut self, b: bool) -> io::Result<()> {
+        self.write_all(&[if b { 0xc3 } else { 0xc2 }])
+    }
+
+    fn uint(&mut self, n: u64) -> io::Result<()> {
+        if n < 0x80 {
+            self.write_all(&[n as u8])
+        } else if n <= u8::MAX as u64 {
+            self.write_all(&[0xcc, n as u8])
+        } else if n <= u16::MAX as u64 {
+            self.write_all(&[0xcd])?;
+            self.write_all(&(n as u16).to_be_bytes())
+        } else if n <= u32::MAX as u64 {
+            self.write_all(&[0xce])?;
+            self.write_all(&(n as u32).to_be_bytes())
+        } else {
+            self.write_all(&[0xcf])?;
+            self.write_all(&n.to_be_bytes())
+        }
+    }
+
+    fn int(&mut self, n: i64) -> io::Result<()> {
+        if n >= 0 {
+            self.uint(n as u64)
+        } else if n >= -32 {
+            self.write_all(&[n as u8])
+        } else if n >= i8::MIN as i64 {
+            self.write_all(&[0xd0, n as u8])
+        } else if n >= i16::MIN as i64 {
+            self.write_all(&[0xd1])?;
+            self.write_all(&(n as i16).to_be_bytes())
+        } else if n >= i32::MIN as i64 {
+            self.write_all(&[0xd2])?;
+            self.write_all(&(n as i32).to_be_bytes())
+        } else {
+            self.write_all(&[0xd3])?;
+            self.write_all(&n.to_be_bytes())
+        }
+    }
+
+    fn u128(&mut self, n: u128) -> io::Result<()> {
+        self.write_all(&[0xd8, EXT_U128 as u8])?;
+        self.write_all(&n.to_be_bytes())
+    }
+
+    fn i128(&mut self, n: i128) -> io::Result<()> {
+        self.write_all(&[0xd8, EXT_I128 as u8])?;
+        self.write_all(&n.to_be_bytes())
+    }
+
+    fn f32(&mut self, f: f32) -> io::Result<()> {
+        self.write_all(&[0xca])?;
+        self.write_all(&f.to_be_bytes())
+    }
+
+    fn f64(&mut self, f: f64) -> io::Result<()> {
+        self.write_all(&[0xcb])?;
+        self.write_all(&f.to_be_bytes())
+    }
+
+    fn str(&mut self, s: &str) -> io::Result<()> {
+        self.write_len(s.len(), (0xa0, 31), [0xd9, 0xda, 0xdb])?;
+        self.write_all(s.as_bytes())
+    }
+
+    fn array(&mut self, len: usize) -> io::Result<()> {
+        self.write_len(len, (0x90, 15), [0, 0xdc, 0xdd])
+    }
+
+    fn map(&mut self, len: usize) -> io::Result<()> {
+        self.write_len(len, (0x80, 15), [0, 0xde, 0xdf])
+    }
+
+    fn bytes(&mut self, b: &[u8]) -> io::Result<()> {
+        if b.len() <= u8::MAX as usize {
+            self.write_all(&[0xc4, b.len() as u8])?;
+        } else if b.len() <= u16::MAX as usize {
+            self.write_all(&[0xc5])?;
+            self.write_all(&(b.len() as u16).to_be_bytes())?;
+        } else {
+            self.write_all(&[0xc6])?;
+            self.write_all(&(b.len() as u32).to_be_bytes())?;
+        }
+        self.write_all(b)
+    }
+
+    fn begin_tag(&mut self, tag: TypeTag) -> io::Result<()> {
+        let ext_type = match tag {
+            TypeTag::Struct => EXT_STRUCT,
+            TypeTag::Enum => EXT_ENUM,
+            TypeTag::Char => EXT_CHAR,
+            TypeTag::OsString => EXT_OS_STRING,
+        };
+        self.tagged.push((ext_type, Vec::new()));
+
+        Ok(())
+    }
+
+    fn end_tag(&mut self) -> io::Result<()> {
+        let (ext_type, data) = self.tagged.pop().expect("a tagged value was started");
+
+        if data.len() <= u8::MAX as usize {
+            self.write_all(&[0xc7, data.len() as u8, ext_type as u8])?;
+        } else if data.len() <= u16::MAX as usize {
+            self.write_all(&[0xc8])?;
+            self.write_all(&(data.len() as u16).to_be_bytes())?;
+            self.write_all(&[ext_type as u8])?;
+        } else {
+            self.write_all(&[0xc9])?;
+            self.write_all(&(data.len() as u32).to_be_bytes())?;
+            self.write_all(&[ext_type as u8])?;
+        }
+        self.write_all(&data)
+    }
+}
+
+struct MsgPackDecoder<R: Read> {
+    reader: R,
+    depth: usize,
+}
+
+impl<R: Read> MsgPackDecoder<R> {
+    fn read_value(&mut self) -> Result<Value, DecodeError> {
+        let marker = self.read_u8()?;
+
+        let value = match marker {
+            0x00..=0x7f => Value::Primitive(Primitive::Integer(Integer::U64(marker as u64))),
+            0x80..=0x8f => self.read_map((marker & 0x0f) as usize)?,
+            0x90..=0x9f => self.read_array((marker & 0x0f) as usize)?,
+            0xa0..=0xbf => self.read_string((marker & 0x1f) as usize)?,
+            0xc0 => Value::None,
+            0xc2 => Value::Primitive(Primitive::Bool(false)),
+            0xc3 => Value::Primitive(Primitive::Bool(true)),
+            0xc4 => self.read_bin(1)?,
+            0xc5 => self.read_bin(2)?,
+            0xc6 => self.read_bin(4)?,
+            0xc7 => self.read_ext(1)?,
+            0xc8 => self.read_ext(2)?,
+            0xc9 => self.read_ext(4)?,
+            0xca => Value::Primitive(Primitive::Float(Float::F32(f32::from_bits(
+                self.read_uint(4)? as u32,
+            )))),
+            0xcb => Value::Primitive(Primitive::Float(Float::F64(f64::from_bits(
+                self.read_uint(8)?,
+            )))),
+            0xcc..=0xcf => Value::Primitive(Primitive::Integer(Integer::U64(
+                self.read_uint(1 << (marker - 0xcc))?,
+            ))),
+            0xd0..=0xd3 => {
+                let size = 1 << (marker - 0xd0);
+                let n = self.read_uint(size)?;
+                let shift = 64 - size * 8;
+                let n = ((n << shift) as i64) >> shift;
+                Value::Primitive(Primitive::Integer(Integer::I64(n)))
+            }
+            0xd4..=0xd8 => self.read_fixext(1 << (marker - 0xd4))?,
+            0xd9 => {
+                let len = self.read_uint(1)? as usize;
+                self.read_string(len)?
+            }
+            0xda => {
+                let len = self.read_uint(2)? as usize;
+                self.read_string(len)?
+            }
+            0xdb => {
+                let len = self.read_uint(4)? as usize;
+                self.read_string(len)?
+            }
+            0xdc => {
+                let len = self.read_uint(2)? as usize;
+                self.read_array(len)?
+            }
+            0xdd => {
+                let len = self.read_uint(4)? as usize;
+                self.read_array(len)?
+            }
+            0xde => {
+                let len = self.read_uint(2)? as usize;
+                self.read_map(len)?
+            }
+            0xdf => {
+                let len = self.read_uint(4)? as usize;
+                self.read_map(len)?
+            }
+            0xe0..=0xff => Value::Primitive(Primitive::Integer(Integer::I64(marker as i8 as i64))),
+            marker => return Err(DecodeError::UnknownMarker(marker)),
+        };
+
+        Ok(value)
+    }
+
+    fn read_array(&mut self, len: usize) -> Result<Value, DecodeError> {
+        self.enter()?;
+        let mut list = Vec::with_capacity(len.min(1024));
+
+        for _ in 0..len {
+            list.push(self.read_value()?);
+        }
+        self.depth -= 1;
+
+        Ok(Value::List(list))
+    }
+
+    fn read_map(&mut self, len: usize) -> Result<Value, DecodeError> {
+        self.enter()?;
+        let mut map = HashMap::with_capacity(len.min(1024));
+
+        for _ in 0..len {
+            let key = into_hashable(self.read_value()?)?;
+            let value = self.read_value()?;
+            map.insert(key, value);
+        }
+        self.depth -= 1;
+
+        Ok(Value::Map(map))
+    }
+
+    fn read_fixext(&mut self, len: usize) -> Result<Value, DecodeError> {
+        let ext_type = self.read_u8()? as i8;
+        self.read_ext_data(ext_type, len)
+    }
+
+    fn read_ext(&mut self, len_size: usize) -> Result<Value, DecodeError> {
+        let len = self.read_uint(len_size)? as usize;
+        let ext_type = self.read_u8()? as i8;
+        self.read_ext_data(ext_type, len)
+    }
+
+    fn read_ext_data(&mut self, ext_type: i8, len: usize) -> Result<Value, DecodeError> {
+        let data = self.read_bytes(len)?;
+
+        let tag = match ext_type {
+            EXT_U128 | EXT_I128 => {
+                let bytes: [u8; 16] = data.try_into().map_err(|_| DecodeError::IntegerOverflow)?;
+                let integer = if ext_type == EXT_U128 {
+                    Integer::U128(u128::from_be_bytes(bytes))
+                } else {
+                    Integer::I128(i128::from_be_bytes(bytes))
+                };
+                return Ok(Value::Primitive(Primitive::Integer(integer)));
+            }
+            EXT_STRUCT => TypeTag::Struct,
+            EXT_ENUM => TypeTag::Enum,
+            EXT_CHAR => TypeTag::Char,
+            EXT_OS_STRING => TypeTag::OsString,
+            ext_type => return Err(DecodeError::UnknownExtension(ext_type as i64)),
+        };
+        // the data contains exactly one encoded value
+        self.enter()?;
+        let mut decoder = MsgPackDecoder {
+            reader: data.as_slice(),
+            depth: self.depth,
+        };
+        let value = decoder.read_value()?;
+        if !decoder.reader.is_empty() {
+            return Err(DecodeError::InvalidTagged);
+        }
+        self.depth -= 1;
+
+        decoded_tagged(tag, value)
+    }
+
+    fn read_string(&mut self, len: usize) -> Result<Value, DecodeError> {
+        let bytes = self.read_bytes(len)?;
+        let s = String::from_utf8(bytes).map_err(|_| DecodeError::InvalidUtf8)?;
+
+        Ok(Value::Primitive(Primitive::String(s)))
+    }
+
+    /// Binary data is decoded as a list of bytes
+    fn read_bin(&mut self, len_size: usize) -> Result<Value, DecodeError> {
+        let len = self.read_uint(len_size)? as usize;
+        let bytes = self.read_bytes(len)?;
+
+        Ok(Value::List(
+            bytes.into_iter().map(|b| b.into_rusty_value()).collect(),
+        ))
+    }
+
+    fn enter(&mut self) -> Result<(), DecodeError> {
+        self.depth += 1;
+
+        if self.depth > MAX_DEPTH {
+            Err(DecodeError::TooDeep)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        let mut buf = [0u8; 1];
+        self.reader.read_exact(&mut buf)?;
+
+        Ok(buf[0])
+    }
+
+    /// Reads a big endian unsigned integer of the given size
+    fn read_uint(&mut self, size: usize) -> Result<u64, DecodeError> {
+        let mut buf = [0u8; 8];
+        self.reader.read_exact(&mut buf[8 - size..])?;
+
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<Vec<u8>, DecodeError> {
+        let mut bytes = Vec::with_capacity(len.min(4096));
+        let read = (&mut self.reader)
+            .take(len as u64)
+            .read_to_end(&mut bytes)?;
+
+        if read < len {
+            Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+        } else {
+            Ok(bytes)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::json::EnumRepr;
+    use crate::msgpack::{self, DecodeError, MsgPackOptions};
+    use crate::*;
+
+    #[derive(RustyValue)]
+    enum TestEnum {
+        Foo,
+        Bar(u64, String),
+        Baz { value: i64 },
+    }
+
+    fn roundtrip(value: Value, options: &MsgPackOptions) -> Value {
+        msgpack::decode(&msgpack::encode(&value, options)).unwrap()
+    }
+
+    #[test]
+    fn it_encodes_primitives() {
+        let opt = MsgPackOptions::default();
+
+        assert_eq!(msgpack::encode(&1u8.into_rusty_value(), &opt), [0x01]);
+        assert_eq!(msgpack::encode(&(-1i32).into_rusty_value(), &opt), [0xff]);
+        assert_eq!(
+            msgpack::encode(&300u16.into_rusty_value(), &opt),
+            [0xcd, 0x01, 0x2c]
+        );
+        assert_eq!(
+            msgpack::encode(&(-200i64).into_rusty_value(), &opt),
+            [0xd1, 0xff, 0x38]
+        );
+        assert_eq!(
+            msgpack::encode(&"abc".into_rusty_value(), &opt),
+            [0xa3, b'a', b'b', b'c']
+        );
+        assert_eq!(msgpack::encode(&true.into_rusty_value(), &opt), [0xc3]);
+        assert_eq!(msgpack::encode(&().into_rusty_value(), &opt), [0xc0]);
+    }
+
+    #[test]
+    fn it_roundtrips_values() {
+        let opt = MsgPackOptions::default();
+
+        assert_eq!(
+            roundtrip(u64::MAX.into_rusty_value(), &opt),
+            u64::MAX.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(i64::MIN.into_rusty_value(), &opt),
+            i64::MIN.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(u128::MAX.into_rusty_value(), &opt),
+            u128::MAX.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(i128::MIN.into_rusty_value(), &opt),
+            i128::MIN.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(1.5f32.into_rusty_value(), &opt),
+            1.5f32.into_rusty_value()
+        );
+        assert_eq!(
+            roundtrip(1.5f64.into_rusty_value(), &opt),
+            1.5f64.into_rusty_value()
+        );
+
+        let long = "a".repeat(300);
+        assert_eq!(
+            roundtrip(long.clone().into_rusty_value(), &opt),
+            long.into_rusty_value()
+        );
+
+        let map = HashMap::from([
+            (String::from("a"), vec![1u64, 2]),
+            (String::from("b"), vec![]),
+        ]);
+        assert_eq!(
+            roundtrip(map.clone().into_rusty_value(), &opt),
+            map.into_rusty_value()
+        );
+    }
+
+    #[test]
+    fn it_uses_the_enum_repr() {
+        let opt = MsgPackOptions {
+            enum_repr: EnumRepr::ExternallyTagged,
+            ..Default::default()
+        };
+        let value = roundtrip(TestEnum::Baz { value: -1 }.into_rusty_value(), &opt);
+
+        let inner = HashMap::from([(String::from("value"), -1i64)]).into_rusty_value();
+        let expected = Value::Map(HashMap::from([("Baz".into_hashable_rusty_value(), inner)]));
+        assert_eq!(value, expected);
+    }
+
+    #[test]
+    fn it_roundtrips_tagged_types() {
+        let opt = MsgPackOptions {
+            tag_types: true,
+            ..Default::default()
+        };
+
+        for value in [
+            TestEnum::Foo,
+            TestEnum::Bar(1, String::from("a")),
+            TestEnum::Baz { value: i64::MIN },
+        ] {
+            let value = value.into_rusty_value();
+            assert_eq!(roundtrip(value.clone(), &opt), value);
+        }
+        let char = |c| Value::Primitive(Primitive::Char(c));
+        let char_key = HashableValue::Primitive(HashablePrimitive::Char('k'));
+        for value in [
+            rusty_value!(struct Point { x: 1u64, y: None }),
+            rusty_value!(struct Wrapper(-1i64)),
+            rusty_value!(struct Marker),
+            rusty_value!([enum TestEnum::Foo, "a", None]),
+            Value::Map(HashMap::from([(char_key, rusty_value!(struct Marker))])),
+            Value::Primitive(Primitive::OsString("path".into())),
+            char('ü'),
+        ] {
+            assert_eq!(roundtrip(value.clone(), &opt), value);
+        }
+        assert_eq!(
+            roundtrip(char('c'), &Default::default()),
+            "c".into_rusty_value()
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn it_roundtrips_non_unicode_os_strings() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let opt = MsgPackOptions {
+            tag_types: true,
+            ..Default::default()
+        };
+        let value = std::ffi::OsString::from_vec(vec![b'a', 0xff]).into_rusty_value();
+        assert_eq!(roundtrip(value.clone(), &opt), value);
+    }
+
+    #[test]
+    fn it_rejects_invalid_data() {
+        assert!(matches!(
+            msgpack::decode(&[0xc1]),
+            Err(DecodeError::UnknownMarker(0xc1))
+        ));
+        assert!(matches!(
+            msgpack::decode(&[0xa3, b'a']),
+            Err(DecodeError::Io(_))
+        ));
+        assert!(matches!(
+            msgpack::decode(&[0xc0, 0xc0]),
+            Err(DecodeError::TrailingBytes)
+        ));
+        assert!(matches!(
+            msgpack::decode(&[0xd4, 0x7f, 0x00]),
+            Err(DecodeError::UnknownExtension(127))
+        ));
+        assert!(matches!(
+            msgpack::decode(&[0xc7, 0x01, msgpack::EXT_ENUM as u8, 0xc0]),
+            Err(DecodeError::InvalidTagged)
+        ));
+    }
+}
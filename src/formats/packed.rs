@@ -0,0 +1,387 @@
+use crate::json::EnumRepr;
+use crate::*;
+use std::{
+    borrow::Cow,
+    ffi::{OsStr, OsString},
+    fmt::Display,
+    io,
+};
+
+/// The maximum nesting depth that is accepted when decoding
+pub(crate) const MAX_DEPTH: usize = 512;
+
+/// The types that are tagged so they are decoded back into the same kind of value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TypeTag {
+    /// An array of the name and the fields
+    Struct,
+    /// An array of the name, the variant and the fields
+    Enum,
+    /// A string with a single char
+    Char,
+    /// A string or the raw bytes of an os string that isn't valid unicode
+    OsString,
+}
+
+/// The low level operations of a binary format that are used to encode a [crate::Value]
+pub(crate) trait Emitter {
+    fn nil(&mut self) -> io::Result<()>;
+    fn bool(&mut self, b: bool) -> io::Result<()>;
+    fn uint(&mut self, n: u64) -> io::Result<()>;
+    fn int(&mut self, n: i64) -> io::Result<()>;
+    fn u128(&mut self, n: u128) -> io::Result<()>;
+    fn i128(&mut self, n: i128) -> io::Result<()>;
+    fn f32(&mut self, f: f32) -> io::Result<()>;
+    fn f64(&mut self, f: f64) -> io::Result<()>;
+    fn str(&mut self, s: &str) -> io::Result<()>;
+    fn bytes(&mut self, b: &[u8]) -> io::Result<()>;
+    fn array(&mut self, len: usize) -> io::Result<()>;
+    fn map(&mut self, len: usize) -> io::Result<()>;
+    /// Begins a tagged value. Exactly one value is emitted before [Emitter::end_tag] is called.
+    fn begin_tag(&mut self, tag: TypeTag) -> io::Result<()>;
+    fn end_tag(&mut self) -> io::Result<()>;
+}
+
+/// Encodes a value into the emitter.
+/// If `tag_types` is set, structs, enums, chars and os strings are wrapped in a [TypeTag]
+/// that keeps their type instead of using the enum repr.
+pub(crate) fn emit_value<E: Emitter>(
+    e: &mut E,
+    value: &Value,
+    enum_repr: &EnumRepr,
+    tag_types: bool,
+) -> io::Result<()> {
+    match value {
+        Value::Primitive(p) => emit_primitive(e, p, tag_types),
+        Value::Struct(s) if tag_types => {
+            e.begin_tag(TypeTag::Struct)?;
+            e.array(2)?;
+            e.str(&s.name)?;
+            emit_tagged_fields(e, &s.fields, enum_repr)?;
+            e.end_tag()
+        }
+        Value::Struct(s) => match &s.fields {
+            Fields::Named(n) => {
+                e.map(n.len())?;
+                for (key, value) in n {
+                    e.str(key)?;
+                    emit_value(e, value, enum_repr, tag_types)?;
+                }
+                Ok(())
+            }
+            Fields::Unnamed(u) if u.len() == 1 => emit_value(e, &u[0], enum_repr, tag_types),
+            Fields::Unnamed(u) => {
+                e.array(u.len())?;
+                u.iter()
+                    .try_for_each(|v| emit_value(e, v, enum_repr, tag_types))
+            }
+            Fields::Unit => e.str(&s.name),
+        },
+        Value::Enum(en) if tag_types => {
+            e.begin_tag(TypeTag::Enum)?;
+            e.array(3)?;
+            e.str(&en.name)?;
+            e.str(&en.variant)?;
+            emit_tagged_fields(e, &en.fields, enum_repr)?;
+            e.end_tag()
+        }
+        Value::Enum(en) => match enum_repr {
+            EnumRepr::Untagged => emit_enum_fields(e, en, enum_repr),
+            EnumRepr::ExternallyTagged => {
+                e.map(1)?;
+                e.str(&en.variant)?;
+                emit_enum_fields(e, en, enum_repr)
+            }
+            EnumRepr::AdjacentlyTagged {
+                type_field,
+                value_field,
+            } => {
+                e.map(2)?;
+                e.str(type_field)?;
+                e.str(&en.variant)?;
+                e.str(value_field)?;
+                emit_enum_fields(e, en, enum_repr)
+            }
+        },
+        Value::Map(m) => {
+            e.map(m.len())?;
+            for (key, value) in m {
+                emit_hashable(e, key, tag_types)?;
+                emit_value(e, value, enum_repr, tag_types)?;
+            }
+            Ok(())
+        }
+        Value::List(l) => {
+            e.array(l.len())?;
+            l.iter()
+                .try_for_each(|v| emit_value(e, v, enum_repr, tag_types))
+        }
+        Value::None => e.nil(),
+    }
+}
+
+/// Emits the fields of a tagged struct or enum as a map, an array or nil
+fn emit_tagged_fields<E: Emitter>(
+    e: &mut E,
+    fields: &Fields,
+    enum_repr: &EnumRepr,
+) -> io::Result<()> {
+    match fields {
+        Fields::Named(n) => {
+            e.map(n.len())?;
+            for (key, value) in n {
+                e.str(key)?;
+                emit_value(e, value, enum_repr, true)?;
+            }
+            Ok(())
+        }
+        Fields::Unnamed(u) => {
+            e.array(u.len())?;
+            u.iter().try_for_each(|v| emit_value(e, v, enum_repr, true))
+        }
+        Fields::Unit => e.nil(),
+    }
+}
+
+/// Emits the fields of an enum the same way they are represented in JSON
+fn emit_enum_fields<E: Emitter>(e: &mut E, en: &Enum, enum_repr: &EnumRepr) -> io::Result<()> {
+    match &en.fields {
+        Fields::Named(n) => {
+            e.map(n.len())?;
+            for (key, value) in n {
+                e.str(key)?;
+                emit_value(e, value, enum_repr, false)?;
+            }
+            Ok(())
+        }
+        Fields::Unnamed(u) if u.len() == 1 => emit_value(e, &u[0], enum_repr, false),
+        Fields::Unnamed(u) => {
+            e.array(u.len())?;
+            u.iter()
+                .try_for_each(|v| emit_value(e, v, enum_repr, false))
+        }
+        Fields::Unit => e.str(&en.variant),
+    }
+}
+
+fn emit_hashable<E: Emitter>(e: &mut E, value: &HashableValue, tag_types: bool) -> io::Result<()> {
+    match value {
+        HashableValue::Primitive(p) => match p {
+            HashablePrimitive::Integer(i) => emit_integer(e, i),
+            HashablePrimitive::String(s) => e.str(s),
+            HashablePrimitive::OsString(o) => emit_os_str(e, o, tag_types),
+            HashablePrimitive::Char(c) => emit_char(e, *c, tag_types),
+            HashablePrimitive::Bool(b) => e.bool(*b),
+        },
+        HashableValue::List(l) => {
+            e.array(l.len())?;
+            l.iter().try_for_each(|v| emit_hashable(e, v, tag_types))
+        }
+        HashableValue::None => e.nil(),
+    }
+}
+
+fn emit_primitive<E: Emitter>(e: &mut E, primitive: &Primitive, tag_types: bool) -> io::Result<()> {
+    match primitive {
+        Primitive::Integer(i) => emit_integer(e, i),
+        Primitive::Float(Float::F32(f)) => e.f32(*f),
+        Primitive::Float(Float::F64(f)) => e.f64(*f),
+        Primitive::String(s) => e.str(s),
+        Primitive::OsString(o) => emit_os_str(e, o, tag_types),
+        Primitive::Char(c) => emit_char(e, *c, tag_types),
+        Primitive::Bool(b) => e.bool(*b),
+    }
+}
+
+fn emit_char<E: Emitter>(e: &mut E, c: char, tag_types: bool) -> io::Result<()> {
+    if !tag_types {
+        return e.str(c.encode_utf8(&mut [0; 4]));
+    }
+    e.begin_tag(TypeTag::Char)?;
+    e.str(c.encode_utf8(&mut [0; 4]))?;
+    e.end_tag()
+}
+
+/// Emits an os string as a string. If the type is tagged, os strings that aren't valid unicode
+/// are emitted as their raw bytes on unix and lossily converted on other platforms.
+fn emit_os_str<E: Emitter>(e: &mut E, s: &OsStr, tag_types: bool) -> io::Result<()> {
+    if !tag_types {
+        return e.str(&s.to_string_lossy());
+    }
+    e.begin_tag(TypeTag::OsString)?;
+    match s.to_str() {
+        Some(s) => e.str(s)?,
+        #[cfg(unix)]
+        None => {
+            use std::os::unix::ffi::OsStrExt;
+            e.bytes(s.as_bytes())?
+        }
+        #[cfg(not(unix))]
+        None => e.str(&s.to_string_lossy())?,
+    }
+    e.end_tag()
+}
+
+fn emit_integer<E: Emitter>(e: &mut E, integer: &Integer) -> io::Result<()> {
+    match *integer {
+        Integer::USize(n) => e.uint(n as u64),
+        Integer::ISize(n) => e.int(n as i64),
+        Integer::U8(n) => e.uint(n as u64),
+        Integer::I8(n) => e.int(n as i64),
+        Integer::U16(n) => e.uint(n as u64),
+        Integer::I16(n) => e.int(n as i64),
+        Integer::U32(n) => e.uint(n as u64),
+        Integer::I32(n) => e.int(n as i64),
+        Integer::U64(n) => e.uint(n),
+        Integer::I64(n) => e.int(n),
+        Integer::U128(n) => e.u128(n),
+        Integer::I128(n) => e.i128(n),
+    }
+}
+
+/// Converts the decoded content of a [TypeTag] back into the tagged value
+pub(crate) fn decoded_tagged(tag: TypeTag, value: Value) -> Result<Value, DecodeError> {
+    let value = match (tag, value) {
+        (TypeTag::Struct, Value::List(items)) => {
+            let [name, fields] =
+                <[Value; 2]>::try_from(items).map_err(|_| DecodeError::InvalidTagged)?;
+            Value::Struct(Struct {
+                name: Cow::Owned(decoded_string(name)?),
+                fields: decoded_fields(fields)?,
+            })
+        }
+        (TypeTag::Enum, Value::List(items)) => {
+            let [name, variant, fields] =
+                <[Value; 3]>::try_from(items).map_err(|_| DecodeError::InvalidTagged)?;
+            Value::Enum(Enum {
+                name: Cow::Owned(decoded_string(name)?),
+                variant: Cow::Owned(decoded_string(variant)?),
+                fields: decoded_fields(fields)?,
+            })
+        }
+        (TypeTag::Char, value) => {
+            let s = decoded_string(value)?;
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Value::Primitive(Primitive::Char(c)),
+                _ => return Err(DecodeError::InvalidTagged),
+            }
+        }
+        (TypeTag::OsString, Value::Primitive(Primitive::String(s))) => {
+            Value::Primitive(Primitive::OsString(OsString::from(s)))
+        }
+        #[cfg(unix)]
+        (TypeTag::OsString, Value::List(bytes)) => {
+            use std::os::unix::ffi::OsStringExt;
+
+            let bytes = bytes
+                .into_iter()
+                .map(|b| match b {
+                    Value::Primitive(Primitive::Integer(Integer::U8(b))) => Ok(b),
+                    _ => Err(DecodeError::InvalidTagged),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Value::Primitive(Primitive::OsString(OsString::from_vec(bytes)))
+        }
+        _ => return Err(DecodeError::InvalidTagged),
+    };
+
+    Ok(value)
+}
+
+fn decoded_string(value: Value) -> Result<String, DecodeError> {
+    match value {
+        Value::Primitive(Primitive::String(s)) => Ok(s),
+        _ => Err(DecodeError::InvalidTagged),
+    }
+}
+
+/// Converts the decoded fields of a tagged struct or enum back into [crate::Fields]
+fn decoded_fields(fields: Value) -> Result<Fields, DecodeError> {
+    let fields = match fields {
+        Value::Map(m) => {
+            let mut named = std::collections::HashMap::with_capacity(m.len());
+            for (key, value) in m {
+                let HashableValue::Primitive(HashablePrimitive::String(key)) = key else {
+                    return Err(DecodeError::InvalidTagged);
+                };
+                named.insert(Cow::Owned(key), value);
+            }
+            Fields::Named(named)
+        }
+        Value::List(l) => Fields::Unnamed(l),
+        Value::None => Fields::Unit,
+        _ => return Err(DecodeError::InvalidTagged),
+    };
+
+    Ok(fields)
+}
+
+/// Converts a decoded map key into a hashable value
+pub(crate) fn into_hashable(value: Value) -> Result<HashableValue, DecodeError> {
+    let hashable = match value {
+        Value::Primitive(p) => HashableValue::Primitive(match p {
+            Primitive::Integer(i) => HashablePrimitive::Integer(i),
+            Primitive::String(s) => HashablePrimitive::String(s),
+            Primitive::OsString(o) => HashablePrimitive::OsString(o),
+            Primitive::Char(c) => HashablePrimitive::Char(c),
+            Primitive::Bool(b) => HashablePrimitive::Bool(b),
+            Primitive::Float(_) => return Err(DecodeError::UnhashableKey),
+        }),
+        Value::List(l) => {
+            HashableValue::List(l.into_iter().map(into_hashable).collect::<Result<_, _>>()?)
+        }
+        Value::None => HashableValue::None,
+        Value::Struct(_) | Value::Enum(_) | Value::Map(_) => {
+            return Err(DecodeError::UnhashableKey)
+        }
+    };
+
+    Ok(hashable)
+}
+
+/// An error that occurred while decoding a value
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(io::Error),
+    /// The data contains a marker that isn't supported
+    UnknownMarker(u8),
+    /// The data contains an extension type or tag that isn't supported
+    UnknownExtension(i64),
+    /// A string doesn't contain valid UTF-8
+    InvalidUtf8,
+    /// An integer doesn't fit into a 128 bit integer
+    IntegerOverflow,
+    /// A map key contains a value that can't be used as a key
+    UnhashableKey,
+    /// A tagged struct, enum, char or os string doesn't have the expected layout
+    InvalidTagged,
+    /// The value is nested deeper than the decoder allows
+    TooDeep,
+    /// There are bytes left after the value has been decoded
+    TrailingBytes,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Io(e) => e.fmt(f),
+            DecodeError::UnknownMarker(m) => write!(f, "unknown marker {m:#04x}"),
+            DecodeError::UnknownExtension(t) => write!(f, "unknown extension type {t}"),
+            DecodeError::InvalidUtf8 => write!(f, "string is not valid utf-8"),
+            DecodeError::IntegerOverflow => write!(f, "integer overflow"),
+            DecodeError::UnhashableKey => write!(f, "map key can't be used as a key"),
+            DecodeError::InvalidTagged => write!(f, "invalid tagged value"),
+            DecodeError::TooDeep => write!(f, "value is nested too deeply"),
+            DecodeError::TrailingBytes => write!(f, "trailing bytes after value"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl From<io::Error> for DecodeError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
@@ -0,0 +1,484 @@
+use crate::*;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{self, Display, Write},
+};
+
+/// Options for how to print a value in a human readable way
+#[derive(Clone, Debug)]
+pub struct PrettyOptions {
+    /// Writes every item of a collection on its own line indented by the given number of spaces.
+    /// The value is written on a single line if not set.
+    pub indent: Option<usize>,
+    /// Collections that are nested deeper than this are written as `..`
+    pub max_depth: Option<usize>,
+    /// Only the given number of items of lists and maps are written, followed by
+    /// the number of items that were left out.
+    pub max_items: Option<usize>,
+    /// Strings that are longer than the given number of characters are truncated
+    pub max_string_len: Option<usize>,
+    /// Writes the names of structs and enums as well as the type suffix of numbers,
+    /// e.g. `MyStruct { foo: 12u8 }` instead of `{ foo: 12 }`
+    pub type_names: bool,
+    /// Replaces sensitive values with a marker before they are written
+    pub redact: Option<RedactOptions>,
+}
+
+impl Default for PrettyOptions {
+    fn default() -> Self {
+        Self {
+            indent: None,
+            max_depth: None,
+            max_items: None,
+            max_string_len: None,
+            type_names: true,
+            redact: None,
+        }
+    }
+}
+
+impl PrettyOptions {
+    /// Returns options for printing over multiple lines with an indentation of four spaces
+    pub fn multiline() -> Self {
+        Self {
+            indent: Some(4),
+            ..Default::default()
+        }
+    }
+}
+
+/// Writes the value in a human readable way into the given writer
+pub fn to_writer<W: Write>(value: &Value, writer: W, options: &PrettyOptions) -> fmt::Result {
+    let value = match &options.redact {
+        Some(redact) => Cow::Owned(value.redacted(redact)),
+        None => Cow::Borrowed(value),
+    };
+
+    PrettyWriter {
+        writer,
+        options,
+        open: Vec::new(),
+    }
+    .write_value(&value)
+}
+
+/// Converts the value into a human readable string
+pub fn to_string(value: &Value, options: &PrettyOptions) -> String {
+    let mut buf = String::new();
+    to_writer(value, &mut buf, options).expect("writing to a string cannot fail");
+
+    buf
+}
+
+/// A value that is displayed with the given pretty printing options
+pub struct Pretty<'a> {
+    value: &'a Value,
+    options: &'a PrettyOptions,
+}
+
+impl Value {
+    /// Returns a wrapper that displays the value with the given options
+    pub fn pretty<'a>(&'a self, options: &'a PrettyOptions) -> Pretty<'a> {
+        Pretty {
+            value: self,
+            options,
+        }
+    }
+}
+
+impl Display for Pretty<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        to_writer(self.value, f, self.options)
+    }
+}
+
+/// Writes the value with the default [PrettyOptions].
+/// The alternate flag (`{:#}`) writes it over multiple lines.
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if f.alternate() {
+            to_writer(self, f, &PrettyOptions::multiline())
+        } else {
+            to_writer(self, f, &PrettyOptions::default())
+        }
+    }
+}
+
+struct PrettyWriter<'a, W: Write> {
+    writer: W,
+    options: &'a PrettyOptions,
+    /// The collections that are currently open
+    open: Vec<Collection>,
+}
+
+struct Collection {
+    /// Whether the collection is enclosed in braces which are padded with spaces on a single line
+    braced: bool,
+    has_items: bool,
+}
+
+impl<'a, W: Write> PrettyWriter<'a, W> {
+    fn write_value(&mut self, value: &Value) -> fmt::Result {
+        match value {
+            Value::Primitive(p) => self.write_primitive(p),
+            Value::Struct(s) => {
+                if self.options.type_names {
+                    self.writer.write_str(&s.name)?;
+                }
+                self.write_fields(&s.fields, self.options.type_names)
+            }
+            Value::Enum(e) => {
+                if self.options.type_names {
+                    write!(self.writer, "{}::", e.name)?;
+                }
+                self.writer.write_str(&e.variant)?;
+                self.write_fields(&e.fields, true)
+            }
+            Value::Map(m) => {
+                let mut entries = m.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(k, _)| *k);
+
+                if self.begin('{')? {
+                    let len = entries.len();
+                    for (key, value) in entries.into_iter().take(self.item_limit()) {
+                        self.next_item()?;
+                        self.write_key(key)?;
+                        self.writer.write_str(": ")?;
+                        self.write_value(value)?;
+                    }
+                    self.write_omitted(len)?;
+                }
+                self.end('}')
+            }
+            Value::List(l) => {
+                if self.begin('[')? {
+                    for value in l.iter().take(self.item_limit()) {
+                        self.next_item()?;
+                        self.write_value(value)?;
+                    }
+                    self.write_omitted(l.len())?;
+                }
+                self.end(']')
+            }
+            Value::None => self.writer.write_str("None"),
+        }
+    }
+
+    /// Writes the fields of a struct or enum variant.
+    /// Unit fields are only written as `()` if the name before them is omitted.
+    fn write_fields(&mut self, fields: &Fields, named: bool) -> fmt::Result {
+        match fields {
+            Fields::Named(n) => {
+                if named {
+                    self.writer.write_char(' ')?;
+                }
+                if self.begin('{')? {
+                    self.write_named_fields(n)?;
+                }
+                self.end('}')
+            }
+            Fields::Unnamed(u) => {
+                if self.begin('(')? {
+                    for value in u {
+                        self.next_item()?;
+                        self.write_value(value)?;
+                    }
+                }
+                self.end(')')
+            }
+            Fields::Unit if named => Ok(()),
+            Fields::Unit => self.writer.write_str("()"),
+        }
+    }
+
+    fn write_named_fields(&mut self, fields: &HashMap<Cow<'static, str>, Value>) -> fmt::Result {
+        let mut fields = fields.iter().collect::<Vec<_>>();
+        fields.sort_by_key(|(k, _)| *k);
+
+        for (key, value) in fields {
+            self.next_item()?;
+            write!(self.writer, "{key}: ")?;
+            self.write_value(value)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_key(&mut self, key: &HashableValue) -> fmt::Result {
+        match key {
+            HashableValue::Primitive(p) => match p {
+                HashablePrimitive::Integer(i) => self.write_integer(i),
+                HashablePrimitive::String(s) => self.write_str(s),
+                HashablePrimitive::OsString(o) => self.write_str(&o.to_string_lossy()),
+                HashablePrimitive::Char(c) => write!(self.writer, "{c:?}"),
+                HashablePrimitive::Bool(b) => write!(self.writer, "{b}"),
+            },
+            HashableValue::List(l) => {
+                self.writer.write_char('[')?;
+                for (i, key) in l.iter().enumerate() {
+                    if i > 0 {
+                        self.writer.write_str(", ")?;
+                    }
+                    self.write_key(key)?;
+                }
+                self.writer.write_char(']')
+            }
+            HashableValue::None => self.writer.write_str("None"),
+        }
+    }
+
+    fn write_primitive(&mut self, primitive: &Primitive) -> fmt::Result {
+        match primitive {
+            Primitive::Integer(i) => self.write_integer(i),
+            Primitive::Float(Float::F32(f)) => self.write_number(f, "f32"),
+            Primitive::Float(Float::F64(f)) => self.write_number(f, "f64"),
+            Primitive::String(s) => self.write_str(s),
+            Primitive::OsString(o) => self.write_str(&o.to_string_lossy()),
+            Primitive::Char(c) => write!(self.writer, "{c:?}"),
+            Primitive::Bool(b) => write!(self.writer, "{b}"),
+        }
+    }
+
+    fn write_integer(&mut self, integer: &Integer) -> fmt::Result {
+        match integer {
+            Integer::USize(n) => self.write_number(n, "usize"),
+            Integer::ISize(n) => self.write_number(n, "isize"),
+            Integer::U8(n) => self.write_number(n, "u8"),
+            Integer::I8(n) => self.write_number(n, "i8"),
+            Integer::U16(n) => self.write_number(n, "u16"),
+            Integer::I16(n) => self.write_number(n, "i16"),
+            Integer::U32(n) => self.write_number(n, "u32"),
+            Integer::I32(n) => self.write_number(n, "i32"),
+            Integer::U64(n) => self.write_number(n, "u64"),
+            Integer::I64(n) => self.write_number(n, "i64"),
+            Integer::U128(n) => self.write_number(n, "u128"),
+            Integer::I128(n) => self.write_number(n, "i128"),
+        }
+    }
+
+    /// Writes a number using its debug representation so that floats keep their decimal point
+    fn write_number<N: fmt::Debug>(&mut self, n: N, suffix: &str) -> fmt::Result {
+        write!(self.writer, "{n:?}")?;
+        if self.options.type_names {
+            self.writer.write_str(suffix)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.options.max_string_len {
+            Some(max) if s.chars().count() > max => {
+                let end = s.char_indices().nth(max).map(|(i, _)| i).unwrap_or(s.len());
+                write!(self.writer, "\"{}…\"", s[..end].escape_debug())
+            }
+            _ => write!(self.writer, "\"{}\"", s.escape_debug()),
+        }
+    }
+
+    fn item_limit(&self) -> usize {
+        self.options.max_items.unwrap_or(usize::MAX)
+    }
+
+    /// Writes the number of items that were left out because of the item limit
+    fn write_omitted(&mut self, len: usize) -> fmt::Result {
+        let limit = self.item_limit();
+
+        if len > limit {
+            self.next_item()?;
+            write!(self.writer, ".. {} more", len - limit)?;
+        }
+
+        Ok(())
+    }
+
+    /// Opens a collection and returns whether its items should be written.
+    /// If the maximum depth has been reached the items are replaced with `..`.
+    fn begin(&mut self, bracket: char) -> Result<bool, fmt::Error> {
+        let braced = bracket == '{';
+        self.writer.write_char(bracket)?;
+
+        let too_deep = self.options.max_depth.is_some_and(|d| self.open.len() >= d);
+        if too_deep {
+            self.writer.write_str(if braced { " .. " } else { ".." })?;
+        }
+        self.open.push(Collection {
+            braced,
+            has_items: false,
+        });
+
+        Ok(!too_deep)
+    }
+
+    fn end(&mut self, bracket: char) -> fmt::Result {
+        if let Some(Collection {
+            braced,
+            has_items: true,
+        }) = self.open.pop()
+        {
+            if self.options.indent.is_some() {
+                self.writer.write_char(',')?;
+                self.write_newline()?;
+            } else if braced {
+                self.writer.write_char(' ')?;
+            }
+        }
+        self.writer.write_char(bracket)
+    }
+
+    /// Writes the separator and indentation for the next item of the current collection
+    fn next_item(&mut self) -> fmt::Result {
+        if let Some(collection) = self.open.last_mut() {
+            let separate = collection.has_items;
+            let braced = collection.braced;
+            collection.has_items = true;
+
+            if self.options.indent.is_some() {
+                if separate {
+                    self.writer.write_char(',')?;
+                }
+                self.write_newline()?;
+            } else if separate {
+                self.writer.write_str(", ")?;
+            } else if braced {
+                self.writer.write_char(' ')?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_newline(&mut self) -> fmt::Result {
+        if let Some(indent) = self.options.indent {
+            self.writer.write_char('\n')?;
+            for _ in 0..self.open.len() * indent {
+                self.writer.write_char(' ')?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::pretty::{self, PrettyOptions};
+    use crate::*;
+
+    #[derive(RustyValue)]
+    struct TestStruct {
+        foo: String,
+        bar: u8,
+        baz: Vec<TestEnum>,
+    }
+
+    #[derive(RustyValue)]
+    struct TestTuple(f32, Option<u64>);
+
+    #[derive(RustyValue)]
+    struct TestUnit;
+
+    #[derive(RustyValue)]
+    enum TestEnum {
+        Foo,
+        Bar(i8),
+        Baz { value: bool },
+    }
+
+    fn test_struct() -> TestStruct {
+        TestStruct {
+            foo: String::from("hi"),
+            bar: 12,
+            baz: vec![TestEnum::Foo, TestEnum::Bar(-1)],
+        }
+    }
+
+    #[test]
+    fn it_displays_values() {
+        assert_eq!(
+            test_struct().into_rusty_value().to_string(),
+            r#"TestStruct { bar: 12u8, baz: [TestEnum::Foo, TestEnum::Bar(-1i8)], foo: "hi" }"#
+        );
+        assert_eq!(
+            TestTuple(1.0, None).into_rusty_value().to_string(),
+            "TestTuple(1.0f32, None)"
+        );
+        assert_eq!(TestUnit.into_rusty_value().to_string(), "TestUnit");
+        assert_eq!(
+            TestEnum::Baz { value: true }.into_rusty_value().to_string(),
+            "TestEnum::Baz { value: true }"
+        );
+        assert_eq!(
+            HashMap::from([("b", 2u16), ("a", 1u16)])
+                .into_rusty_value()
+                .to_string(),
+            r#"{ "a": 1u16, "b": 2u16 }"#
+        );
+        assert_eq!(Vec::<u8>::new().into_rusty_value().to_string(), "[]");
+    }
+
+    #[test]
+    fn it_displays_multiline_values() {
+        assert_eq!(
+            format!("{:#}", test_struct().into_rusty_value()),
+            r#"TestStruct {
+    bar: 12u8,
+    baz: [
+        TestEnum::Foo,
+        TestEnum::Bar(
+            -1i8,
+        ),
+    ],
+    foo: "hi",
+}"#
+        );
+    }
+
+    #[test]
+    fn it_hides_type_names() {
+        let opt = PrettyOptions {
+            type_names: false,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            pretty::to_string(&test_struct().into_rusty_value(), &opt),
+            r#"{ bar: 12, baz: [Foo, Bar(-1)], foo: "hi" }"#
+        );
+        assert_eq!(pretty::to_string(&TestUnit.into_rusty_value(), &opt), "()");
+    }
+
+    #[test]
+    fn it_limits_the_output() {
+        let opt = PrettyOptions {
+            max_depth: Some(1),
+            max_items: Some(2),
+            max_string_len: Some(3),
+            ..Default::default()
+        };
+        let value = TestStruct {
+            foo: String::from("hello"),
+            baz: vec![TestEnum::Foo, TestEnum::Foo, TestEnum::Foo],
+            ..test_struct()
+        }
+        .into_rusty_value();
+
+        assert_eq!(
+            value.pretty(&opt).to_string(),
+            r#"TestStruct { bar: 12u8, baz: [..], foo: "hel…" }"#
+        );
+        assert_eq!(
+            pretty::to_string(&vec![1u8, 2, 3, 4].into_rusty_value(), &opt),
+            "[1u8, 2u8, .. 2 more]"
+        );
+        assert_eq!(
+            pretty::to_string(&vec![test_struct()].into_rusty_value(), &opt),
+            "[TestStruct { .. }]"
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use crate::*;
+use std::{borrow::Cow, collections::HashMap, io};
+
+/// Options for how to write RON
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub struct RonOptions {
+    /// Writes every item of a collection on its own line with the given indentation.
+    /// The value is written on a single line if not set.
+    pub indent: Option<String>,
+    /// Omits the names of structs, e.g. `(foo: 1)` instead of `MyStruct(foo: 1)`
+    pub omit_struct_names: bool,
+    /// Enables the `implicit_some` extension in the output.
+    /// [crate::Value] doesn't distinguish between `Some(value)` and `value`, so without this extension
+    /// optional values are written without the `Some(..)` wrapper that is expected when parsing them.
+    pub implicit_some: bool,
+}
+
+impl RonOptions {
+    /// Returns options for pretty printing with an indentation of four spaces
+    pub fn pretty() -> Self {
+        Self {
+            indent: Some(String::from("    ")),
+            ..Default::default()
+        }
+    }
+}
+
+/// Writes the value as RON into the given writer
+pub fn to_writer<W: io::Write>(value: &Value, writer: W, options: &RonOptions) -> io::Result<()> {
+    let mut writer = RonWriter {
+        writer,
+        options,
+        open: Vec::new(),
+    };
+    if options.implicit_some {
+        writer.writer.write_all(b"#![enable(implicit_some)]")?;
+        writer.write_newline()?;
+    }
+
+    writer.write_value(value)
+}
+
+/// Converts the value into a RON string
+pub fn to_string(value: &Value, options: &RonOptions) -> String {
+    let mut buf = Vec::new();
+    to_writer(value, &mut buf, options).expect("writing to a vec cannot fail");
+
+    String::from_utf8(buf).expect("the ron writer only emits valid utf-8")
+}
+
+struct RonWriter<'a, W: io::Write> {
+    writer: W,
+    options: &'a RonOptions,
+    /// Whether the collections that are currently open already contain an item
+    open: Vec<bool>,
+}
+
+impl<'a, W: io::Write> RonWriter<'a, W> {
+    fn write_value(&mut self, value: &Value) -> io::Result<()> {
+        match value {
+            Value::Primitive(p) => self.write_primitive(p),
+            Value::Struct(s) => {
+                if !self.options.omit_struct_names {
+                    self.writer.write_all(s.name.as_bytes())?;
+                }
+                if self.options.omit_struct_names || !matches!(s.fields, Fields::Unit) {
+                    self.write_fields(&s.fields)?;
+                }
+                Ok(())
+            }
+            Value::Enum(e) => {
+                self.writer.write_all(e.variant.as_bytes())?;
+                if !matches!(e.fields, Fields::Unit) {
+                    self.write_fields(&e.fields)?;
+                }
+                Ok(())
+            }
+            Value::Map(m) => {
+                let mut entries = m.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(k, _)| *k);
+
+                self.begin(b'{')?;
+                for (key, value) in entries {
+                    self.next_item()?;
+                    self.write_key(key)?;
+                    self.writer.write_all(b": ")?;
+                    self.write_value(value)?;
+                }
+                self.end(b'}')
+            }
+            Value::List(l) => {
+                self.begin(b'[')?;
+                for value in l {
+                    self.next_item()?;
+                    self.write_value(value)?;
+                }
+                self.end(b']')
+            }
+            Value::None => self.writer.write_all(b"None"),
+        }
+    }
+
+    /// Writes the fields of a struct or enum variant as a tuple
+    fn write_fields(&mut self, fields: &Fields) -> io::Result<()> {
+        self.begin(b'(')?;
+        match fields {
+            Fields::Named(n) => self.write_named_fields(n)?,
+            Fields::Unnamed(u) => {
+                for value in u {
+                    self.next_item()?;
+                    self.write_value(value)?;
+                }
+            }
+            Fields::Unit => {}
+        }
+        self.end(b')')
+    }
+
+    fn write_named_fields(&mut self, fields: &HashMap<Cow<'static, str>, Value>) -> io::Result<()> {
+        let mut fields = fields.iter().collect::<Vec<_>>();
+        fields.sort_by_key(|(k, _)| *k);
+
+        for (key, value) in fields {
+            self.next_item()?;
+            write!(self.writer, "{key}: ")?;
+            self.write_value(value)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_key(&mut self, key: &HashableValue) -> io::Result<()> {
+        match key {
+            HashableValue::Primitive(p) => match p {
+                HashablePrimitive::Integer(i) => write!(self.writer, "{i}"),
+                HashablePrimitive::String(s) => write!(self.writer, "\"{}\"", s.escape_debug()),
+                HashablePrimitive::OsString(o) => {
+                    write!(self.writer, "\"{}\"", o.to_string_lossy().escape_debug())
+                }
+                HashablePrimitive::Char(c) => write!(self.writer, "'{}'", c.escape_debug()),
+                HashablePrimitive::Bool(b) => write!(self.writer, "{b}"),
+            },
+            HashableValue::List(l) => {
+                self.writer.write_all(b"[")?;
+                for (i, key) in l.iter().enumerate() {
+                    if i > 0 {
+                        self.writer.write_all(b", ")?;
+                    }
+                    self.write_key(key)?;
+                }
+                self.writer.write_all(b"]")
+            }
+            HashableValue::None => self.writer.write_all(b"None"),
+        }
+    }
+
+    fn write_primitive(&mut self, primitive: &Primitive) -> io::Result<()> {
+        match primitive {
+            Primitive::Integer(i) => write!(self.writer, "{i}"),
+            Primitive::Float(f) => {
+                let f = match f {
+                    Float::F32(f) => *f as f64,
+                    Float::F64(f) => *f,
+                };
+                if f.is_nan() {
+                    self.writer.write_all(b"NaN")
+                } else {
+                    write!(self.writer, "{f:?}")
+                }
+            }
+            Primitive::String(s) => write!(self.writer, "\"{}\"", s.escape_debug()),
+            Primitive::OsString(o) => {
+                write!(self.writer, "\"{}\"", o.to_string_lossy().escape_debug())
+            }
+            Primitive::Char(c) => write!(self.writer, "'{}'", c.escape_debug()),
+            Primitive::Bool(b) => write!(self.writer, "{b}"),
+        }
+    }
+
+    fn begin(&mut self, bracket: u8) -> io::Result<()> {
+        self.open.push(false);
+        self.writer.write_all(&[bracket])
+    }
+
+    fn end(&mut self, bracket: u8) -> io::Result<()> {
+        if self.open.pop() == Some(true) && self.options.indent.is_some() {
+            self.writer.write_all(b",")?;
+            self.write_newline()?;
+        }
+        self.writer.write_all(&[bracket])
+    }
+
+    /// Writes the separator and indentation for the next item of the current collection
+    fn next_item(&mut self) -> io::Result<()> {
+        if let Some(has_items) = self.open.last_mut() {
+            let separate = *has_items;
+            *has_items = true;
+
+            if self.options.indent.is_some() {
+                if separate {
+                    self.writer.write_all(b",")?;
+                }
+                self.write_newline()?;
+            } else if separate {
+                self.writer.write_all(b", ")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_newline(&mut self) -> io::Result<()> {
+        if let Some(indent) = &self.options.indent {
+            self.writer.write_all(b"\n")?;
+            for _ in 0..self.open.len() {
+                self.writer.write_all(indent.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::ron::{self, RonOptions};
+    use crate::*;
+
+    #[derive(RustyValue)]
+    struct TestStruct {
+        foo: u8,
+        bar: Vec<TestEnum>,
+        baz: Option<String>,
+    }
+
+    #[derive(RustyValue)]
+    struct TestTuple(String, f32);
+
+    #[derive(RustyValue)]
+    struct TestUnit;
+
+    #[derive(RustyValue)]
+    enum TestEnum {
+        Foo,
+        Bar(u8),
+        Baz { value: String },
+    }
+
+    fn test_struct() -> TestStruct {
+        TestStruct {
+            foo: 1,
+            bar: vec![
+                TestEnum::Foo,
+                TestEnum::Bar(2),
+                TestEnum::Baz {
+                    value: String::from("\"hi\""),
+                },
+            ],
+            baz: None,
+        }
+    }
+
+    #[test]
+    fn it_writes_compact_ron() {
+        assert_eq!(
+            ron::to_string(&test_struct().into_rusty_value(), &RonOptions::default()),
+            r#"TestStruct(bar: [Foo, Bar(2), Baz(value: "\"hi\"")], baz: None, foo: 1)"#
+        );
+        assert_eq!(
+            ron::to_string(
+                &TestTuple(String::from("a"), 1.0).into_rusty_value(),
+                &RonOptions::default()
+            ),
+            r#"TestTuple("a", 1.0)"#
+        );
+        assert_eq!(
+            ron::to_string(&TestUnit.into_rusty_value(), &RonOptions::default()),
+            "TestUnit"
+        );
+    }
+
+    #[test]
+    fn it_omits_struct_names() {
+        let opt = RonOptions {
+            omit_struct_names: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            ron::to_string(&TestTuple(String::from("a"), 1.0).into_rusty_value(), &opt),
+            r#"("a", 1.0)"#
+        );
+        assert_eq!(ron::to_string(&TestUnit.into_rusty_value(), &opt), "()");
+    }
+
+    #[test]
+    fn it_writes_pretty_ron() {
+        let opt = RonOptions {
+            implicit_some: true,
+            ..RonOptions::pretty()
+        };
+        let value = TestStruct {
+            baz: Some(String::from("c")),
+            bar: vec![TestEnum::Bar(2)],
+            ..test_struct()
+        };
+
+        assert_eq!(
+            ron::to_string(&value.into_rusty_value(), &opt),
+            r#"#![enable(implicit_some)]
+TestStruct(
+    bar: [
+        Bar(
+            2,
+        ),
+    ],
+    baz: "c",
+    foo: 1,
+)"#
+        );
+    }
+
+    #[test]
+    fn it_writes_maps() {
+        let value = HashMap::from([(1u8, "a"), (2u8, "b")]).into_rusty_value();
+
+        assert_eq!(
+            ron::to_string(&value, &RonOptions::default()),
+            r#"{1: "a", 2: "b"}"#
+        );
+    }
+}
@@ -0,0 +1,564 @@
+use crate::json::{hashable_to_string, EnumRepr};
+use crate::*;
+use std::{fmt::Display, io};
+
+/// Options for how to represent certain rust types
+/// as TOML
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub struct TomlOptions {
+    pub enum_repr: EnumRepr,
+    pub spec: TomlSpec,
+    /// Skips named fields and map entries that have a value of [crate::Value::None]
+    /// instead of reporting an error
+    pub skip_none_fields: bool,
+}
+
+/// The version of the TOML spec the output has to conform to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub enum TomlSpec {
+    /// Arrays must only contain values of the same type
+    V0_5,
+    #[default]
+    V1_0,
+}
+
+/// An error that occurred while converting a value into TOML
+#[derive(Debug)]
+pub enum TomlError {
+    /// The top level value isn't represented as a table
+    NotATable,
+    /// TOML has no representation for [crate::Value::None]
+    NoneValue {
+        path: String,
+    },
+    /// TOML integers are limited to the range of an i64
+    IntegerOverflow {
+        path: String,
+    },
+    /// The array contains values of different types which isn't allowed in [TomlSpec::V0_5]
+    MixedArray {
+        path: String,
+    },
+    /// Multiple keys of the map are written as the same string, e.g. `1u8` and `"1"`
+    DuplicateKey {
+        path: String,
+    },
+    Io(io::Error),
+}
+
+impl Display for TomlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TomlError::NotATable => write!(f, "the top level value must be a table"),
+            TomlError::NoneValue { path } => write!(f, "cannot represent none at `{path}`"),
+            TomlError::IntegerOverflow { path } => {
+                write!(f, "the integer at `{path}` doesn't fit into an i64")
+            }
+            TomlError::MixedArray { path } => {
+                write!(
+                    f,
+                    "the array at `{path}` contains values of different types"
+                )
+            }
+            TomlError::DuplicateKey { path } => {
+                write!(f, "multiple keys of the map are written as `{path}`")
+            }
+            TomlError::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TomlError {}
+
+impl From<io::Error> for TomlError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+/// Writes the value as a TOML document into the given writer.
+/// The value needs to be represented as a table, e.g. a struct with named fields or a map.
+pub fn to_writer<W: io::Write>(
+    value: &Value,
+    mut writer: W,
+    options: &TomlOptions,
+) -> Result<(), TomlError> {
+    match into_item(value, String::new(), options)? {
+        Some(Item::Table(entries)) => write_table(&mut writer, "", &entries, &mut false),
+        _ => Err(TomlError::NotATable),
+    }
+}
+
+/// Converts the value into a TOML document
+pub fn to_string(value: &Value, options: &TomlOptions) -> Result<String, TomlError> {
+    let mut buf = Vec::new();
+    to_writer(value, &mut buf, options)?;
+
+    Ok(String::from_utf8(buf).expect("the toml writer only emits valid utf-8"))
+}
+
+/// The kind of a TOML value
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    String,
+    Integer,
+    Float,
+    Bool,
+}
+
+/// An intermediate representation of a TOML value
+enum Item {
+    /// An already formatted scalar
+    Scalar(Kind, String),
+    Array(Vec<Item>),
+    Table(Vec<(String, Item)>),
+}
+
+impl Item {
+    fn is_table(&self) -> bool {
+        matches!(self, Item::Table(_))
+    }
+
+    fn is_array_of_tables(&self) -> bool {
+        matches!(self, Item::Array(a) if !a.is_empty() && a.iter().all(Item::is_table))
+    }
+
+    fn same_kind(&self, other: &Item) -> bool {
+        match (self, other) {
+            (Item::Scalar(a, _), Item::Scalar(b, _)) => a == b,
+            (Item::Array(_), Item::Array(_)) | (Item::Table(_), Item::Table(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Converts the value into an item. Returns `None` for values that should be skipped
+fn into_item(value: &Value, path: String, opt: &TomlOptions) -> Result<Option<Item>, TomlError> {
+    let item = match value {
+        Value::Primitive(p) => primitive_into_item(p, path)?,
+        Value::Struct(s) => fields_into_item(&s.fields, &s.name, path, opt)?,
+        Value::Enum(e) => {
+            let fields = fields_into_item(&e.fields, &e.variant, path.clone(), opt)?;
+
+            match &opt.enum_repr {
+                EnumRepr::Untagged => fields,
+                EnumRepr::ExternallyTagged => Item::Table(vec![(e.variant.to_string(), fields)]),
+                EnumRepr::AdjacentlyTagged {
+                    type_field,
+                    value_field,
+                } => Item::Table(vec![
+                    (type_field.to_owned(), string_item(&e.variant)),
+                    (value_field.to_owned(), fields),
+                ]),
+            }
+        }
+        Value::Map(m) => {
+            let mut entries = Vec::with_capacity(m.len());
+
+            for (key, value) in m {
+                let key = hashable_to_string(key);
+                if let Some(item) = into_item(value, join_key(&path, &key), opt)? {
+                    entries.push((key, item));
+                }
+            }
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            if let Some(pair) = entries.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+                return Err(TomlError::DuplicateKey {
+                    path: join_key(&path, &pair[0].0),
+                });
+            }
+
+            Item::Table(entries)
+        }
+        Value::List(l) => list_into_item(l, path, opt)?,
+        Value::None if opt.skip_none_fields => return Ok(None),
+        Value::None => return Err(TomlError::NoneValue { path }),
+    };
+
+    Ok(Some(item))
+}
+
+fn fields_into_item(
+    fields: &Fields,
+    name: &str,
+    path: String,
+    opt: &TomlOptions,
+) -> Result<Item, TomlError> {
+    match fields {
+        Fields::Named(n) => {
+            let mut entries = Vec::with_capacity(n.len());
+
+            for (key, value) in n {
+                if let Some(item) = into_item(value, join_key(&path, key), opt)? {
+                    entries.push((key.to_string(), item));
+                }
+            }
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            Ok(Item::Table(entries))
+        }
+        Fields::Unnamed(u) if u.len() == 1 => {
+            into_item(&u[0], path.clone(), opt)?.ok_or(TomlError::NoneValue { path })
+        }
+        Fields::Unnamed(u) => list_into_item(u, path, opt),
+        Fields::Unit => Ok(string_item(name)),
+    }
+}
+
+fn list_into_item(list: &[Value], path: String, opt: &TomlOptions) -> Result<Item, TomlError> {
+    let mut items = Vec::with_capacity(list.len());
+
+    for (i, value) in list.iter().enumerate() {
+        let item_path = format!("{path}[{i}]");
+        let item = into_item(value, item_path.clone(), opt)?
+            .ok_or(TomlError::NoneValue { path: item_path })?;
+        items.push(item);
+    }
+    if opt.spec == TomlSpec::V0_5 && items.windows(2).any(|w| !w[0].same_kind(&w[1])) {
+        return Err(TomlError::MixedArray { path });
+    }
+
+    Ok(Item::Array(items))
+}
+
+fn primitive_into_item(primitive: &Primitive, path: String) -> Result<Item, TomlError> {
+    let item = match primitive {
+        Primitive::Integer(i) => {
+            let n = match *i {
+                Integer::USize(n) => i64::try_from(n).ok(),
+                Integer::ISize(n) => i64::try_from(n).ok(),
+                Integer::U8(n) => Some(n as i64),
+                Integer::I8(n) => Some(n as i64),
+                Integer::U16(n) => Some(n as i64),
+                Integer::I16(n) => Some(n as i64),
+                Integer::U32(n) => Some(n as i64),
+                Integer::I32(n) => Some(n as i64),
+                Integer::U64(n) => i64::try_from(n).ok(),
+                Integer::I64(n) => Some(n),
+                Integer::U128(n) => i64::try_from(n).ok(),
+                Integer::I128(n) => i64::try_from(n).ok(),
+            };
+            let n = n.ok_or(TomlError::IntegerOverflow { path })?;
+            Item::Scalar(Kind::Integer, n.to_string())
+        }
+        Primitive::Float(f) => {
+            let f = match f {
+                Float::F32(f) => *f as f64,
+                Float::F64(f) => *f,
+            };
+            let repr = if f.is_nan() {
+                String::from("nan")
+            } else if f.is_infinite() {
+                String::from(if f > 0.0 { "inf" } else { "-inf" })
+            } else {
+                format!("{f:?}")
+            };
+            Item::Scalar(Kind::Float, repr)
+        }
+        Primitive::String(s) => string_item(s),
+        Primitive::OsString(o) => string_item(&o.to_string_lossy()),
+        Primitive::Char(c) => string_item(c.encode_utf8(&mut [0; 4])),
+        Primitive::Bool(b) => Item::Scalar(Kind::Bool, b.to_string()),
+    };
+
+    Ok(item)
+}
+
+fn string_item(s: &str) -> Item {
+    Item::Scalar(Kind::String, quote(s))
+}
+
+/// Writes the entries of a table. Values are written before nested tables
+/// as they would be part of the last table header otherwise
+fn write_table<W: io::Write>(
+    writer: &mut W,
+    header: &str,
+    entries: &[(String, Item)],
+    written: &mut bool,
+) -> Result<(), TomlError> {
+    let (tables, values): (Vec<_>, Vec<_>) = entries
+        .iter()
+        .partition(|(_, item)| item.is_table() || item.is_array_of_tables());
+
+    for (key, item) in values {
+        write!(writer, "{} = ", format_key(key))?;
+        write_inline(writer, item)?;
+        writeln!(writer)?;
+        *written = true;
+    }
+
+    for (key, item) in tables {
+        let path = if header.is_empty() {
+            format_key(key)
+        } else {
+            format!("{header}.{}", format_key(key))
+        };
+
+        match item {
+            Item::Table(entries) => {
+                write_header(writer, &format!("[{path}]"), written)?;
+                write_table(writer, &path, entries, written)?;
+            }
+            Item::Array(tables) => {
+                for table in tables {
+                    if let Item::Table(entries) = table {
+                        write_header(writer, &format!("[[{path}]]"), written)?;
+                        write_table(writer, &path, entries, written)?;
+                    }
+                }
+            }
+            Item::Scalar(..) => unreachable!("scalars are written as values"),
+        }
+    }
+
+    Ok(())
+}
+
+fn write_header<W: io::Write>(writer: &mut W, header: &str, written: &mut bool) -> io::Result<()> {
+    if *written {
+        writeln!(writer)?;
+    }
+    *written = true;
+
+    writeln!(writer, "{header}")
+}
+
+fn write_inline<W: io::Write>(writer: &mut W, item: &Item) -> io::Result<()> {
+    match item {
+        Item::Scalar(_, s) => writer.write_all(s.as_bytes()),
+        Item::Array(items) => {
+            writer.write_all(b"[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b", ")?;
+                }
+                write_inline(writer, item)?;
+            }
+            writer.write_all(b"]")
+        }
+        Item::Table(entries) if entries.is_empty() => writer.write_all(b"{}"),
+        Item::Table(entries) => {
+            writer.write_all(b"{ ")?;
+            for (i, (key, item)) in entries.iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b", ")?;
+                }
+                write!(writer, "{} = ", format_key(key))?;
+                write_inline(writer, item)?;
+            }
+            writer.write_all(b" }")
+        }
+    }
+}
+
+fn join_key(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Formats a key as a bare key if possible and as a quoted key otherwise
+fn format_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if is_bare {
+        key.to_owned()
+    } else {
+        quote(key)
+    }
+}
+
+/// Formats a string as a basic TOML string
+fn quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use crate as rusty_value;
+    use crate::toml::{self, TomlError, TomlOptions, TomlSpec};
+    use crate::*;
+
+    #[derive(RustyValue)]
+    struct Config {
+        name: String,
+        port: u16,
+        database: Database,
+        servers: Vec<Server>,
+    }
+
+    #[derive(RustyValue)]
+    struct Database {
+        url: String,
+        timeout: f32,
+    }
+
+    #[derive(RustyValue)]
+    struct Server {
+        host: String,
+        tags: Vec<String>,
+    }
+
+    fn config() -> Config {
+        Config {
+            name: String::from("test \"config\""),
+            port: 8080,
+            database: Database {
+                url: String::from("postgres://localhost"),
+                timeout: 1.5,
+            },
+            servers: vec![
+                Server {
+                    host: String::from("a"),
+                    tags: vec![String::from("x")],
+                },
+                Server {
+                    host: String::from("b"),
+                    tags: vec![],
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn it_writes_tables() {
+        let toml = toml::to_string(&config().into_rusty_value(), &TomlOptions::default()).unwrap();
+
+        assert_eq!(
+            toml,
+            r#"name = "test \"config\""
+port = 8080
+
+[database]
+timeout = 1.5
+url = "postgres://localhost"
+
+[[servers]]
+host = "a"
+tags = ["x"]
+
+[[servers]]
+host = "b"
+tags = []
+"#
+        );
+    }
+
+    #[test]
+    fn it_rejects_non_tables() {
+        let result = toml::to_string(&12u8.into_rusty_value(), &TomlOptions::default());
+
+        assert!(matches!(result, Err(TomlError::NotATable)));
+    }
+
+    #[derive(RustyValue)]
+    struct Unrepresentable {
+        big: u128,
+        optional: Option<u8>,
+    }
+
+    #[test]
+    fn it_reports_unrepresentable_values() {
+        let value = Unrepresentable {
+            big: u128::MAX,
+            optional: Some(1),
+        }
+        .into_rusty_value();
+        let result = toml::to_string(&value, &TomlOptions::default());
+        assert!(matches!(result, Err(TomlError::IntegerOverflow { path }) if path == "big"));
+
+        let value = Unrepresentable {
+            big: 1,
+            optional: None,
+        }
+        .into_rusty_value();
+        let result = toml::to_string(&value, &TomlOptions::default());
+        assert!(matches!(result, Err(TomlError::NoneValue { path }) if path == "optional"));
+
+        let opt = TomlOptions {
+            skip_none_fields: true,
+            ..Default::default()
+        };
+        assert_eq!(toml::to_string(&value, &opt).unwrap(), "big = 1\n");
+    }
+
+    #[test]
+    fn it_rejects_keys_that_are_written_the_same() {
+        let value = rusty_value!({ "ports" => { 1u8 => "a", 1u16 => "b" } });
+
+        assert!(matches!(
+            toml::to_string(&value, &TomlOptions::default()),
+            Err(TomlError::DuplicateKey { path }) if path == "ports.1"
+        ));
+    }
+
+    #[test]
+    fn it_rejects_mixed_arrays_in_old_spec() {
+        let value = Value::Map(
+            [(
+                "mixed".into_hashable_rusty_value(),
+                Value::List(vec![1u8.into_rusty_value(), "a".into_rusty_value()]),
+            )]
+            .into_iter()
+            .collect(),
+        );
+        let opt = TomlOptions {
+            spec: TomlSpec::V0_5,
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            toml::to_string(&value, &opt),
+            Err(TomlError::MixedArray { path }) if path == "mixed"
+        ));
+        assert_eq!(
+            toml::to_string(&value, &TomlOptions::default()).unwrap(),
+            "mixed = [1, \"a\"]\n"
+        );
+    }
+
+    #[derive(RustyValue)]
+    struct Nested {
+        database: Database,
+    }
+
+    #[test]
+    fn it_writes_documents_starting_with_tables() {
+        let value = Nested {
+            database: Database {
+                url: String::from("sqlite://"),
+                timeout: 2.0,
+            },
+        }
+        .into_rusty_value();
+
+        assert_eq!(
+            toml::to_string(&value, &TomlOptions::default()).unwrap(),
+            "[database]\ntimeout = 2.0\nurl = \"sqlite://\"\n"
+        );
+    }
+}
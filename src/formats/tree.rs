@@ -0,0 +1,296 @@
+use crate::*;
+use std::io;
+
+const RESET: &str = "\x1b[0m";
+/// Style for struct and enum names
+const TYPE_STYLE: &str = "\x1b[1;36m";
+/// Style for field names, list indices and map keys
+const KEY_STYLE: &str = "\x1b[34m";
+/// Style for primitive values
+const VALUE_STYLE: &str = "\x1b[32m";
+/// Style for the tree lines and the kinds of values
+const DIM_STYLE: &str = "\x1b[2m";
+
+/// Options for how to render a value as a tree
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub struct TreeOptions {
+    /// Highlights the tree with ANSI color codes
+    pub color: bool,
+    /// Only the given number of children are rendered for every node,
+    /// followed by a line with the number of children that were left out.
+    pub max_items: Option<usize>,
+}
+
+/// Renders the value as a tree into the given writer.
+/// Every node is written on its own line.
+pub fn to_writer<W: io::Write>(value: &Value, writer: W, options: &TreeOptions) -> io::Result<()> {
+    let mut writer = TreeWriter { writer, options };
+    writer.write_label(value)?;
+    writer.writer.write_all(b"\n")?;
+    writer.write_children(value, &mut String::new())
+}
+
+/// Renders the value as a tree
+pub fn to_string(value: &Value, options: &TreeOptions) -> String {
+    let mut buf = Vec::new();
+    to_writer(value, &mut buf, options).expect("writing to a vec cannot fail");
+
+    String::from_utf8(buf).expect("the tree writer only emits valid utf-8")
+}
+
+/// The key under which a child node is listed
+enum Key<'a> {
+    Field(&'a str),
+    Index(usize),
+    Map(&'a HashableValue),
+}
+
+struct TreeWriter<'a, W: io::Write> {
+    writer: W,
+    options: &'a TreeOptions,
+}
+
+impl<'a, W: io::Write> TreeWriter<'a, W> {
+    /// Writes the children of the value with the given prefix in front of every line
+    fn write_children(&mut self, value: &Value, prefix: &mut String) -> io::Result<()> {
+        let children = children(value);
+        let limit = self.options.max_items.unwrap_or(usize::MAX);
+        let omitted = children.len().saturating_sub(limit);
+
+        for (i, (key, child)) in children.iter().take(limit).enumerate() {
+            let last = omitted == 0 && i + 1 == children.len();
+            self.write_styled(
+                DIM_STYLE,
+                &format!("{prefix}{}", if last { "└── " } else { "├── " }),
+            )?;
+            self.write_key(key)?;
+            self.writer.write_all(b": ")?;
+            self.write_label(child)?;
+            self.writer.write_all(b"\n")?;
+
+            let len = prefix.len();
+            prefix.push_str(if last { "    " } else { "│   " });
+            self.write_children(child, prefix)?;
+            prefix.truncate(len);
+        }
+        if omitted > 0 {
+            self.write_styled(DIM_STYLE, &format!("{prefix}└── … {omitted} more"))?;
+            self.writer.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    fn write_key(&mut self, key: &Key) -> io::Result<()> {
+        let key = match key {
+            Key::Field(name) => name.to_string(),
+            Key::Index(i) => format!("[{i}]"),
+            Key::Map(key) => hashable_to_string(key),
+        };
+        self.write_styled(KEY_STYLE, &key)
+    }
+
+    /// Writes the line describing the value itself without its children
+    fn write_label(&mut self, value: &Value) -> io::Result<()> {
+        match value {
+            Value::Primitive(p) => {
+                let (value, kind) = primitive_label(p);
+                self.write_styled(VALUE_STYLE, &value)?;
+                self.write_styled(DIM_STYLE, &format!(" ({kind})"))
+            }
+            Value::Struct(s) => self.write_styled(TYPE_STYLE, &s.name),
+            Value::Enum(e) => self.write_styled(TYPE_STYLE, &format!("{}::{}", e.name, e.variant)),
+            Value::Map(m) => {
+                let noun = if m.len() == 1 { "entry" } else { "entries" };
+                self.write_styled(DIM_STYLE, &format!("map ({} {noun})", m.len()))
+            }
+            Value::List(l) => {
+                let noun = if l.len() == 1 { "item" } else { "items" };
+                self.write_styled(DIM_STYLE, &format!("list ({} {noun})", l.len()))
+            }
+            Value::None => self.write_styled(VALUE_STYLE, "None"),
+        }
+    }
+
+    fn write_styled(&mut self, style: &str, text: &str) -> io::Result<()> {
+        if self.options.color {
+            write!(self.writer, "{style}{text}{RESET}")
+        } else {
+            self.writer.write_all(text.as_bytes())
+        }
+    }
+}
+
+/// Returns the child nodes of a value in the order they are rendered
+fn children(value: &Value) -> Vec<(Key<'_>, &Value)> {
+    match value {
+        Value::Struct(Struct { fields, .. }) | Value::Enum(Enum { fields, .. }) => match fields {
+            Fields::Named(n) => {
+                let mut fields = n.iter().collect::<Vec<_>>();
+                fields.sort_by_key(|(k, _)| *k);
+                fields
+                    .into_iter()
+                    .map(|(k, v)| (Key::Field(k), v))
+                    .collect()
+            }
+            Fields::Unnamed(u) => u
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (Key::Index(i), v))
+                .collect(),
+            Fields::Unit => Vec::new(),
+        },
+        Value::Map(m) => {
+            let mut entries = m.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(k, _)| *k);
+            entries.into_iter().map(|(k, v)| (Key::Map(k), v)).collect()
+        }
+        Value::List(l) => l
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (Key::Index(i), v))
+            .collect(),
+        Value::Primitive(_) | Value::None => Vec::new(),
+    }
+}
+
+/// Returns the rendered value and the kind of a primitive
+fn primitive_label(primitive: &Primitive) -> (String, &'static str) {
+    match primitive {
+        Primitive::Integer(i) => (i.to_string(), integer_kind(i)),
+        Primitive::Float(Float::F32(f)) => (format!("{f:?}"), "f32"),
+        Primitive::Float(Float::F64(f)) => (format!("{f:?}"), "f64"),
+        Primitive::String(s) => (format!("\"{}\"", s.escape_debug()), "String"),
+        Primitive::OsString(o) => (
+            format!("\"{}\"", o.to_string_lossy().escape_debug()),
+            "OsString",
+        ),
+        Primitive::Char(c) => (format!("{c:?}"), "char"),
+        Primitive::Bool(b) => (b.to_string(), "bool"),
+    }
+}
+
+fn integer_kind(integer: &Integer) -> &'static str {
+    match integer {
+        Integer::USize(_) => "usize",
+        Integer::ISize(_) => "isize",
+        Integer::U8(_) => "u8",
+        Integer::I8(_) => "i8",
+        Integer::U16(_) => "u16",
+        Integer::I16(_) => "i16",
+        Integer::U32(_) => "u32",
+        Integer::I32(_) => "i32",
+        Integer::U64(_) => "u64",
+        Integer::I64(_) => "i64",
+        Integer::U128(_) => "u128",
+        Integer::I128(_) => "i128",
+    }
+}
+
+fn hashable_to_string(value: &HashableValue) -> String {
+    match value {
+        HashableValue::Primitive(HashablePrimitive::String(s)) => {
+            format!("\"{}\"", s.escape_debug())
+        }
+        HashableValue::Primitive(HashablePrimitive::OsString(o)) => {
+            format!("\"{}\"", o.to_string_lossy().escape_debug())
+        }
+        HashableValue::Primitive(HashablePrimitive::Char(c)) => format!("{c:?}"),
+        HashableValue::Primitive(p) => p.to_string(),
+        HashableValue::List(l) => format!(
+            "[{}]",
+            l.iter()
+                .map(hashable_to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        HashableValue::None => String::from("None"),
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::tree::{self, TreeOptions};
+    use crate::*;
+
+    #[derive(RustyValue)]
+    struct TestStruct {
+        name: String,
+        items: Vec<TestEnum>,
+        limits: HashMap<String, f64>,
+    }
+
+    #[derive(RustyValue)]
+    enum TestEnum {
+        Foo,
+        Bar(u8, Option<bool>),
+    }
+
+    fn test_struct() -> TestStruct {
+        TestStruct {
+            name: String::from("config"),
+            items: vec![TestEnum::Foo, TestEnum::Bar(1, None)],
+            limits: HashMap::from([(String::from("b"), 2.0), (String::from("a"), 0.5)]),
+        }
+    }
+
+    #[test]
+    fn it_renders_a_tree() {
+        assert_eq!(
+            tree::to_string(&test_struct().into_rusty_value(), &TreeOptions::default()),
+            r#"TestStruct
+├── items: list (2 items)
+│   ├── [0]: TestEnum::Foo
+│   └── [1]: TestEnum::Bar
+│       ├── [0]: 1 (u8)
+│       └── [1]: None
+├── limits: map (2 entries)
+│   ├── "a": 0.5 (f64)
+│   └── "b": 2.0 (f64)
+└── name: "config" (String)
+"#
+        );
+        assert_eq!(
+            tree::to_string(&12u16.into_rusty_value(), &TreeOptions::default()),
+            "12 (u16)\n"
+        );
+        assert_eq!(
+            tree::to_string(
+                &HashMap::from([(1u8, ())]).into_rusty_value(),
+                &TreeOptions::default()
+            ),
+            "map (1 entry)\n└── 1: None\n"
+        );
+    }
+
+    #[test]
+    fn it_collapses_long_lists() {
+        let opt = TreeOptions {
+            max_items: Some(2),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            tree::to_string(&vec![1u8, 2, 3, 4].into_rusty_value(), &opt),
+            "list (4 items)\n├── [0]: 1 (u8)\n├── [1]: 2 (u8)\n└── … 2 more\n"
+        );
+    }
+
+    #[test]
+    fn it_renders_colors() {
+        let opt = TreeOptions {
+            color: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            tree::to_string(&vec![true].into_rusty_value(), &opt),
+            "\x1b[2mlist (1 item)\x1b[0m\n\x1b[2m└── \x1b[0m\x1b[34m[0]\x1b[0m: \x1b[32mtrue\x1b[0m\x1b[2m (bool)\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,523 @@
+use crate::json::{hashable_to_string, EnumRepr};
+use crate::*;
+use std::{borrow::Cow, io};
+
+/// Options for how to represent certain rust types
+/// as YAML
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub struct YamlOptions {
+    pub enum_repr: YamlEnumRepr,
+    /// The number of spaces used for each level of indentation
+    pub indent: usize,
+}
+
+impl Default for YamlOptions {
+    fn default() -> Self {
+        Self {
+            enum_repr: YamlEnumRepr::default(),
+            indent: 2,
+        }
+    }
+}
+
+/// Controls how enums should be represented in YAML
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub enum YamlEnumRepr {
+    /// Enum variants with fields are tagged with the name of the variant (`!Variant`).
+    /// Unit variants are represented as their name.
+    #[default]
+    Tagged,
+    /// Enums are represented the same way as they are in JSON
+    Json(EnumRepr),
+}
+
+/// Writes the value as a YAML document into the given writer
+pub fn to_writer<W: io::Write>(value: &Value, writer: W, options: &YamlOptions) -> io::Result<()> {
+    YamlWriter { writer, options }.write_node(Node::Value(value), Pos::Root, 0)
+}
+
+/// Converts the value into a YAML document
+pub fn to_string(value: &Value, options: &YamlOptions) -> String {
+    let mut buf = Vec::new();
+    to_writer(value, &mut buf, options).expect("writing to a vec cannot fail");
+
+    String::from_utf8(buf).expect("the yaml writer only emits valid utf-8")
+}
+
+/// A node of the YAML document that is being written
+#[derive(Clone, Copy)]
+enum Node<'a> {
+    Value(&'a Value),
+    Str(&'a str),
+    /// The fields of a struct or enum variant.
+    /// Unit fields are represented by the given name
+    Fields(&'a Fields, &'a str),
+}
+
+/// What has been written on the current line before a node
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pos {
+    /// Nothing, the node is the document root
+    Root,
+    /// A mapping key
+    AfterKey,
+    /// A sequence item indicator
+    AfterDash,
+    /// A tag. Another tag can't be written before the node
+    AfterTag,
+}
+
+struct YamlWriter<'a, W: io::Write> {
+    writer: W,
+    options: &'a YamlOptions,
+}
+
+impl<'a, W: io::Write> YamlWriter<'a, W> {
+    fn write_node(&mut self, node: Node<'_>, pos: Pos, indent: usize) -> io::Result<()> {
+        match node {
+            Node::Value(value) => match value {
+                Value::Primitive(p) => self.write_primitive(p, pos, indent),
+                Value::Struct(s) => self.write_node(Node::Fields(&s.fields, &s.name), pos, indent),
+                Value::Enum(e) => self.write_enum(e, pos, indent),
+                Value::Map(m) => {
+                    let mut entries = m
+                        .iter()
+                        .map(|(k, v)| (flow_key(k), Node::Value(v)))
+                        .collect::<Vec<_>>();
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    self.write_mapping(entries, pos, indent)
+                }
+                Value::List(l) => self.write_sequence(l.iter().map(Node::Value), pos, indent),
+                Value::None => self.write_scalar("null", pos),
+            },
+            Node::Str(s) => self.write_str(s, pos, indent),
+            Node::Fields(fields, name) => match fields {
+                Fields::Named(n) => {
+                    let mut entries = n
+                        .iter()
+                        .map(|(k, v)| (scalar(k), Node::Value(v)))
+                        .collect::<Vec<_>>();
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    self.write_mapping(entries, pos, indent)
+                }
+                Fields::Unnamed(u) if u.len() == 1 => {
+                    self.write_node(Node::Value(&u[0]), pos, indent)
+                }
+                Fields::Unnamed(u) => self.write_sequence(u.iter().map(Node::Value), pos, indent),
+                Fields::Unit => self.write_node(Node::Str(name), pos, indent),
+            },
+        }
+    }
+
+    fn write_enum(&mut self, e: &Enum, pos: Pos, indent: usize) -> io::Result<()> {
+        let fields = Node::Fields(&e.fields, &e.variant);
+
+        match &self.options.enum_repr {
+            YamlEnumRepr::Tagged if matches!(e.fields, Fields::Unit) => {
+                self.write_node(Node::Str(&e.variant), pos, indent)
+            }
+            // a node can only have a single tag so nested enums are externally tagged
+            YamlEnumRepr::Tagged if pos == Pos::AfterTag => {
+                self.write_mapping(vec![(scalar(&e.variant), fields)], pos, indent)
+            }
+            YamlEnumRepr::Tagged => {
+                if pos != Pos::Root {
+                    self.writer.write_all(b" ")?;
+                }
+                write!(self.writer, "!{}", e.variant)?;
+                self.write_node(fields, Pos::AfterTag, indent)
+            }
+            YamlEnumRepr::Json(EnumRepr::Untagged) => self.write_node(fields, pos, indent),
+            YamlEnumRepr::Json(EnumRepr::ExternallyTagged) => {
+                self.write_mapping(vec![(scalar(&e.variant), fields)], pos, indent)
+            }
+            YamlEnumRepr::Json(EnumRepr::AdjacentlyTagged {
+                type_field,
+                value_field,
+            }) => self.write_mapping(
+                vec![
+                    (scalar(type_field), Node::Str(&e.variant)),
+                    (scalar(value_field), fields),
+                ],
+                pos,
+                indent,
+            ),
+        }
+    }
+
+    fn write_mapping(
+        &mut self,
+        entries: Vec<(Cow<'_, str>, Node<'_>)>,
+        pos: Pos,
+        indent: usize,
+    ) -> io::Result<()> {
+        if entries.is_empty() {
+            return self.write_scalar("{}", pos);
+        }
+        self.begin_collection(pos)?;
+
+        for (i, (key, value)) in entries.into_iter().enumerate() {
+            if i > 0 || pos != Pos::AfterDash {
+                self.write_indent(indent)?;
+            }
+            write!(self.writer, "{key}:")?;
+            self.write_node(value, Pos::AfterKey, indent + self.options.indent)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_sequence<'v, I: Iterator<Item = Node<'v>>>(
+        &mut self,
+        items: I,
+        pos: Pos,
+        indent: usize,
+    ) -> io::Result<()> {
+        let mut items = items.peekable();
+
+        if items.peek().is_none() {
+            return self.write_scalar("[]", pos);
+        }
+        self.begin_collection(pos)?;
+
+        for (i, item) in items.enumerate() {
+            if i > 0 || pos != Pos::AfterDash {
+                self.write_indent(indent)?;
+            }
+            self.writer.write_all(b"-")?;
+            self.write_node(item, Pos::AfterDash, indent + 2)?;
+        }
+
+        Ok(())
+    }
+
+    fn begin_collection(&mut self, pos: Pos) -> io::Result<()> {
+        match pos {
+            Pos::Root => Ok(()),
+            Pos::AfterKey | Pos::AfterTag => self.writer.write_all(b"\n"),
+            Pos::AfterDash => self.writer.write_all(b" "),
+        }
+    }
+
+    fn write_primitive(
+        &mut self,
+        primitive: &Primitive,
+        pos: Pos,
+        indent: usize,
+    ) -> io::Result<()> {
+        match primitive {
+            Primitive::Integer(i) => self.write_scalar(&i.to_string(), pos),
+            Primitive::Float(f) => {
+                // f32 values are formatted on their own so they keep their shortest representation
+                let (f, repr) = match f {
+                    Float::F32(f) => (*f as f64, format!("{f:?}")),
+                    Float::F64(f) => (*f, format!("{f:?}")),
+                };
+                let repr = if f.is_nan() {
+                    String::from(".nan")
+                } else if f.is_infinite() {
+                    String::from(if f > 0.0 { ".inf" } else { "-.inf" })
+                } else {
+                    float_scalar(&repr)
+                };
+                self.write_scalar(&repr, pos)
+            }
+            Primitive::String(s) => self.write_str(s, pos, indent),
+            Primitive::OsString(o) => self.write_str(&o.to_string_lossy(), pos, indent),
+            Primitive::Char(c) => self.write_str(c.encode_utf8(&mut [0; 4]), pos, indent),
+            Primitive::Bool(b) => self.write_scalar(&b.to_string(), pos),
+        }
+    }
+
+    /// Writes a string either as a literal block scalar if it spans multiple lines
+    /// or as a (quoted) flow scalar
+    fn write_str(&mut self, s: &str, pos: Pos, indent: usize) -> io::Result<()> {
+        if !is_block_safe(s) {
+            return self.write_scalar(&scalar(s), pos);
+        }
+        let (chomping, body) = if let Some(body) = s.strip_suffix('\n') {
+            if body.ends_with('\n') {
+                ("+", body)
+            } else {
+                ("", body)
+            }
+        } else {
+            ("-", s)
+        };
+        if pos != Pos::Root {
+            self.writer.write_all(b" ")?;
+        }
+        writeln!(self.writer, "|{chomping}")?;
+        let indent = indent.max(self.options.indent);
+
+        for line in body.split('\n') {
+            if !line.is_empty() {
+                self.write_indent(indent)?;
+                self.writer.write_all(line.as_bytes())?;
+            }
+            self.writer.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    fn write_scalar(&mut self, scalar: &str, pos: Pos) -> io::Result<()> {
+        if pos != Pos::Root {
+            self.writer.write_all(b" ")?;
+        }
+        writeln!(self.writer, "{scalar}")
+    }
+
+    fn write_indent(&mut self, indent: usize) -> io::Result<()> {
+        write!(self.writer, "{:indent$}", "")
+    }
+}
+
+/// Renders a map key as a flow node
+fn flow_key(key: &HashableValue) -> Cow<'static, str> {
+    match key {
+        HashableValue::Primitive(HashablePrimitive::String(s)) => {
+            Cow::Owned(scalar(s).into_owned())
+        }
+        HashableValue::Primitive(HashablePrimitive::OsString(_))
+        | HashableValue::Primitive(HashablePrimitive::Char(_)) => {
+            Cow::Owned(scalar(&hashable_to_string(key)).into_owned())
+        }
+        HashableValue::Primitive(p) => Cow::Owned(p.to_string()),
+        HashableValue::List(l) => Cow::Owned(format!(
+            "[{}]",
+            l.iter().map(flow_key).collect::<Vec<_>>().join(", ")
+        )),
+        HashableValue::None => Cow::Borrowed("null"),
+    }
+}
+
+/// Converts the debug representation of a finite float into the YAML 1.1 float syntax
+/// which requires a `.` and a sign in front of the exponent, e.g. `1.0e+300`
+fn float_scalar(repr: &str) -> String {
+    let (mantissa, exponent) = match repr.split_once('e') {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (repr, None),
+    };
+    let mut scalar = mantissa.to_owned();
+
+    if !mantissa.contains('.') {
+        scalar.push_str(".0");
+    }
+    if let Some(exponent) = exponent {
+        scalar.push('e');
+        if !exponent.starts_with('-') {
+            scalar.push('+');
+        }
+        scalar.push_str(exponent);
+    }
+
+    scalar
+}
+
+/// Characters that YAML treats as line breaks or that are dropped by parsers
+/// but aren't control characters
+const SPECIAL_CHARS: [char; 3] = ['\u{2028}', '\u{2029}', '\u{feff}'];
+
+/// Renders a string as a plain scalar if that's unambiguous
+/// and as a double quoted scalar otherwise
+fn scalar(s: &str) -> Cow<'_, str> {
+    if is_plain_safe(s) {
+        return Cow::Borrowed(s);
+    }
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() || SPECIAL_CHARS.contains(&c) => {
+                quoted.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    Cow::Owned(quoted)
+}
+
+fn is_plain_safe(s: &str) -> bool {
+    // `<<` and `=` are the merge and value keys of YAML 1.1
+    const RESERVED: &[&str] = &[
+        "true", "false", "yes", "no", "on", "off", "y", "n", "null", "~", "<<", "=",
+    ];
+
+    let Some(first) = s.chars().next() else {
+        return false;
+    };
+
+    !first.is_whitespace()
+        && !s.ends_with(char::is_whitespace)
+        && !s.ends_with(':')
+        && !"-?:,[]{}#&*!|>'\"%@`+.0123456789".contains(first)
+        && !s.contains(": ")
+        && !s.contains(" #")
+        && !s.contains(|c: char| c.is_control() || ",[]{}".contains(c))
+        && !s.contains(SPECIAL_CHARS)
+        && !RESERVED.iter().any(|r| r.eq_ignore_ascii_case(s))
+        && s.parse::<f64>().is_err()
+}
+
+fn is_block_safe(s: &str) -> bool {
+    let first_line = s.split('\n').find(|l| !l.is_empty());
+
+    s.contains('\n')
+        && !s.contains(|c: char| c.is_control() && c != '\n' && c != '\t')
+        && !s.contains(SPECIAL_CHARS)
+        && first_line.is_some_and(|l| !l.starts_with([' ', '\t']))
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::json::EnumRepr;
+    use crate::yaml::{self, YamlEnumRepr, YamlOptions};
+    use crate::*;
+
+    #[derive(RustyValue)]
+    struct TestStruct {
+        foo: String,
+        bar: Vec<TestEnum>,
+    }
+
+    #[derive(RustyValue)]
+    enum TestEnum {
+        Foo,
+        Bar(u8),
+        Baz { value: String },
+    }
+
+    #[test]
+    fn it_writes_scalars() {
+        let opt = YamlOptions::default();
+
+        assert_eq!(yaml::to_string(&12u8.into_rusty_value(), &opt), "12\n");
+        assert_eq!(
+            yaml::to_string(&f32::NAN.into_rusty_value(), &opt),
+            ".nan\n"
+        );
+        assert_eq!(
+            yaml::to_string(&"hello".into_rusty_value(), &opt),
+            "hello\n"
+        );
+        assert_eq!(
+            yaml::to_string(&"true".into_rusty_value(), &opt),
+            "\"true\"\n"
+        );
+        assert_eq!(yaml::to_string(&"12".into_rusty_value(), &opt), "\"12\"\n");
+        assert_eq!(
+            yaml::to_string(&"a: b".into_rusty_value(), &opt),
+            "\"a: b\"\n"
+        );
+        assert_eq!(yaml::to_string(&().into_rusty_value(), &opt), "null\n");
+        assert_eq!(yaml::to_string(&"<<".into_rusty_value(), &opt), "\"<<\"\n");
+        assert_eq!(yaml::to_string(&"=".into_rusty_value(), &opt), "\"=\"\n");
+        assert_eq!(
+            yaml::to_string(&"a\u{2028}b".into_rusty_value(), &opt),
+            "\"a\\u2028b\"\n"
+        );
+        assert_eq!(
+            yaml::to_string(&"a\n\u{2029}b".into_rusty_value(), &opt),
+            "\"a\\n\\u2029b\"\n"
+        );
+    }
+
+    #[test]
+    fn it_writes_floats() {
+        let opt = YamlOptions::default();
+        let float = |value: Value| yaml::to_string(&value, &opt);
+
+        assert_eq!(float(1e300f64.into_rusty_value()), "1.0e+300\n");
+        assert_eq!(float(5e-7f64.into_rusty_value()), "5.0e-7\n");
+        assert_eq!(float(1.5e-7f64.into_rusty_value()), "1.5e-7\n");
+        assert_eq!(float(0.1f32.into_rusty_value()), "0.1\n");
+        assert_eq!(float(2f64.into_rusty_value()), "2.0\n");
+        assert_eq!(float((-1e20f32).into_rusty_value()), "-1.0e+20\n");
+        assert_eq!(float(f64::NEG_INFINITY.into_rusty_value()), "-.inf\n");
+    }
+
+    #[test]
+    fn it_writes_block_scalars() {
+        let value = HashMap::from([("text", "line 1\nline 2\n")]).into_rusty_value();
+        assert_eq!(
+            yaml::to_string(&value, &YamlOptions::default()),
+            "text: |\n  line 1\n  line 2\n"
+        );
+
+        let value = vec!["line 1\n\nline 2"].into_rusty_value();
+        assert_eq!(
+            yaml::to_string(&value, &YamlOptions::default()),
+            "- |-\n  line 1\n\n  line 2\n"
+        );
+    }
+
+    #[test]
+    fn it_writes_tagged_enums() {
+        let value = TestStruct {
+            foo: String::from("hello"),
+            bar: vec![
+                TestEnum::Foo,
+                TestEnum::Bar(1),
+                TestEnum::Baz {
+                    value: String::from("world"),
+                },
+            ],
+        }
+        .into_rusty_value();
+
+        assert_eq!(
+            yaml::to_string(&value, &YamlOptions::default()),
+            "bar:\n  - Foo\n  - !Bar 1\n  - !Baz\n    value: world\nfoo: hello\n"
+        );
+    }
+
+    #[test]
+    fn it_writes_json_style_enums() {
+        let value = vec![TestEnum::Bar(1)].into_rusty_value();
+        let opt = YamlOptions {
+            enum_repr: YamlEnumRepr::Json(EnumRepr::AdjacentlyTagged {
+                type_field: String::from("type"),
+                value_field: String::from("value"),
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(yaml::to_string(&value, &opt), "- type: Bar\n  value: 1\n");
+
+        let opt = YamlOptions {
+            enum_repr: YamlEnumRepr::Json(EnumRepr::ExternallyTagged),
+            indent: 4,
+        };
+        let value = TestEnum::Baz {
+            value: String::from("world"),
+        }
+        .into_rusty_value();
+
+        assert_eq!(yaml::to_string(&value, &opt), "Baz:\n    value: world\n");
+    }
+
+    #[test]
+    fn it_writes_empty_collections() {
+        let value = HashMap::from([("list", Vec::<u8>::new())]).into_rusty_value();
+
+        assert_eq!(
+            yaml::to_string(&value, &YamlOptions::default()),
+            "list: []\n"
+        );
+    }
+}
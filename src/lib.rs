@@ -1,11 +1,37 @@
-#![doc=include_str!("../README.md")]
+#![cfg_attr(feature = "derive", doc = include_str!("../README.md"))]
 
+pub(crate) mod canonical;
+pub(crate) mod diff;
 pub(crate) mod formats;
+mod macros;
+pub(crate) mod merge;
+pub(crate) mod patch;
+pub(crate) mod path;
+pub(crate) mod query;
+pub(crate) mod redact;
+pub(crate) mod schema;
+pub(crate) mod stream;
+pub(crate) mod validate;
 pub(crate) mod value;
+pub(crate) mod value_ref;
 pub(crate) mod value_trait;
+pub(crate) mod visit;
+pub use canonical::*;
+pub use diff::*;
+#[allow(unused_imports)]
 pub use formats::*;
+pub use merge::*;
+pub use patch::*;
+pub use path::*;
+pub use query::*;
+pub use redact::*;
+pub use schema::*;
+pub use stream::*;
+pub use validate::*;
 pub use value::*;
+pub use value_ref::*;
 pub use value_trait::*;
+pub use visit::*;
 
 #[doc(inline)]
 #[allow(unused_imports)]
@@ -0,0 +1,143 @@
+/// Constructs a [crate::Value] from a literal like syntax.
+///
+/// - `None` creates a [crate::Value::None]
+/// - `[a, b]` creates a list
+/// - `{ key => value }` creates a map. The keys must implement [crate::HashableRustyValue]
+/// - `struct Name { field: value }`, `struct Name(a, b)` and `struct Name` create structs
+/// - `enum Name::Variant { field: value }`, `enum Name::Variant(a, b)` and `enum Name::Variant` create enums
+/// - any other expression is converted with [crate::RustyValue::into_rusty_value],
+///   so typed literals like `12u8` keep their type
+///
+/// Values can be nested. Every list item, field and map entry takes one expansion step,
+/// so collections with more than about 120 entries need a higher `recursion_limit`.
+///
+/// ```
+/// use rusty_value::{rusty_value, Value};
+///
+/// let value = rusty_value!(struct Config {
+///     name: "server",
+///     ports: [80u16, 443u16],
+///     mode: enum Mode::Fast,
+///     limits: { "cpu" => 2.5, "memory" => None },
+/// });
+///
+/// assert!(matches!(value, Value::Struct(_)));
+/// ```
+#[macro_export]
+macro_rules! rusty_value {
+    // fields of structs and enums
+    (@fields) => {
+        $crate::Fields::Unit
+    };
+    (@fields { $($tt:tt)* }) => {
+        $crate::Fields::Named($crate::rusty_value!(@named [] $($tt)*))
+    };
+    (@fields ( $($tt:tt)* )) => {
+        $crate::Fields::Unnamed($crate::rusty_value!(@list [] $($tt)*))
+    };
+
+    // comma separated values, one value per step
+    (@list [$($out:tt)*]) => {
+        ::std::vec![$($out)*]
+    };
+    (@list [$($out:tt)*] $value:tt $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@list [$($out)* $crate::rusty_value!($value),] $($($rest)*)?)
+    };
+    (@list [$($out:tt)*] struct $name:ident $({ $($named:tt)* })? $(( $($unnamed:tt)* ))? $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@list [$($out)*
+            $crate::rusty_value!(struct $name $({ $($named)* })? $(( $($unnamed)* ))?),
+        ] $($($rest)*)?)
+    };
+    (@list [$($out:tt)*] enum $name:ident :: $variant:ident $({ $($named:tt)* })? $(( $($unnamed:tt)* ))? $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@list [$($out)*
+            $crate::rusty_value!(enum $name::$variant $({ $($named)* })? $(( $($unnamed)* ))?),
+        ] $($($rest)*)?)
+    };
+    (@list [$($out:tt)*] $value:expr $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@list [$($out)* $crate::rusty_value!($value),] $($($rest)*)?)
+    };
+
+    // comma separated `field: value` pairs
+    (@named [$($out:tt)*]) => {
+        ::std::collections::HashMap::from([$($out)*])
+    };
+    (@named [$($out:tt)*] $key:ident : $value:tt $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@named [$($out)* (
+            ::std::borrow::Cow::Borrowed(stringify!($key)),
+            $crate::rusty_value!($value),
+        ),] $($($rest)*)?)
+    };
+    (@named [$($out:tt)*] $key:ident : struct $name:ident $({ $($named:tt)* })? $(( $($unnamed:tt)* ))? $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@named [$($out)* (
+            ::std::borrow::Cow::Borrowed(stringify!($key)),
+            $crate::rusty_value!(struct $name $({ $($named)* })? $(( $($unnamed)* ))?),
+        ),] $($($rest)*)?)
+    };
+    (@named [$($out:tt)*] $key:ident : enum $name:ident :: $variant:ident $({ $($named:tt)* })? $(( $($unnamed:tt)* ))? $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@named [$($out)* (
+            ::std::borrow::Cow::Borrowed(stringify!($key)),
+            $crate::rusty_value!(enum $name::$variant $({ $($named)* })? $(( $($unnamed)* ))?),
+        ),] $($($rest)*)?)
+    };
+    (@named [$($out:tt)*] $key:ident : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@named [$($out)* (
+            ::std::borrow::Cow::Borrowed(stringify!($key)),
+            $crate::rusty_value!($value),
+        ),] $($($rest)*)?)
+    };
+
+    // comma separated `key => value` pairs
+    (@map [$($out:tt)*]) => {
+        ::std::collections::HashMap::from([$($out)*])
+    };
+    (@map [$($out:tt)*] $key:expr => $value:tt $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@map [$($out)* (
+            $crate::HashableRustyValue::into_hashable_rusty_value($key),
+            $crate::rusty_value!($value),
+        ),] $($($rest)*)?)
+    };
+    (@map [$($out:tt)*] $key:expr => struct $name:ident $({ $($named:tt)* })? $(( $($unnamed:tt)* ))? $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@map [$($out)* (
+            $crate::HashableRustyValue::into_hashable_rusty_value($key),
+            $crate::rusty_value!(struct $name $({ $($named)* })? $(( $($unnamed)* ))?),
+        ),] $($($rest)*)?)
+    };
+    (@map [$($out:tt)*] $key:expr => enum $name:ident :: $variant:ident $({ $($named:tt)* })? $(( $($unnamed:tt)* ))? $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@map [$($out)* (
+            $crate::HashableRustyValue::into_hashable_rusty_value($key),
+            $crate::rusty_value!(enum $name::$variant $({ $($named)* })? $(( $($unnamed)* ))?),
+        ),] $($($rest)*)?)
+    };
+    (@map [$($out:tt)*] $key:expr => $value:expr $(, $($rest:tt)*)?) => {
+        $crate::rusty_value!(@map [$($out)* (
+            $crate::HashableRustyValue::into_hashable_rusty_value($key),
+            $crate::rusty_value!($value),
+        ),] $($($rest)*)?)
+    };
+
+    (None) => {
+        $crate::Value::None
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::Value::List($crate::rusty_value!(@list [] $($tt)*))
+    };
+    ({ $($tt:tt)* }) => {
+        $crate::Value::Map($crate::rusty_value!(@map [] $($tt)*))
+    };
+    (struct $name:ident $($fields:tt)?) => {
+        $crate::Value::Struct($crate::Struct {
+            name: ::std::borrow::Cow::Borrowed(stringify!($name)),
+            fields: $crate::rusty_value!(@fields $($fields)?),
+        })
+    };
+    (enum $name:ident :: $variant:ident $($fields:tt)?) => {
+        $crate::Value::Enum($crate::Enum {
+            name: ::std::borrow::Cow::Borrowed(stringify!($name)),
+            variant: ::std::borrow::Cow::Borrowed(stringify!($variant)),
+            fields: $crate::rusty_value!(@fields $($fields)?),
+        })
+    };
+    ($value:expr) => {
+        $crate::RustyValue::into_rusty_value($value)
+    };
+}
@@ -0,0 +1,378 @@
+use std::{borrow::Cow, collections::HashMap, hash::Hash};
+
+use crate::{Fields, Path, PathSegment, Value};
+
+/// Options for how values are merged by [Value::merge]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeStrategy {
+    pub lists: ListMerge,
+    pub none: NoneMerge,
+}
+
+/// How two lists are merged
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum ListMerge {
+    /// The list is replaced with the other list
+    #[default]
+    Replace,
+    /// The items of the other list are appended
+    Append,
+    /// Items at the same index are merged and additional items of the other list are appended
+    MergeByIndex,
+    /// Items with an equal value at the given path are merged and the remaining
+    /// items of the other list are appended
+    MergeByKey(Path),
+}
+
+/// How a [Value::None] in the other value is handled
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum NoneMerge {
+    /// `None` is treated as an absent value and doesn't override anything
+    #[default]
+    Absent,
+    /// `None` is treated as an explicit null value and overrides the existing value
+    Null,
+}
+
+/// A conflict that occurred while merging.
+/// The value of the other side is used in case of a conflict.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MergeConflict {
+    /// The structs or enums have different names
+    TypeMismatch {
+        path: Path,
+        ours: String,
+        theirs: String,
+    },
+    /// The enums have different variants
+    VariantMismatch {
+        path: Path,
+        ours: String,
+        theirs: String,
+    },
+}
+
+impl Value {
+    /// Recursively merges the other value into this one.
+    /// Named fields and map entries are merged by their keys,
+    /// unnamed fields by their index and lists depending on the strategy.
+    /// Any other value is replaced with the other value.
+    ///
+    /// Returns the conflicts where structs or enums of different types
+    /// or enum variants were replaced. Named fields and map entries are merged
+    /// in the order of their keys, so the order of the conflicts is deterministic.
+    pub fn merge(&mut self, other: Value, strategy: &MergeStrategy) -> Vec<MergeConflict> {
+        let mut conflicts = Vec::new();
+        Merger {
+            strategy,
+            conflicts: &mut conflicts,
+        }
+        .merge(&mut Path::new(), self, other);
+
+        conflicts
+    }
+}
+
+struct Merger<'a> {
+    strategy: &'a MergeStrategy,
+    conflicts: &'a mut Vec<MergeConflict>,
+}
+
+impl Merger<'_> {
+    fn merge(&mut self, path: &mut Path, ours: &mut Value, theirs: Value) {
+        match (ours, theirs) {
+            (_, Value::None) if self.strategy.none == NoneMerge::Absent => {}
+            (Value::Struct(o), Value::Struct(t)) if o.name == t.name => {
+                self.merge_fields(path, &mut o.fields, t.fields)
+            }
+            (Value::Enum(o), Value::Enum(t)) if o.name == t.name && o.variant == t.variant => {
+                self.merge_fields(path, &mut o.fields, t.fields)
+            }
+            (Value::Map(o), Value::Map(t)) => {
+                let mut entries = t.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                for (key, value) in entries {
+                    path.push(PathSegment::Key(key.clone()));
+                    self.merge_entry(path, o, key, value);
+                    path.pop();
+                }
+            }
+            (Value::List(o), Value::List(t)) => self.merge_lists(path, o, t),
+            (ours, theirs) => {
+                self.check_conflict(path, ours, &theirs);
+                *ours = theirs;
+            }
+        }
+    }
+
+    fn merge_fields(&mut self, path: &mut Path, ours: &mut Fields, theirs: Fields) {
+        match (ours, theirs) {
+            (Fields::Named(o), Fields::Named(t)) => self.merge_named(path, o, t),
+            (Fields::Unnamed(o), Fields::Unnamed(t)) => self.merge_by_index(path, o, t),
+            (ours, theirs) => *ours = theirs,
+        }
+    }
+
+    fn merge_named(
+        &mut self,
+        path: &mut Path,
+        ours: &mut HashMap<Cow<'static, str>, Value>,
+        theirs: HashMap<Cow<'static, str>, Value>,
+    ) {
+        let mut fields = theirs.into_iter().collect::<Vec<_>>();
+        fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, value) in fields {
+            path.push(PathSegment::Field(name.to_string()));
+            self.merge_entry(path, ours, name, value);
+            path.pop();
+        }
+    }
+
+    /// Merges a value into an existing entry or inserts it if the entry doesn't exist
+    fn merge_entry<K: Eq + Hash>(
+        &mut self,
+        path: &mut Path,
+        ours: &mut HashMap<K, Value>,
+        key: K,
+        theirs: Value,
+    ) {
+        match ours.get_mut(&key) {
+            Some(ours) => self.merge(path, ours, theirs),
+            None if theirs == Value::None && self.strategy.none == NoneMerge::Absent => {}
+            None => {
+                ours.insert(key, theirs);
+            }
+        }
+    }
+
+    fn merge_lists(&mut self, path: &mut Path, ours: &mut Vec<Value>, theirs: Vec<Value>) {
+        match &self.strategy.lists {
+            ListMerge::Replace => *ours = theirs,
+            ListMerge::Append => ours.extend(theirs),
+            ListMerge::MergeByIndex => self.merge_by_index(path, ours, theirs),
+            ListMerge::MergeByKey(key) => {
+                for value in theirs {
+                    let index = value.get(key).and_then(|k| {
+                        ours.iter()
+                            .position(|item| item.get(key).is_some_and(|o| o == k))
+                    });
+
+                    match index {
+                        Some(index) => {
+                            path.push(PathSegment::Index(index));
+                            self.merge(path, &mut ours[index], value);
+                            path.pop();
+                        }
+                        None => ours.push(value),
+                    }
+                }
+            }
+        }
+    }
+
+    fn merge_by_index(&mut self, path: &mut Path, ours: &mut Vec<Value>, theirs: Vec<Value>) {
+        for (index, value) in theirs.into_iter().enumerate() {
+            match ours.get_mut(index) {
+                Some(ours) => {
+                    path.push(PathSegment::Index(index));
+                    self.merge(path, ours, value);
+                    path.pop();
+                }
+                None => ours.push(value),
+            }
+        }
+    }
+
+    fn check_conflict(&mut self, path: &Path, ours: &Value, theirs: &Value) {
+        let conflict = match (ours, theirs) {
+            (Value::Struct(o), Value::Struct(t)) => MergeConflict::TypeMismatch {
+                path: path.clone(),
+                ours: o.name.to_string(),
+                theirs: t.name.to_string(),
+            },
+            (Value::Enum(o), Value::Enum(t)) if o.name != t.name => MergeConflict::TypeMismatch {
+                path: path.clone(),
+                ours: o.name.to_string(),
+                theirs: t.name.to_string(),
+            },
+            (Value::Enum(o), Value::Enum(t)) => MergeConflict::VariantMismatch {
+                path: path.clone(),
+                ours: o.variant.to_string(),
+                theirs: t.variant.to_string(),
+            },
+            _ => return,
+        };
+        self.conflicts.push(conflict);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    fn defaults() -> Value {
+        rusty_value!(struct Config {
+            name: "default",
+            port: 80u16,
+            log: None,
+            hosts: ["a", "b"],
+            env: { "HOME" => "/root" },
+            mode: enum Mode::Fast,
+        })
+    }
+
+    #[test]
+    fn it_merges_named_fields_and_maps() {
+        let mut value = defaults();
+        let conflicts = value.merge(
+            rusty_value!(struct Config {
+                port: 8080u16,
+                log: "debug",
+                name: None,
+                hosts: ["c"],
+                env: { "PATH" => "/bin" },
+            }),
+            &MergeStrategy::default(),
+        );
+
+        assert_eq!(conflicts, Vec::new());
+        assert_eq!(
+            value,
+            rusty_value!(struct Config {
+                name: "default",
+                port: 8080u16,
+                log: "debug",
+                hosts: ["c"],
+                env: { "HOME" => "/root", "PATH" => "/bin" },
+                mode: enum Mode::Fast,
+            })
+        );
+    }
+
+    #[test]
+    fn it_handles_none_as_null() {
+        let mut value = defaults();
+        value.merge(
+            rusty_value!(
+                struct Config {
+                    name: None,
+                    extra: None,
+                }
+            ),
+            &MergeStrategy {
+                none: NoneMerge::Null,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(value.get(&"name".parse().unwrap()), Some(&Value::None));
+        assert_eq!(value.get(&"extra".parse().unwrap()), Some(&Value::None));
+    }
+
+    #[test]
+    fn it_merges_lists() {
+        let merge = |lists: ListMerge, ours: Value, theirs: Value| {
+            let mut ours = ours;
+            ours.merge(
+                theirs,
+                &MergeStrategy {
+                    lists,
+                    ..Default::default()
+                },
+            );
+            ours
+        };
+
+        assert_eq!(
+            merge(ListMerge::Append, rusty_value!([1u8]), rusty_value!([2u8])),
+            rusty_value!([1u8, 2u8])
+        );
+        assert_eq!(
+            merge(
+                ListMerge::MergeByIndex,
+                rusty_value!([{ "a" => 1u8 }, 2u8]),
+                rusty_value!([{ "b" => 2u8 }, None, 3u8])
+            ),
+            rusty_value!([{ "a" => 1u8, "b" => 2u8 }, 2u8, 3u8])
+        );
+        assert_eq!(
+            merge(
+                ListMerge::MergeByKey("name".parse().unwrap()),
+                rusty_value!([
+                    struct Server { name: "a", port: 80u16 },
+                    struct Server { name: "b", port: 81u16 },
+                ]),
+                rusty_value!([
+                    struct Server { name: "b", port: 8081u16 },
+                    struct Server { name: "c", port: 82u16 },
+                ])
+            ),
+            rusty_value!([
+                struct Server { name: "a", port: 80u16 },
+                struct Server { name: "b", port: 8081u16 },
+                struct Server { name: "c", port: 82u16 },
+            ])
+        );
+    }
+
+    #[test]
+    fn it_reports_conflicts() {
+        let mut value = defaults();
+        let conflicts = value.merge(
+            rusty_value!(struct Config {
+                mode: enum Mode::Slow { delay: 1u32 },
+                hosts: struct Hosts,
+            }),
+            &MergeStrategy::default(),
+        );
+
+        assert_eq!(
+            conflicts,
+            vec![MergeConflict::VariantMismatch {
+                path: "mode".parse().unwrap(),
+                ours: String::from("Fast"),
+                theirs: String::from("Slow"),
+            }]
+        );
+        assert_eq!(
+            value.get(&"mode".parse().unwrap()),
+            Some(&rusty_value!(enum Mode::Slow { delay: 1u32 }))
+        );
+
+        let mut flags = rusty_value!(struct Flags {
+            b: enum Flag::On,
+            a: enum Flag::On,
+            map: { 2u8 => enum Flag::On, 1u8 => enum Flag::On },
+        });
+        let conflicts = flags.merge(
+            rusty_value!(struct Flags {
+                map: { 1u8 => enum Flag::Off, 2u8 => enum Flag::Off },
+                b: enum Flag::Off,
+                a: enum Flag::Off,
+            }),
+            &MergeStrategy::default(),
+        );
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|c| match c {
+                    MergeConflict::VariantMismatch { path, .. } => path.to_string(),
+                    MergeConflict::TypeMismatch { path, .. } => path.to_string(),
+                })
+                .collect::<Vec<_>>(),
+            vec!["a", "b", "map[1u8]", "map[2u8]"]
+        );
+
+        let conflicts = value.merge(rusty_value!(struct Other), &MergeStrategy::default());
+        assert_eq!(
+            conflicts,
+            vec![MergeConflict::TypeMismatch {
+                path: Path::new(),
+                ours: String::from("Config"),
+                theirs: String::from("Other"),
+            }]
+        );
+    }
+}
@@ -0,0 +1,588 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Display};
+
+use crate::{
+    Change, Enum, Fields, ParsePathError, Path, PathError, PathSegment, Primitive, RustyValue,
+    Struct, Value,
+};
+
+/// A single operation of a [Patch].
+/// The operations behave like the ones of JSON Patch (RFC 6902).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    /// Inserts the value into a list or sets a field or map entry.
+    /// The parent of the path must exist.
+    Add { path: Path, value: Value },
+    /// Removes the value at the path which must exist
+    Remove { path: Path },
+    /// Replaces the value at the path which must exist
+    Replace { path: Path, value: Value },
+    /// Removes the value at `from` and adds it at `path`
+    Move { from: Path, path: Path },
+    /// Adds a copy of the value at `from` at `path`
+    Copy { from: Path, path: Path },
+    /// Checks that the value at the path is equal to the given value
+    Test { path: Path, value: Value },
+}
+
+/// A list of operations that are applied to a value one after another
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Patch {
+    operations: Vec<Operation>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the patch with an [Operation::Add] appended
+    pub fn add(self, path: Path, value: Value) -> Self {
+        self.with(Operation::Add { path, value })
+    }
+
+    /// Returns the patch with an [Operation::Remove] appended
+    pub fn remove(self, path: Path) -> Self {
+        self.with(Operation::Remove { path })
+    }
+
+    /// Returns the patch with an [Operation::Replace] appended
+    pub fn replace(self, path: Path, value: Value) -> Self {
+        self.with(Operation::Replace { path, value })
+    }
+
+    /// Returns the patch with an [Operation::Move] appended
+    pub fn move_value(self, from: Path, path: Path) -> Self {
+        self.with(Operation::Move { from, path })
+    }
+
+    /// Returns the patch with an [Operation::Copy] appended
+    pub fn copy(self, from: Path, path: Path) -> Self {
+        self.with(Operation::Copy { from, path })
+    }
+
+    /// Returns the patch with an [Operation::Test] appended
+    pub fn test(self, path: Path, value: Value) -> Self {
+        self.with(Operation::Test { path, value })
+    }
+
+    fn with(mut self, operation: Operation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    pub fn push(&mut self, operation: Operation) {
+        self.operations.push(operation)
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// Creates a patch from the changes returned by [crate::diff].
+    /// Applying it to the old value of the diff results in the new value.
+    pub fn from_changes(changes: &[Change]) -> Self {
+        let mut operations = Vec::with_capacity(changes.len());
+        let mut removals = Vec::new();
+
+        for change in changes {
+            match change {
+                Change::Added { path, value } => operations.push(Operation::Add {
+                    path: path.clone(),
+                    value: value.clone(),
+                }),
+                // list items are removed from the back so that the indices stay valid
+                Change::Removed { path, .. } => {
+                    removals.push(Operation::Remove { path: path.clone() })
+                }
+                Change::Changed { path, new, .. }
+                | Change::TypeChanged { path, new, .. }
+                | Change::VariantChanged { path, new, .. } => operations.push(Operation::Replace {
+                    path: path.clone(),
+                    value: new.clone(),
+                }),
+            }
+        }
+        operations.extend(removals.into_iter().rev());
+
+        Self { operations }
+    }
+
+    /// Applies all operations to the value.
+    /// If an operation fails the value is left unchanged.
+    pub fn apply(&self, value: &mut Value) -> Result<(), PatchError> {
+        let mut patched = value.clone();
+
+        for (index, operation) in self.operations.iter().enumerate() {
+            apply_operation(&mut patched, operation).map_err(|kind| PatchError {
+                operation: index,
+                kind,
+            })?;
+        }
+        *value = patched;
+
+        Ok(())
+    }
+}
+
+impl From<Vec<Operation>> for Patch {
+    fn from(operations: Vec<Operation>) -> Self {
+        Self { operations }
+    }
+}
+
+/// Patches are represented like a derived struct with an `operations` field
+/// so they can be written with any format and sent to other programs
+impl RustyValue for Patch {
+    fn into_rusty_value(self) -> Value {
+        Value::Struct(Struct {
+            name: Cow::Borrowed("Patch"),
+            fields: Fields::Named(HashMap::from([(
+                Cow::Borrowed("operations"),
+                self.operations.into_rusty_value(),
+            )])),
+        })
+    }
+}
+
+/// Operations are represented like a derived enum with paths written as strings
+impl RustyValue for Operation {
+    fn into_rusty_value(self) -> Value {
+        let (variant, fields) = match self {
+            Operation::Add { path, value } => {
+                ("Add", vec![path_field("path", path), ("value", value)])
+            }
+            Operation::Remove { path } => ("Remove", vec![path_field("path", path)]),
+            Operation::Replace { path, value } => {
+                ("Replace", vec![path_field("path", path), ("value", value)])
+            }
+            Operation::Move { from, path } => (
+                "Move",
+                vec![path_field("from", from), path_field("path", path)],
+            ),
+            Operation::Copy { from, path } => (
+                "Copy",
+                vec![path_field("from", from), path_field("path", path)],
+            ),
+            Operation::Test { path, value } => {
+                ("Test", vec![path_field("path", path), ("value", value)])
+            }
+        };
+
+        Value::Enum(Enum {
+            name: Cow::Borrowed("Operation"),
+            variant: Cow::Borrowed(variant),
+            fields: Fields::Named(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| (Cow::Borrowed(name), value))
+                    .collect(),
+            ),
+        })
+    }
+}
+
+fn path_field(name: &'static str, path: Path) -> (&'static str, Value) {
+    (name, Value::Primitive(Primitive::String(path.to_string())))
+}
+
+/// Reads a patch from the value of [Patch::into_rusty_value] or from a list of operations.
+/// The names of the struct and enum aren't checked so that the patch can be read from
+/// formats that don't keep them.
+impl TryFrom<Value> for Patch {
+    type Error = InvalidPatchError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let operations = match value {
+            Value::Struct(Struct {
+                fields: Fields::Named(mut fields),
+                ..
+            }) => fields
+                .remove("operations")
+                .ok_or(InvalidPatchError::MissingField("operations"))?,
+            value => value,
+        };
+        let Value::List(operations) = operations else {
+            return Err(InvalidPatchError::UnexpectedValue);
+        };
+
+        operations
+            .into_iter()
+            .map(Operation::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Patch::from)
+    }
+}
+
+/// Reads an operation from the value of [Operation::into_rusty_value]
+impl TryFrom<Value> for Operation {
+    type Error = InvalidPatchError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let Value::Enum(Enum {
+            variant,
+            fields: Fields::Named(mut fields),
+            ..
+        }) = value
+        else {
+            return Err(InvalidPatchError::UnexpectedValue);
+        };
+        let mut field = |name: &'static str| {
+            fields
+                .remove(name)
+                .ok_or(InvalidPatchError::MissingField(name))
+        };
+        let operation = match &*variant {
+            "Add" => Operation::Add {
+                path: parse_path(field("path")?)?,
+                value: field("value")?,
+            },
+            "Remove" => Operation::Remove {
+                path: parse_path(field("path")?)?,
+            },
+            "Replace" => Operation::Replace {
+                path: parse_path(field("path")?)?,
+                value: field("value")?,
+            },
+            "Move" => Operation::Move {
+                from: parse_path(field("from")?)?,
+                path: parse_path(field("path")?)?,
+            },
+            "Copy" => Operation::Copy {
+                from: parse_path(field("from")?)?,
+                path: parse_path(field("path")?)?,
+            },
+            "Test" => Operation::Test {
+                path: parse_path(field("path")?)?,
+                value: field("value")?,
+            },
+            _ => return Err(InvalidPatchError::UnknownOperation(variant.into_owned())),
+        };
+
+        Ok(operation)
+    }
+}
+
+fn parse_path(value: Value) -> Result<Path, InvalidPatchError> {
+    match value {
+        Value::Primitive(Primitive::String(path)) => {
+            path.parse().map_err(InvalidPatchError::InvalidPath)
+        }
+        _ => Err(InvalidPatchError::UnexpectedValue),
+    }
+}
+
+impl Value {
+    /// Applies the patch to the value.
+    /// If an operation fails the value is left unchanged.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), PatchError> {
+        patch.apply(self)
+    }
+}
+
+fn apply_operation(value: &mut Value, operation: &Operation) -> Result<(), PatchErrorKind> {
+    match operation {
+        Operation::Add { path, value: v } => add(value, path, v.clone()),
+        Operation::Remove { path } => remove(value, path).map(|_| ()),
+        Operation::Replace { path, value: v } => {
+            let target = value
+                .get_mut(path)
+                .ok_or_else(|| PatchErrorKind::NotFound(path.clone()))?;
+            *target = v.clone();
+            Ok(())
+        }
+        Operation::Move { from, path } => {
+            if path.segments().starts_with(from.segments()) && path != from {
+                return Err(PatchErrorKind::MoveIntoChild);
+            }
+            let v = remove(value, from)?;
+            add(value, path, v)
+        }
+        Operation::Copy { from, path } => {
+            let v = value
+                .get(from)
+                .ok_or_else(|| PatchErrorKind::NotFound(from.clone()))?
+                .clone();
+            add(value, path, v)
+        }
+        Operation::Test {
+            path,
+            value: expected,
+        } => match value.get(path) {
+            Some(v) if v == expected => Ok(()),
+            Some(_) => Err(PatchErrorKind::TestFailed(path.clone())),
+            None => Err(PatchErrorKind::NotFound(path.clone())),
+        },
+    }
+}
+
+/// Adds the value at the path, inserting it if the path points into a list
+fn add(value: &mut Value, path: &Path, v: Value) -> Result<(), PatchErrorKind> {
+    if let Some((PathSegment::Index(index), parent)) = path.segments().split_last() {
+        if let Some(Value::List(list)) = value.get_mut(&Path::from(parent.to_vec())) {
+            if *index > list.len() {
+                return Err(PathError::IndexOutOfBounds(*index).into());
+            }
+            list.insert(*index, v);
+            return Ok(());
+        }
+    }
+    value.set(path, v)?;
+
+    Ok(())
+}
+
+fn remove(value: &mut Value, path: &Path) -> Result<Value, PatchErrorKind> {
+    if path.is_empty() {
+        return Err(PatchErrorKind::RemoveRoot);
+    }
+    value
+        .remove(path)
+        .ok_or_else(|| PatchErrorKind::NotFound(path.clone()))
+}
+
+/// An error that occurred while applying a [Patch]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatchError {
+    /// The index of the operation that failed
+    pub operation: usize,
+    pub kind: PatchErrorKind,
+}
+
+/// The reason why an operation of a [Patch] failed
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatchErrorKind {
+    /// There's no value at the path
+    NotFound(Path),
+    /// The value couldn't be added at the path
+    Path(PathError),
+    /// The value at the path isn't equal to the expected value
+    TestFailed(Path),
+    /// A value can't be moved into one of its own children
+    MoveIntoChild,
+    /// The root value can't be removed
+    RemoveRoot,
+}
+
+impl Display for PatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "operation {} failed: {}", self.operation, self.kind)
+    }
+}
+
+impl Display for PatchErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatchErrorKind::NotFound(path) => write!(f, "no value at path `{path}`"),
+            PatchErrorKind::Path(e) => e.fmt(f),
+            PatchErrorKind::TestFailed(path) => {
+                write!(f, "the value at path `{path}` doesn't match")
+            }
+            PatchErrorKind::MoveIntoChild => write!(f, "can't move a value into its own child"),
+            PatchErrorKind::RemoveRoot => write!(f, "can't remove the root value"),
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+impl From<PathError> for PatchErrorKind {
+    fn from(e: PathError) -> Self {
+        Self::Path(e)
+    }
+}
+
+/// An error that occurred while converting a [Value] into a [Patch] or an [Operation]
+#[derive(Clone, Debug, PartialEq)]
+pub enum InvalidPatchError {
+    /// The value doesn't have the shape of a patch, an operation or a path
+    UnexpectedValue,
+    /// The value has no field with the given name
+    MissingField(&'static str),
+    /// The variant of the operation isn't known
+    UnknownOperation(String),
+    /// A path of the operation couldn't be parsed
+    InvalidPath(ParsePathError),
+}
+
+impl Display for InvalidPatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidPatchError::UnexpectedValue => write!(f, "the value isn't a patch"),
+            InvalidPatchError::MissingField(name) => write!(f, "missing field `{name}`"),
+            InvalidPatchError::UnknownOperation(variant) => {
+                write!(f, "unknown operation `{variant}`")
+            }
+            InvalidPatchError::InvalidPath(e) => write!(f, "invalid path: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for InvalidPatchError {}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    fn path(path: &str) -> Path {
+        path.parse().unwrap()
+    }
+
+    fn test_value() -> Value {
+        rusty_value!(struct Config {
+            hosts: ["a", "b"],
+            limits: { "cpu" => 2u8 },
+            mode: enum Mode::Fast,
+        })
+    }
+
+    #[test]
+    fn it_applies_operations() {
+        let mut value = test_value();
+        let patch = Patch::new()
+            .test(path("hosts[0]"), rusty_value!("a"))
+            .add(path("hosts[1]"), rusty_value!("c"))
+            .add(path("hosts[3]"), rusty_value!("d"))
+            .remove(path("hosts[0]"))
+            .replace(path("mode"), rusty_value!(enum Mode::Slow { delay: 1u32 }))
+            .add(path("limits.memory"), rusty_value!(512u32))
+            .copy(path("limits.cpu"), path("mode.delay"))
+            .move_value(path("limits.memory"), path("memory"));
+
+        value.apply_patch(&patch).unwrap();
+
+        assert_eq!(
+            value,
+            rusty_value!(struct Config {
+                hosts: ["c", "b", "d"],
+                limits: { "cpu" => 2u8 },
+                mode: enum Mode::Slow { delay: 2u8 },
+                memory: 512u32,
+            })
+        );
+    }
+
+    #[test]
+    fn it_applies_patches_transactionally() {
+        let mut value = test_value();
+        let patch = Patch::new()
+            .remove(path("hosts[0]"))
+            .test(path("hosts[0]"), rusty_value!("a"));
+
+        assert_eq!(
+            patch.apply(&mut value),
+            Err(PatchError {
+                operation: 1,
+                kind: PatchErrorKind::TestFailed(path("hosts[0]")),
+            })
+        );
+        assert_eq!(value, test_value());
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        let error = |patch: Patch| patch.apply(&mut test_value()).unwrap_err().kind;
+
+        assert_eq!(
+            error(Patch::new().remove(path("hosts[2]"))),
+            PatchErrorKind::NotFound(path("hosts[2]"))
+        );
+        assert_eq!(
+            error(Patch::new().replace(path("missing"), Value::None)),
+            PatchErrorKind::NotFound(path("missing"))
+        );
+        assert_eq!(
+            error(Patch::new().add(path("hosts[3]"), Value::None)),
+            PatchErrorKind::Path(PathError::IndexOutOfBounds(3))
+        );
+        assert_eq!(
+            error(Patch::new().add(path("missing.field"), Value::None)),
+            PatchErrorKind::Path(PathError::ParentNotFound)
+        );
+        assert_eq!(
+            error(Patch::new().move_value(path("limits"), path("limits.other"))),
+            PatchErrorKind::MoveIntoChild
+        );
+        assert_eq!(
+            error(Patch::new().remove(Path::new())),
+            PatchErrorKind::RemoveRoot
+        );
+    }
+
+    #[test]
+    fn it_applies_diffs() {
+        let old = test_value();
+        let new = rusty_value!(struct Config {
+            hosts: ["a"],
+            limits: { "memory" => 512u32 },
+            mode: enum Mode::Slow { delay: 1u32 },
+        });
+        let mut value = old.clone();
+
+        value
+            .apply_patch(&Patch::from_changes(&diff(&old, &new)))
+            .unwrap();
+        assert_eq!(value, new);
+
+        let longer = rusty_value!(struct Config {
+            hosts: ["a", "b", "c", "d"],
+            limits: {},
+            mode: enum Mode::Fast,
+        });
+        value
+            .apply_patch(&Patch::from_changes(&diff(&value, &longer)))
+            .unwrap();
+        assert_eq!(value, longer);
+        value
+            .apply_patch(&Patch::from_changes(&diff(&value, &old)))
+            .unwrap();
+        assert_eq!(value, old);
+    }
+
+    #[test]
+    fn it_converts_patches_to_values() {
+        let patch = Patch::new()
+            .add(path("hosts[1]"), rusty_value!("c"))
+            .remove(path("hosts[0]"))
+            .replace(path(r#"limits["cpu"]"#), rusty_value!(4u8))
+            .move_value(path("mode"), path("old_mode"))
+            .copy(path("hosts"), path("backup"))
+            .test(path("limits[1u8]"), Value::None);
+
+        let value = patch.clone().into_rusty_value();
+        let Value::Struct(Struct {
+            fields: Fields::Named(fields),
+            ..
+        }) = &value
+        else {
+            panic!("expected a struct");
+        };
+        let Value::List(operations) = fields["operations"].clone() else {
+            panic!("expected a list");
+        };
+        assert_eq!(
+            operations[1],
+            rusty_value!(enum Operation::Remove { path: "hosts[0]" })
+        );
+        assert_eq!(Patch::try_from(value), Ok(patch.clone()));
+        assert_eq!(Patch::try_from(Value::List(operations.clone())), Ok(patch));
+
+        assert_eq!(
+            Operation::try_from(rusty_value!(enum Operation::Remove { from: "a" })),
+            Err(InvalidPatchError::MissingField("path"))
+        );
+        assert_eq!(
+            Operation::try_from(rusty_value!(enum Operation::Drop { path: "a" })),
+            Err(InvalidPatchError::UnknownOperation(String::from("Drop")))
+        );
+        assert_eq!(
+            Operation::try_from(rusty_value!(enum Operation::Remove { path: "a[" })),
+            Err(InvalidPatchError::InvalidPath(
+                ParsePathError::UnexpectedEnd
+            ))
+        );
+        assert_eq!(
+            Patch::try_from(rusty_value!("a")),
+            Err(InvalidPatchError::UnexpectedValue)
+        );
+    }
+}
@@ -0,0 +1,924 @@
+use std::{borrow::Cow, collections::HashMap, fmt::Display, str::FromStr};
+
+use crate::{Fields, HashablePrimitive, HashableRustyValue, HashableValue, Integer, Value};
+
+/// A single step of a [Path]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// A named field of a struct or enum variant, or a map entry with a string key
+    Field(String),
+    /// An unnamed field of a struct or enum variant, a list item, or a map entry with an integer key
+    Index(usize),
+    /// The key of a map entry
+    Key(HashableValue),
+}
+
+/// A path to a value nested inside a [crate::Value].
+///
+/// Paths can be parsed from strings like `config.servers[0].port`.
+/// Named fields are separated with a `.`, indices are written in brackets
+/// and map keys that aren't identifiers can be written as `["key"]`.
+/// Other map keys are written like rust literals, e.g. `[-1i32]`, `['c']`, `[true]`,
+/// `[None]` or `[[1u8, "a"]]`. Integers without a suffix match integer keys of any type.
+/// Os string keys are written as strings.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+impl Path {
+    /// Creates an empty path that refers to the root value
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the path with a named field appended
+    pub fn field<S: Into<String>>(mut self, name: S) -> Self {
+        self.segments.push(PathSegment::Field(name.into()));
+        self
+    }
+
+    /// Returns the path with an index appended
+    pub fn index(mut self, index: usize) -> Self {
+        self.segments.push(PathSegment::Index(index));
+        self
+    }
+
+    /// Returns the path with a map key appended
+    pub fn key<H: HashableRustyValue>(mut self, key: H) -> Self {
+        self.segments
+            .push(PathSegment::Key(key.into_hashable_rusty_value()));
+        self
+    }
+
+    pub fn push(&mut self, segment: PathSegment) {
+        self.segments.push(segment)
+    }
+
+    pub fn pop(&mut self) -> Option<PathSegment> {
+        self.segments.pop()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+}
+
+impl From<Vec<PathSegment>> for Path {
+    fn from(segments: Vec<PathSegment>) -> Self {
+        Self { segments }
+    }
+}
+
+impl FromIterator<PathSegment> for Path {
+    fn from_iter<T: IntoIterator<Item = PathSegment>>(iter: T) -> Self {
+        Self {
+            segments: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Field(name) if is_identifier(name) => {
+                    if i > 0 {
+                        write!(f, ".")?;
+                    }
+                    write!(f, "{name}")?;
+                }
+                PathSegment::Field(name) => write!(f, "[\"{}\"]", name.escape_debug())?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+                PathSegment::Key(key) => write!(f, "[{}]", KeyDisplay(key))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+struct KeyDisplay<'a>(&'a HashableValue);
+
+impl Display for KeyDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            HashableValue::Primitive(HashablePrimitive::String(s)) => {
+                write!(f, "\"{}\"", s.escape_debug())
+            }
+            HashableValue::Primitive(HashablePrimitive::OsString(o)) => {
+                write!(f, "\"{}\"", o.to_string_lossy().escape_debug())
+            }
+            HashableValue::Primitive(HashablePrimitive::Char(c)) => write!(f, "{c:?}"),
+            HashableValue::Primitive(HashablePrimitive::Integer(i)) => {
+                write!(f, "{i}{}", integer_suffix(i))
+            }
+            HashableValue::Primitive(p) => write!(f, "{p}"),
+            HashableValue::List(l) => {
+                write!(f, "[")?;
+                for (i, key) in l.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", KeyDisplay(key))?;
+                }
+                write!(f, "]")
+            }
+            HashableValue::None => write!(f, "None"),
+        }
+    }
+}
+
+fn integer_suffix(integer: &Integer) -> &'static str {
+    match integer {
+        Integer::USize(_) => "usize",
+        Integer::ISize(_) => "isize",
+        Integer::U8(_) => "u8",
+        Integer::I8(_) => "i8",
+        Integer::U16(_) => "u16",
+        Integer::I16(_) => "i16",
+        Integer::U32(_) => "u32",
+        Integer::I32(_) => "i32",
+        Integer::U64(_) => "u64",
+        Integer::I64(_) => "i64",
+        Integer::U128(_) => "u128",
+        Integer::I128(_) => "i128",
+    }
+}
+
+/// Parses an integer with an optional type suffix.
+/// Integers without a suffix are parsed as i64 or i128 if they don't fit.
+fn parse_integer(digits: &str, suffix: &str) -> Option<Integer> {
+    let integer = match suffix {
+        "usize" => Integer::USize(digits.parse().ok()?),
+        "isize" => Integer::ISize(digits.parse().ok()?),
+        "u8" => Integer::U8(digits.parse().ok()?),
+        "i8" => Integer::I8(digits.parse().ok()?),
+        "u16" => Integer::U16(digits.parse().ok()?),
+        "i16" => Integer::I16(digits.parse().ok()?),
+        "u32" => Integer::U32(digits.parse().ok()?),
+        "i32" => Integer::I32(digits.parse().ok()?),
+        "u64" => Integer::U64(digits.parse().ok()?),
+        "i64" => Integer::I64(digits.parse().ok()?),
+        "u128" => Integer::U128(digits.parse().ok()?),
+        "i128" => Integer::I128(digits.parse().ok()?),
+        "" => digits
+            .parse()
+            .map(Integer::I64)
+            .or_else(|_| digits.parse().map(Integer::I128))
+            .ok()?,
+        _ => return None,
+    };
+
+    Some(integer)
+}
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().all(|c| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// An error that occurred while parsing a [Path]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParsePathError {
+    /// The character at the given byte position wasn't expected
+    UnexpectedChar { position: usize, char: char },
+    /// The path ended in the middle of a segment
+    UnexpectedEnd,
+    /// The index at the given byte position doesn't fit into a usize
+    InvalidIndex { position: usize },
+    /// The integer key at the given byte position has an unknown suffix or doesn't fit into its type
+    InvalidKey { position: usize },
+}
+
+impl Display for ParsePathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParsePathError::UnexpectedChar { position, char } => {
+                write!(f, "unexpected character {char:?} at position {position}")
+            }
+            ParsePathError::UnexpectedEnd => write!(f, "unexpected end of path"),
+            ParsePathError::InvalidIndex { position } => {
+                write!(f, "invalid index at position {position}")
+            }
+            ParsePathError::InvalidKey { position } => {
+                write!(f, "invalid key at position {position}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParsePathError {}
+
+impl FromStr for Path {
+    type Err = ParsePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PathParser::new(s).parse()
+    }
+}
+
+/// Parses paths and the parts of paths that are shared with the query syntax
+pub(crate) struct PathParser<'a> {
+    pub(crate) input: &'a str,
+    pub(crate) chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> PathParser<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            chars: input.char_indices().peekable(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Path, ParsePathError> {
+        let mut path = Path::new();
+
+        if let Some((_, c)) = self.chars.peek() {
+            if *c != '[' && *c != '.' {
+                path.push(self.parse_name()?);
+            }
+        }
+        while let Some((position, c)) = self.chars.next() {
+            match c {
+                '.' => path.push(self.parse_name()?),
+                '[' => {
+                    path.push(self.parse_bracketed()?);
+                    self.expect(']')?;
+                }
+                char => return Err(ParsePathError::UnexpectedChar { position, char }),
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Parses a field name. Names that only consist of digits are indices.
+    fn parse_name(&mut self) -> Result<PathSegment, ParsePathError> {
+        let start = self.position();
+        while self
+            .chars
+            .peek()
+            .is_some_and(|(_, c)| !matches!(c, '.' | '[' | ']'))
+        {
+            self.chars.next();
+        }
+        let name = &self.input[start..self.position()];
+
+        if name.is_empty() {
+            match self.chars.peek() {
+                Some((position, char)) => Err(ParsePathError::UnexpectedChar {
+                    position: *position,
+                    char: *char,
+                }),
+                None => Err(ParsePathError::UnexpectedEnd),
+            }
+        } else if name.chars().all(|c| c.is_ascii_digit()) {
+            name.parse()
+                .map(PathSegment::Index)
+                .map_err(|_| ParsePathError::InvalidIndex { position: start })
+        } else {
+            Ok(PathSegment::Field(name.to_owned()))
+        }
+    }
+
+    /// Parses the content between brackets
+    pub(crate) fn parse_bracketed(&mut self) -> Result<PathSegment, ParsePathError> {
+        match self.chars.peek().copied() {
+            Some((position, c)) if c.is_ascii_digit() => {
+                let digits = self.take_while(|c| c.is_ascii_digit());
+                if self.chars.peek().is_some_and(|(_, c)| c.is_alphabetic()) {
+                    return self.parse_integer_suffix(position, digits).map(|i| {
+                        PathSegment::Key(HashableValue::Primitive(HashablePrimitive::Integer(i)))
+                    });
+                }
+                digits
+                    .parse()
+                    .map(PathSegment::Index)
+                    .map_err(|_| ParsePathError::InvalidIndex { position })
+            }
+            _ => self.parse_key().map(PathSegment::Key),
+        }
+    }
+
+    /// Parses a map key that is written like a rust literal
+    fn parse_key(&mut self) -> Result<HashableValue, ParsePathError> {
+        let key = match self.chars.peek().copied() {
+            Some((_, '"')) => {
+                self.chars.next();
+                self.parse_quoted('"')?.into_hashable_rusty_value()
+            }
+            Some((position, '\'')) => {
+                self.chars.next();
+                let key = self.parse_quoted('\'')?;
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => HashableValue::Primitive(HashablePrimitive::Char(c)),
+                    _ => {
+                        return Err(ParsePathError::UnexpectedChar {
+                            position,
+                            char: '\'',
+                        })
+                    }
+                }
+            }
+            Some((_, '[')) => {
+                self.chars.next();
+                let mut keys = Vec::new();
+                loop {
+                    self.take_while(|c| c == ' ');
+                    if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+                        break;
+                    }
+                    keys.push(self.parse_key()?);
+                    self.take_while(|c| c == ' ');
+                    if self.chars.next_if(|(_, c)| *c == ',').is_none() {
+                        self.expect(']')?;
+                        break;
+                    }
+                }
+                HashableValue::List(keys)
+            }
+            Some((position, c)) if c == '-' || c.is_ascii_digit() => {
+                self.chars.next();
+                self.take_while(|c| c.is_ascii_digit());
+                let digits = &self.input[position..self.position()];
+                let integer = self.parse_integer_suffix(position, digits)?;
+                HashableValue::Primitive(HashablePrimitive::Integer(integer))
+            }
+            Some((position, c)) if c.is_alphabetic() => {
+                match self.take_while(|c| c.is_alphanumeric()) {
+                    "true" => HashableValue::Primitive(HashablePrimitive::Bool(true)),
+                    "false" => HashableValue::Primitive(HashablePrimitive::Bool(false)),
+                    "None" => HashableValue::None,
+                    _ => return Err(ParsePathError::UnexpectedChar { position, char: c }),
+                }
+            }
+            Some((position, char)) => {
+                return Err(ParsePathError::UnexpectedChar { position, char })
+            }
+            None => return Err(ParsePathError::UnexpectedEnd),
+        };
+
+        Ok(key)
+    }
+
+    /// Parses the optional type suffix after the digits of an integer key
+    fn parse_integer_suffix(
+        &mut self,
+        position: usize,
+        digits: &str,
+    ) -> Result<Integer, ParsePathError> {
+        let suffix = self.take_while(|c| c.is_alphanumeric());
+        parse_integer(digits, suffix).ok_or(ParsePathError::InvalidKey { position })
+    }
+
+    /// Consumes characters as long as they match and returns them
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position();
+        while self.chars.next_if(|(_, c)| f(*c)).is_some() {}
+
+        &self.input[start..self.position()]
+    }
+
+    /// Parses a quoted string after the opening quote with rust style escapes
+    pub(crate) fn parse_quoted(&mut self, quote: char) -> Result<String, ParsePathError> {
+        let mut s = String::new();
+
+        loop {
+            match self.chars.next() {
+                Some((_, c)) if c == quote => return Ok(s),
+                Some((_, '\\')) => s.push(self.parse_escape()?),
+                Some((_, c)) => s.push(c),
+                None => return Err(ParsePathError::UnexpectedEnd),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<char, ParsePathError> {
+        let c = match self.chars.next() {
+            Some((_, 'n')) => '\n',
+            Some((_, 'r')) => '\r',
+            Some((_, 't')) => '\t',
+            Some((_, '0')) => '\0',
+            Some((_, c @ ('\\' | '"' | '\''))) => c,
+            Some((_, 'u')) => {
+                self.expect('{')?;
+                let start = self.position();
+                while self
+                    .chars
+                    .peek()
+                    .is_some_and(|(_, c)| c.is_ascii_hexdigit())
+                {
+                    self.chars.next();
+                }
+                let c = u32::from_str_radix(&self.input[start..self.position()], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or(ParsePathError::UnexpectedChar {
+                        position: start,
+                        char: 'u',
+                    })?;
+                self.expect('}')?;
+                c
+            }
+            Some((position, char)) => {
+                return Err(ParsePathError::UnexpectedChar { position, char })
+            }
+            None => return Err(ParsePathError::UnexpectedEnd),
+        };
+
+        Ok(c)
+    }
+
+    pub(crate) fn expect(&mut self, expected: char) -> Result<(), ParsePathError> {
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((position, char)) => Err(ParsePathError::UnexpectedChar { position, char }),
+            None => Err(ParsePathError::UnexpectedEnd),
+        }
+    }
+
+    pub(crate) fn position(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map(|(i, _)| *i)
+            .unwrap_or(self.input.len())
+    }
+}
+
+/// An error that occurred while modifying a value at a path
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// The value that should contain the last segment of the path doesn't exist
+    ParentNotFound,
+    /// The last segment of the path can't be used on the value that should contain it
+    InvalidSegment(PathSegment),
+    /// The index is past the end of the list or fields
+    IndexOutOfBounds(usize),
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathError::ParentNotFound => write!(f, "the parent of the path doesn't exist"),
+            PathError::InvalidSegment(segment) => {
+                write!(f, "the segment {segment:?} can't be used on the parent")
+            }
+            PathError::IndexOutOfBounds(index) => write!(f, "index {index} is out of bounds"),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+impl Value {
+    /// Returns the value at the given path
+    pub fn get(&self, path: &Path) -> Option<&Value> {
+        path.segments()
+            .iter()
+            .try_fold(self, |value, segment| value.child(segment))
+    }
+
+    /// Returns a mutable reference to the value at the given path
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut Value> {
+        path.segments()
+            .iter()
+            .try_fold(self, |value, segment| value.child_mut(segment))
+    }
+
+    /// Sets the value at the given path and returns the previous value.
+    /// Named fields and map entries are inserted if they don't exist yet
+    /// and an index equal to the length of a list appends to it.
+    pub fn set(&mut self, path: &Path, value: Value) -> Result<Option<Value>, PathError> {
+        let Some((last, parent)) = path.segments().split_last() else {
+            return Ok(Some(std::mem::replace(self, value)));
+        };
+        let parent = parent
+            .iter()
+            .try_fold(self, |value, segment| value.child_mut(segment))
+            .ok_or(PathError::ParentNotFound)?;
+
+        match (parent, last) {
+            (
+                Value::Struct(crate::Struct {
+                    fields: Fields::Named(fields),
+                    ..
+                })
+                | Value::Enum(crate::Enum {
+                    fields: Fields::Named(fields),
+                    ..
+                }),
+                segment,
+            ) => {
+                let name = field_name(segment)
+                    .ok_or_else(|| PathError::InvalidSegment(segment.clone()))?;
+                Ok(fields.insert(Cow::Owned(name.to_owned()), value))
+            }
+            (Value::Map(map), segment) => {
+                let key = match find_key(map, segment) {
+                    Some(key) => key.clone(),
+                    None => match segment {
+                        PathSegment::Field(name) => name.to_owned().into_hashable_rusty_value(),
+                        PathSegment::Key(key) => key.clone(),
+                        PathSegment::Index(_) => {
+                            return Err(PathError::InvalidSegment(segment.clone()))
+                        }
+                    },
+                };
+                Ok(map.insert(key, value))
+            }
+            (Value::List(list), PathSegment::Index(index)) if *index == list.len() => {
+                list.push(value);
+                Ok(None)
+            }
+            (
+                Value::List(list)
+                | Value::Struct(crate::Struct {
+                    fields: Fields::Unnamed(list),
+                    ..
+                })
+                | Value::Enum(crate::Enum {
+                    fields: Fields::Unnamed(list),
+                    ..
+                }),
+                PathSegment::Index(index),
+            ) => list
+                .get_mut(*index)
+                .map(|old| Some(std::mem::replace(old, value)))
+                .ok_or(PathError::IndexOutOfBounds(*index)),
+            (_, segment) => Err(PathError::InvalidSegment(segment.clone())),
+        }
+    }
+
+    /// Removes the value at the given path and returns it.
+    /// Removing a list item or an unnamed field shifts the following items.
+    pub fn remove(&mut self, path: &Path) -> Option<Value> {
+        let (last, parent) = path.segments().split_last()?;
+        let parent = parent
+            .iter()
+            .try_fold(self, |value, segment| value.child_mut(segment))?;
+
+        match parent {
+            Value::Struct(s) => remove_field(&mut s.fields, last),
+            Value::Enum(e) => remove_field(&mut e.fields, last),
+            Value::Map(map) => {
+                let key = find_key(map, last)?.clone();
+                map.remove(&key)
+            }
+            Value::List(list) => remove_index(list, last),
+            Value::Primitive(_) | Value::None => None,
+        }
+    }
+
+    /// Returns the direct children of the value with the segments that lead to them.
+    /// Named fields and map entries are sorted by their keys.
+    pub(crate) fn children(&self) -> Vec<(PathSegment, &Value)> {
+        match self {
+            Value::Struct(crate::Struct { fields, .. })
+            | Value::Enum(crate::Enum { fields, .. }) => match fields {
+                Fields::Named(named) => {
+                    let mut named = named.iter().collect::<Vec<_>>();
+                    named.sort_by_key(|(k, _)| *k);
+                    named
+                        .into_iter()
+                        .map(|(k, v)| (PathSegment::Field(k.to_string()), v))
+                        .collect()
+                }
+                Fields::Unnamed(unnamed) => indexed(unnamed),
+                Fields::Unit => Vec::new(),
+            },
+            Value::Map(map) => {
+                let mut entries = map.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(k, _)| *k);
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (PathSegment::Key(k.clone()), v))
+                    .collect()
+            }
+            Value::List(list) => indexed(list),
+            Value::Primitive(_) | Value::None => Vec::new(),
+        }
+    }
+
+    pub(crate) fn child(&self, segment: &PathSegment) -> Option<&Value> {
+        match self {
+            Value::Struct(s) => field(&s.fields, segment),
+            Value::Enum(e) => field(&e.fields, segment),
+            Value::Map(map) => map.get(find_key(map, segment)?),
+            Value::List(list) => match segment {
+                PathSegment::Index(index) => list.get(*index),
+                _ => None,
+            },
+            Value::Primitive(_) | Value::None => None,
+        }
+    }
+
+    fn child_mut(&mut self, segment: &PathSegment) -> Option<&mut Value> {
+        match self {
+            Value::Struct(s) => field_mut(&mut s.fields, segment),
+            Value::Enum(e) => field_mut(&mut e.fields, segment),
+            Value::Map(map) => {
+                let key = find_key(map, segment)?.clone();
+                map.get_mut(&key)
+            }
+            Value::List(list) => match segment {
+                PathSegment::Index(index) => list.get_mut(*index),
+                _ => None,
+            },
+            Value::Primitive(_) | Value::None => None,
+        }
+    }
+}
+
+fn indexed(values: &[Value]) -> Vec<(PathSegment, &Value)> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (PathSegment::Index(i), v))
+        .collect()
+}
+
+/// Returns the name of a field if the segment can refer to a named field
+fn field_name(segment: &PathSegment) -> Option<&str> {
+    match segment {
+        PathSegment::Field(name) => Some(name),
+        PathSegment::Key(HashableValue::Primitive(HashablePrimitive::String(name))) => Some(name),
+        _ => None,
+    }
+}
+
+fn field<'a>(fields: &'a Fields, segment: &PathSegment) -> Option<&'a Value> {
+    match (fields, segment) {
+        (Fields::Named(named), segment) => named.get(field_name(segment)?),
+        (Fields::Unnamed(unnamed), PathSegment::Index(index)) => unnamed.get(*index),
+        _ => None,
+    }
+}
+
+fn field_mut<'a>(fields: &'a mut Fields, segment: &PathSegment) -> Option<&'a mut Value> {
+    match (fields, segment) {
+        (Fields::Named(named), segment) => named.get_mut(field_name(segment)?),
+        (Fields::Unnamed(unnamed), PathSegment::Index(index)) => unnamed.get_mut(*index),
+        _ => None,
+    }
+}
+
+fn remove_field(fields: &mut Fields, segment: &PathSegment) -> Option<Value> {
+    match fields {
+        Fields::Named(named) => named.remove(field_name(segment)?),
+        Fields::Unnamed(unnamed) => remove_index(unnamed, segment),
+        Fields::Unit => None,
+    }
+}
+
+fn remove_index(list: &mut Vec<Value>, segment: &PathSegment) -> Option<Value> {
+    match segment {
+        PathSegment::Index(index) if *index < list.len() => Some(list.remove(*index)),
+        _ => None,
+    }
+}
+
+/// Finds the key of the map entry the segment refers to.
+/// Fields match string keys and indices match integer keys of any type.
+/// Integer keys match integer keys of another type if there is no key of the same type.
+fn find_key<'a>(
+    map: &'a HashMap<HashableValue, Value>,
+    segment: &PathSegment,
+) -> Option<&'a HashableValue> {
+    match segment {
+        PathSegment::Key(key) => map
+            .get_key_value(key)
+            .map(|(k, _)| k)
+            .or_else(|| match key {
+                HashableValue::Primitive(HashablePrimitive::Integer(i)) => {
+                    find_integer_key(map, integer_to_i128(i)?)
+                }
+                _ => None,
+            }),
+        PathSegment::Field(name) => map
+            .get_key_value(&HashableValue::Primitive(HashablePrimitive::String(
+                name.to_owned(),
+            )))
+            .map(|(k, _)| k),
+        PathSegment::Index(index) => find_integer_key(map, *index as i128),
+    }
+}
+
+fn find_integer_key(map: &HashMap<HashableValue, Value>, value: i128) -> Option<&HashableValue> {
+    map.keys().find(|key| {
+        matches!(
+            key,
+            HashableValue::Primitive(HashablePrimitive::Integer(i)) if integer_to_i128(i) == Some(value)
+        )
+    })
+}
+
+/// Converts the integer into an i128 if it fits
+pub(crate) fn integer_to_i128(integer: &Integer) -> Option<i128> {
+    let value = match *integer {
+        Integer::USize(n) => n as i128,
+        Integer::ISize(n) => n as i128,
+        Integer::U8(n) => n as i128,
+        Integer::I8(n) => n as i128,
+        Integer::U16(n) => n as i128,
+        Integer::I16(n) => n as i128,
+        Integer::U32(n) => n as i128,
+        Integer::I32(n) => n as i128,
+        Integer::U64(n) => n as i128,
+        Integer::I64(n) => n as i128,
+        Integer::U128(n) => return i128::try_from(n).ok(),
+        Integer::I128(n) => n,
+    };
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::*;
+
+    fn test_value() -> Value {
+        rusty_value!(struct Config {
+            name: "test",
+            servers: [
+                struct Server { host: "localhost", port: 80u16 },
+                struct Server { host: "example.com", port: 443u16 },
+            ],
+            limits: { "cpu" => 2u8, "memory.max" => 512u32 },
+            codes: { 404u16 => "not found" },
+            mode: enum Mode::Fast(1u8, 2u8),
+        })
+    }
+
+    #[test]
+    fn it_parses_paths() {
+        let path: Path = "config.servers[0].port".parse().unwrap();
+        assert_eq!(
+            path,
+            Path::new()
+                .field("config")
+                .field("servers")
+                .index(0)
+                .field("port")
+        );
+
+        let path: Path = r#"[1]["a.b"]['c'].0"#.parse().unwrap();
+        let mut expected = Path::new().index(1).key("a.b");
+        expected.push(PathSegment::Key(HashableValue::Primitive(
+            HashablePrimitive::Char('c'),
+        )));
+        assert_eq!(path, expected.index(0));
+
+        assert_eq!("".parse::<Path>().unwrap(), Path::new());
+        assert_eq!(
+            "a..b".parse::<Path>(),
+            Err(ParsePathError::UnexpectedChar {
+                position: 2,
+                char: '.'
+            })
+        );
+        assert_eq!("a[0".parse::<Path>(), Err(ParsePathError::UnexpectedEnd));
+        assert_eq!(
+            "a[99999999999999999999999]".parse::<Path>(),
+            Err(ParsePathError::InvalidIndex { position: 2 })
+        );
+    }
+
+    #[test]
+    fn it_displays_paths() {
+        for path in [
+            "config.servers[0].port",
+            r#"limits["memory.max"]"#,
+            "a['c'][1]",
+        ] {
+            assert_eq!(path.parse::<Path>().unwrap().to_string(), path);
+        }
+        assert_eq!(Path::new().field("1").to_string(), r#"["1"]"#);
+    }
+
+    #[test]
+    fn it_roundtrips_map_keys() {
+        let path = Path::from(vec![
+            PathSegment::Key((-1i32).into_hashable_rusty_value()),
+            PathSegment::Key(404u16.into_hashable_rusty_value()),
+            PathSegment::Key(true.into_hashable_rusty_value()),
+            PathSegment::Key(HashableValue::List(vec![
+                1u8.into_hashable_rusty_value(),
+                2u8.into_hashable_rusty_value(),
+            ])),
+            PathSegment::Key(HashableValue::List(vec![
+                HashableValue::Primitive(HashablePrimitive::Char('k')),
+                "a\"b".into_hashable_rusty_value(),
+            ])),
+            PathSegment::Key(HashableValue::None),
+        ]);
+
+        assert_eq!(
+            path.to_string(),
+            r#"[-1i32][404u16][true][[1u8, 2u8]][['k', "a\"b"]][None]"#
+        );
+        assert_eq!(path.to_string().parse::<Path>().unwrap(), path);
+        assert_eq!(
+            "[1x]".parse::<Path>(),
+            Err(ParsePathError::InvalidKey { position: 1 })
+        );
+        assert_eq!("[-1".parse::<Path>(), Err(ParsePathError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn it_gets_values() {
+        let value = test_value();
+        let get = |path: &str| value.get(&path.parse().unwrap());
+
+        assert_eq!(get("servers[1].port"), Some(&rusty_value!(443u16)));
+        assert_eq!(get("limits.cpu"), Some(&rusty_value!(2u8)));
+        assert_eq!(get(r#"limits["memory.max"]"#), Some(&rusty_value!(512u32)));
+        assert_eq!(get("codes[404]"), Some(&rusty_value!("not found")));
+        assert_eq!(get("codes[404i64]"), Some(&rusty_value!("not found")));
+        assert_eq!(get("mode.1"), Some(&rusty_value!(2u8)));
+        assert_eq!(get(""), Some(&value));
+        assert_eq!(get("servers[2]"), None);
+        assert_eq!(get("name.foo"), None);
+    }
+
+    #[test]
+    fn it_sets_values() {
+        let mut value = test_value();
+        let mut set = |path: &str, v: Value| value.set(&path.parse().unwrap(), v);
+
+        assert_eq!(
+            set("servers[0].port", rusty_value!(8080u16)),
+            Ok(Some(rusty_value!(80u16)))
+        );
+        assert_eq!(set("servers[2]", rusty_value!(None)), Ok(None));
+        assert_eq!(set("limits.disk", rusty_value!(1u8)), Ok(None));
+        assert_eq!(set("debug", rusty_value!(true)), Ok(None));
+        assert_eq!(
+            set("servers[5]", rusty_value!(None)),
+            Err(PathError::IndexOutOfBounds(5))
+        );
+        assert_eq!(
+            set("missing.field", rusty_value!(None)),
+            Err(PathError::ParentNotFound)
+        );
+        assert_eq!(
+            set("name.field", rusty_value!(None)),
+            Err(PathError::InvalidSegment(PathSegment::Field(String::from(
+                "field"
+            ))))
+        );
+
+        let get = |path: &str| value.get(&path.parse().unwrap()).cloned();
+        assert_eq!(get("servers[0].port"), Some(rusty_value!(8080u16)));
+        assert_eq!(get("servers[2]"), Some(Value::None));
+        assert_eq!(get("limits.disk"), Some(rusty_value!(1u8)));
+        assert_eq!(get("debug"), Some(rusty_value!(true)));
+
+        if let Some(Value::Primitive(Primitive::Integer(Integer::U8(cpu)))) =
+            value.get_mut(&"limits.cpu".parse().unwrap())
+        {
+            *cpu += 1;
+        }
+        assert_eq!(
+            value.get(&"limits.cpu".parse().unwrap()),
+            Some(&rusty_value!(3u8))
+        );
+    }
+
+    #[test]
+    fn it_removes_values() {
+        let mut value = test_value();
+        let mut remove = |path: &str| value.remove(&path.parse().unwrap());
+
+        assert_eq!(remove("servers[0].host"), Some(rusty_value!("localhost")));
+        assert_eq!(remove("servers[0].host"), None);
+        assert_eq!(
+            remove("servers[0]"),
+            Some(rusty_value!(struct Server { port: 80u16 }))
+        );
+        assert_eq!(remove("codes[404]"), Some(rusty_value!("not found")));
+        assert_eq!(remove(""), None);
+
+        assert_eq!(
+            value.get(&"servers".parse().unwrap()),
+            Some(&rusty_value!([struct Server { host: "example.com", port: 443u16 }]))
+        );
+        assert_eq!(
+            value.get(&"codes".parse().unwrap()),
+            Some(&Value::Map(HashMap::new()))
+        );
+    }
+}
@@ -0,0 +1,725 @@
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+
+use crate::path::{integer_to_i128, PathParser};
+use crate::{
+    Float, ParsePathError, Path, PathSegment, Primitive, RustyValue, Sink, StreamRustyValue, Value,
+};
+
+/// A query that selects values nested inside a [crate::Value].
+///
+/// The syntax is similar to JSONPath:
+///
+/// - `$` refers to the root value and can be omitted
+/// - `.name`, `[0]` and `["key"]` select a child like in a [crate::Path]
+/// - `.*` and `[*]` select all children
+/// - `..` selects from the value and all of its descendants, e.g. `..port` or `..*`
+/// - `[?filter]` selects all children that match the filter
+///
+/// Filters compare values relative to the current child `@` with literals
+/// (numbers, strings, `true`, `false` and `None`) using `==`, `!=`, `<`, `<=`, `>` and `>=`.
+/// `name(@)` returns the name of a struct or enum and `variant(@)` the variant of an enum.
+/// An operand without a comparison checks if the value exists.
+/// Conditions can be combined with `&&`, `||`, `!` and parentheses.
+///
+/// ```
+/// use rusty_value::{rusty_value, Query};
+///
+/// let value = rusty_value!([
+///     enum State::Failed { code: 1u8 },
+///     enum State::Running,
+/// ]);
+/// let query: Query = r#"$..[?name(@) == "State" && variant(@) == "Failed"].code"#
+///     .parse()
+///     .unwrap();
+/// let matches = value.query(&query);
+///
+/// assert_eq!(matches.len(), 1);
+/// assert_eq!(matches[0].path.to_string(), "[0].code");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    steps: Vec<Step>,
+    /// The text the query was parsed from
+    source: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    /// Selects from the direct children
+    Child(Selector),
+    /// Selects from the children of the value and all of its descendants
+    Descendants(Selector),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Selector {
+    Segment(PathSegment),
+    Wildcard,
+    Filter(Filter),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Filter {
+    Or(Box<Filter>, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Exists(Operand),
+    Compare(Operand, Comparison, Literal),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+    Value(Path),
+    Name(Path),
+    Variant(Path),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Literal {
+    Integer(i128),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    None,
+}
+
+/// A value that was selected by a [Query]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match<'a> {
+    /// The path to the value from the root of the queried value
+    pub path: Path,
+    pub value: &'a Value,
+}
+
+impl Query {
+    /// Returns all values inside the given value that match the query
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<Match<'a>> {
+        let mut current = vec![Match {
+            path: Path::new(),
+            value,
+        }];
+
+        for step in &self.steps {
+            let mut next = Vec::new();
+
+            for m in current {
+                match step {
+                    Step::Child(selector) => selector.select(&m, &mut next),
+                    Step::Descendants(selector) => {
+                        let mut stack = vec![m];
+                        while let Some(m) = stack.pop() {
+                            selector.select(&m, &mut next);
+                            let children = m.value.children();
+                            stack.extend(children.into_iter().rev().map(|(segment, value)| {
+                                let mut path = m.path.clone();
+                                path.push(segment);
+                                Match { path, value }
+                            }));
+                        }
+                    }
+                }
+            }
+            current = next;
+        }
+
+        current
+    }
+}
+
+impl Value {
+    /// Returns all values inside this value that match the query
+    pub fn query(&self, query: &Query) -> Vec<Match<'_>> {
+        query.select(self)
+    }
+}
+
+impl Selector {
+    fn select<'a>(&self, m: &Match<'a>, out: &mut Vec<Match<'a>>) {
+        let mut push = |segment: PathSegment, value| {
+            let mut path = m.path.clone();
+            path.push(segment);
+            out.push(Match { path, value });
+        };
+
+        match self {
+            Selector::Segment(segment) => {
+                if let Some(value) = m.value.child(segment) {
+                    push(segment.clone(), value);
+                }
+            }
+            Selector::Wildcard => {
+                for (segment, value) in m.value.children() {
+                    push(segment, value);
+                }
+            }
+            Selector::Filter(filter) => {
+                for (segment, value) in m.value.children() {
+                    if filter.matches(value) {
+                        push(segment, value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            Filter::Or(a, b) => a.matches(value) || b.matches(value),
+            Filter::And(a, b) => a.matches(value) && b.matches(value),
+            Filter::Not(f) => !f.matches(value),
+            Filter::Exists(operand) => operand.resolve(value).is_some(),
+            Filter::Compare(operand, comparison, literal) => {
+                let ordering = match operand.resolve(value) {
+                    Some(Resolved::Value(value)) => compare(value, literal),
+                    Some(Resolved::Str(s)) => match literal {
+                        Literal::String(l) => Some(s.cmp(l.as_str())),
+                        _ => None,
+                    },
+                    None => None,
+                };
+                comparison.holds(ordering)
+            }
+        }
+    }
+}
+
+enum Resolved<'a> {
+    Value(&'a Value),
+    Str(&'a str),
+}
+
+impl Operand {
+    fn resolve<'a>(&self, value: &'a Value) -> Option<Resolved<'a>> {
+        match self {
+            Operand::Value(path) => value.get(path).map(Resolved::Value),
+            Operand::Name(path) => match value.get(path)? {
+                Value::Struct(s) => Some(Resolved::Str(&s.name)),
+                Value::Enum(e) => Some(Resolved::Str(&e.name)),
+                _ => None,
+            },
+            Operand::Variant(path) => match value.get(path)? {
+                Value::Enum(e) => Some(Resolved::Str(&e.variant)),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(self, ordering: Option<Ordering>) -> bool {
+        match self {
+            Comparison::Eq => ordering == Some(Ordering::Equal),
+            Comparison::Ne => ordering != Some(Ordering::Equal),
+            Comparison::Lt => ordering == Some(Ordering::Less),
+            Comparison::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Comparison::Gt => ordering == Some(Ordering::Greater),
+            Comparison::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+/// Compares a value with a literal. Values of different kinds can't be compared.
+fn compare(value: &Value, literal: &Literal) -> Option<Ordering> {
+    let Value::Primitive(primitive) = value else {
+        return matches!((value, literal), (Value::None, Literal::None)).then_some(Ordering::Equal);
+    };
+
+    match (primitive, literal) {
+        (Primitive::Integer(i), Literal::Integer(l)) => Some(integer_to_i128(i)?.cmp(l)),
+        (Primitive::Integer(i), Literal::Float(l)) => (integer_to_i128(i)? as f64).partial_cmp(l),
+        (Primitive::Float(f), Literal::Integer(l)) => float_to_f64(f).partial_cmp(&(*l as f64)),
+        (Primitive::Float(f), Literal::Float(l)) => float_to_f64(f).partial_cmp(l),
+        (Primitive::String(s), Literal::String(l)) => Some(s.as_str().cmp(l)),
+        (Primitive::OsString(o), Literal::String(l)) => Some(o.to_string_lossy().as_ref().cmp(l)),
+        (Primitive::Char(c), Literal::String(l)) => Some(c.to_string().cmp(l)),
+        (Primitive::Bool(b), Literal::Bool(l)) => Some(b.cmp(l)),
+        _ => None,
+    }
+}
+
+fn float_to_f64(float: &Float) -> f64 {
+    match float {
+        Float::F32(f) => *f as f64,
+        Float::F64(f) => *f,
+    }
+}
+
+/// An error that occurred while parsing a [Query]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseQueryError {
+    /// The character at the given byte position wasn't expected
+    UnexpectedChar { position: usize, char: char },
+    /// The query ended in the middle of a step or filter
+    UnexpectedEnd,
+    /// The index or number at the given byte position is invalid
+    InvalidNumber { position: usize },
+}
+
+impl Display for ParseQueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseQueryError::UnexpectedChar { position, char } => {
+                write!(f, "unexpected character {char:?} at position {position}")
+            }
+            ParseQueryError::UnexpectedEnd => write!(f, "unexpected end of query"),
+            ParseQueryError::InvalidNumber { position } => {
+                write!(f, "invalid number at position {position}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseQueryError {}
+
+impl From<ParsePathError> for ParseQueryError {
+    fn from(e: ParsePathError) -> Self {
+        match e {
+            ParsePathError::UnexpectedChar { position, char } => {
+                Self::UnexpectedChar { position, char }
+            }
+            ParsePathError::UnexpectedEnd => Self::UnexpectedEnd,
+            ParsePathError::InvalidIndex { position } | ParsePathError::InvalidKey { position } => {
+                Self::InvalidNumber { position }
+            }
+        }
+    }
+}
+
+/// Writes the query as it was parsed
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.source.fmt(f)
+    }
+}
+
+/// Queries are represented as their text
+impl RustyValue for Query {
+    fn into_rusty_value(self) -> Value {
+        Value::Primitive(Primitive::String(self.source))
+    }
+}
+
+impl StreamRustyValue for Query {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.str(&self.source)
+    }
+}
+
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = QueryParser {
+            inner: PathParser::new(s),
+        };
+        let steps = parser.parse_steps()?;
+
+        Ok(Query {
+            steps,
+            source: s.to_owned(),
+        })
+    }
+}
+
+struct QueryParser<'a> {
+    inner: PathParser<'a>,
+}
+
+impl<'a> QueryParser<'a> {
+    fn parse_steps(&mut self) -> Result<Vec<Step>, ParseQueryError> {
+        let mut steps = Vec::new();
+
+        self.eat('$');
+        if self.peek().is_some_and(|c| c != '.' && c != '[') {
+            steps.push(Step::Child(self.parse_name_selector()?));
+        }
+        while let Some((position, c)) = self.inner.chars.next() {
+            match c {
+                '.' if self.eat('.') => {
+                    let selector = if self.eat('[') {
+                        self.parse_bracket_selector()?
+                    } else {
+                        self.parse_name_selector()?
+                    };
+                    steps.push(Step::Descendants(selector));
+                }
+                '.' => steps.push(Step::Child(self.parse_name_selector()?)),
+                '[' => steps.push(Step::Child(self.parse_bracket_selector()?)),
+                char => return Err(ParseQueryError::UnexpectedChar { position, char }),
+            }
+        }
+
+        Ok(steps)
+    }
+
+    fn parse_name_selector(&mut self) -> Result<Selector, ParseQueryError> {
+        if self.eat('*') {
+            return Ok(Selector::Wildcard);
+        }
+        let start = self.inner.position();
+        let name = self.parse_identifier()?;
+
+        if name.chars().all(|c| c.is_ascii_digit()) {
+            name.parse()
+                .map(|i| Selector::Segment(PathSegment::Index(i)))
+                .map_err(|_| ParseQueryError::InvalidNumber { position: start })
+        } else {
+            Ok(Selector::Segment(PathSegment::Field(name.to_owned())))
+        }
+    }
+
+    /// Parses the content of brackets after the opening bracket
+    fn parse_bracket_selector(&mut self) -> Result<Selector, ParseQueryError> {
+        let selector = if self.eat('*') {
+            Selector::Wildcard
+        } else if self.eat('?') {
+            let filter = self.parse_or()?;
+            self.skip_whitespace();
+            Selector::Filter(filter)
+        } else {
+            Selector::Segment(self.inner.parse_bracketed()?)
+        };
+        self.inner.expect(']')?;
+
+        Ok(selector)
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, ParseQueryError> {
+        let mut filter = self.parse_and()?;
+
+        while self.eat_operator("||") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.parse_and()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, ParseQueryError> {
+        let mut filter = self.parse_unary()?;
+
+        while self.eat_operator("&&") {
+            filter = Filter::And(Box::new(filter), Box::new(self.parse_unary()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, ParseQueryError> {
+        self.skip_whitespace();
+
+        if self.eat('!') {
+            Ok(Filter::Not(Box::new(self.parse_unary()?)))
+        } else if self.eat('(') {
+            let filter = self.parse_or()?;
+            self.skip_whitespace();
+            self.inner.expect(')')?;
+            Ok(filter)
+        } else {
+            let operand = self.parse_operand()?;
+            match self.parse_comparison() {
+                Some(comparison) => {
+                    self.skip_whitespace();
+                    Ok(Filter::Compare(operand, comparison, self.parse_literal()?))
+                }
+                None => Ok(Filter::Exists(operand)),
+            }
+        }
+    }
+
+    fn parse_operand(&mut self) -> Result<Operand, ParseQueryError> {
+        if self.peek() == Some('@') {
+            return Ok(Operand::Value(self.parse_relative_path()?));
+        }
+        let start = self.inner.position();
+        let function = self.parse_identifier()?;
+        self.inner.expect('(')?;
+        self.skip_whitespace();
+        let path = self.parse_relative_path()?;
+        self.skip_whitespace();
+        self.inner.expect(')')?;
+
+        match function {
+            "name" => Ok(Operand::Name(path)),
+            "variant" => Ok(Operand::Variant(path)),
+            _ => Err(ParseQueryError::UnexpectedChar {
+                position: start,
+                char: function.chars().next().unwrap_or_default(),
+            }),
+        }
+    }
+
+    /// Parses a path that starts with `@`
+    fn parse_relative_path(&mut self) -> Result<Path, ParseQueryError> {
+        self.inner.expect('@')?;
+        let mut path = Path::new();
+
+        loop {
+            if self.eat('.') {
+                match self.parse_name_selector()? {
+                    Selector::Segment(segment) => path.push(segment),
+                    _ => return Err(self.unexpected()),
+                }
+            } else if self.eat('[') {
+                path.push(self.inner.parse_bracketed()?);
+                self.inner.expect(']')?;
+            } else {
+                return Ok(path);
+            }
+        }
+    }
+
+    fn parse_comparison(&mut self) -> Option<Comparison> {
+        self.skip_whitespace();
+
+        [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ]
+        .into_iter()
+        .find(|(op, _)| self.eat_operator(op))
+        .map(|(_, comparison)| comparison)
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal, ParseQueryError> {
+        match self.peek() {
+            Some('"') => {
+                self.inner.chars.next();
+                Ok(Literal::String(self.inner.parse_quoted('"')?))
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(_) => {
+                let start = self.inner.position();
+                match self.parse_identifier()? {
+                    "true" => Ok(Literal::Bool(true)),
+                    "false" => Ok(Literal::Bool(false)),
+                    "None" => Ok(Literal::None),
+                    other => Err(ParseQueryError::UnexpectedChar {
+                        position: start,
+                        char: other.chars().next().unwrap_or_default(),
+                    }),
+                }
+            }
+            None => Err(ParseQueryError::UnexpectedEnd),
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Literal, ParseQueryError> {
+        let start = self.inner.position();
+        self.eat('-');
+        let mut previous = None;
+        // a sign is only part of the number directly after the exponent marker
+        while let Some(c) = self.peek().filter(|&c| {
+            c.is_ascii_digit()
+                || matches!(c, '.' | 'e' | 'E')
+                || (matches!(c, '+' | '-') && matches!(previous, Some('e' | 'E')))
+        }) {
+            self.inner.chars.next();
+            previous = Some(c);
+        }
+        let number = &self.inner.input[start..self.inner.position()];
+        let literal = if number.contains(['.', 'e', 'E']) {
+            number.parse().map(Literal::Float).ok()
+        } else {
+            number.parse().map(Literal::Integer).ok()
+        };
+
+        literal.ok_or(ParseQueryError::InvalidNumber { position: start })
+    }
+
+    fn parse_identifier(&mut self) -> Result<&'a str, ParseQueryError> {
+        let start = self.inner.position();
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.inner.chars.next();
+        }
+        let end = self.inner.position();
+
+        if start == end {
+            Err(self.unexpected())
+        } else {
+            Ok(&self.inner.input[start..end])
+        }
+    }
+
+    fn eat_operator(&mut self, operator: &str) -> bool {
+        self.skip_whitespace();
+        let position = self.inner.position();
+
+        if self.inner.input[position..].starts_with(operator) {
+            for _ in operator.chars() {
+                self.inner.chars.next();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.inner.chars.next_if(|(_, c)| *c == expected).is_some()
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.inner.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .inner
+            .chars
+            .next_if(|(_, c)| c.is_whitespace())
+            .is_some()
+        {}
+    }
+
+    fn unexpected(&mut self) -> ParseQueryError {
+        match self.inner.chars.peek() {
+            Some((position, char)) => ParseQueryError::UnexpectedChar {
+                position: *position,
+                char: *char,
+            },
+            None => ParseQueryError::UnexpectedEnd,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    fn test_value() -> Value {
+        rusty_value!(struct Status {
+            name: "cluster",
+            jobs: [
+                struct Job { id: 1u32, state: enum State::Running, retries: 0u8 },
+                struct Job { id: 2u32, state: enum State::Failed { code: 3i32 }, retries: 2u8 },
+                struct Job { id: 3u32, state: enum State::Failed { code: 1i32 }, retries: 5u8 },
+            ],
+            limits: { "cpu" => 2.5f64, "memory" => 512u32 },
+        })
+    }
+
+    fn select(query: &str) -> Vec<(String, Value)> {
+        let value = test_value();
+        value
+            .query(&query.parse().unwrap())
+            .into_iter()
+            .map(|m| (m.path.to_string(), m.value.clone()))
+            .collect()
+    }
+
+    fn paths(query: &str) -> Vec<String> {
+        select(query).into_iter().map(|(p, _)| p).collect()
+    }
+
+    #[test]
+    fn it_selects_children() {
+        assert_eq!(
+            select("$.jobs[1].id"),
+            vec![(String::from("jobs[1].id"), rusty_value!(2u32))]
+        );
+        assert_eq!(
+            select(r#"limits["cpu"]"#),
+            vec![(String::from(r#"limits["cpu"]"#), rusty_value!(2.5f64))]
+        );
+        assert_eq!(paths("jobs[5]"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn it_selects_wildcards() {
+        assert_eq!(
+            paths("jobs.*.id"),
+            vec!["jobs[0].id", "jobs[1].id", "jobs[2].id"]
+        );
+        assert_eq!(paths("$[*]"), vec!["jobs", "limits", "name"]);
+    }
+
+    #[test]
+    fn it_selects_descendants() {
+        assert_eq!(
+            paths("..code"),
+            vec!["jobs[1].state.code", "jobs[2].state.code"]
+        );
+        assert_eq!(
+            paths("limits..*"),
+            vec![r#"limits["cpu"]"#, r#"limits["memory"]"#]
+        );
+    }
+
+    #[test]
+    fn it_filters_values() {
+        assert_eq!(
+            paths("jobs[?@.retries > 1].id"),
+            vec!["jobs[1].id", "jobs[2].id"]
+        );
+        assert_eq!(
+            paths("jobs[?@.retries >= 2 && !(@.id == 3)]"),
+            vec!["jobs[1]"]
+        );
+        assert_eq!(paths("limits[?@ < 100]"), vec![r#"limits["cpu"]"#]);
+        assert_eq!(paths(r#"$[?@ == "cluster"]"#), vec!["name"]);
+        assert_eq!(paths("jobs[?@.state.code]"), vec!["jobs[1]", "jobs[2]"]);
+        assert_eq!(
+            paths("jobs[?@.id == 1 || @.state.code == -1.0e0 || @.state.code == 1]"),
+            vec!["jobs[0]", "jobs[2]"]
+        );
+        assert_eq!(
+            paths("limits[?@ > 2.5e-1 && @ < 1.0E+2]"),
+            vec![r#"limits["cpu"]"#]
+        );
+    }
+
+    #[test]
+    fn it_filters_names_and_variants() {
+        assert_eq!(
+            paths(r#"$..[?name(@) == "State" && variant(@) == "Failed"]"#),
+            vec!["jobs[1].state", "jobs[2].state"]
+        );
+        assert_eq!(
+            paths(r#"jobs[?variant(@.state) != "Failed"]"#),
+            vec!["jobs[0]"]
+        );
+        assert_eq!(paths(r#"$[?name(@) == "Job"]"#), Vec::<String>::new());
+    }
+
+    #[test]
+    fn it_rejects_invalid_queries() {
+        assert_eq!(
+            "jobs[?@.id ==]".parse::<Query>(),
+            Err(ParseQueryError::UnexpectedChar {
+                position: 13,
+                char: ']'
+            })
+        );
+        assert_eq!(
+            "jobs[?size(@) > 1]".parse::<Query>(),
+            Err(ParseQueryError::UnexpectedChar {
+                position: 6,
+                char: 's'
+            })
+        );
+        assert_eq!(
+            "jobs[".parse::<Query>(),
+            Err(ParseQueryError::UnexpectedEnd)
+        );
+        assert_eq!(
+            "jobs[?@.id == 1.2.3]".parse::<Query>(),
+            Err(ParseQueryError::InvalidNumber { position: 14 })
+        );
+    }
+}
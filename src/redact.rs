@@ -0,0 +1,484 @@
+use crate::path::is_identifier;
+use crate::*;
+
+/// The default marker that replaces redacted values
+pub const REDACTED: &str = "<redacted>";
+
+/// Selects the values that are replaced with a marker when redacting a [crate::Value]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "derive", derive(RustyValue))]
+pub struct RedactOptions {
+    /// The string that replaces redacted values
+    pub marker: String,
+    /// Named fields and map entries with string keys are redacted if their name matches
+    /// one of the patterns. `*` matches any number of characters and the case is ignored,
+    /// e.g. `*password*` or `token`.
+    pub field_patterns: Vec<String>,
+    /// All values that are selected by one of the queries are redacted
+    pub queries: Vec<Query>,
+}
+
+impl Default for RedactOptions {
+    fn default() -> Self {
+        Self {
+            marker: String::from(REDACTED),
+            field_patterns: Vec::new(),
+            queries: Vec::new(),
+        }
+    }
+}
+
+impl RedactOptions {
+    /// Returns options that redact the fields of the type that are marked
+    /// with `#[rusty_value(sensitive)]` in its [RustyType] schema.
+    ///
+    /// The attribute only marks the field in the schema, so values are only redacted
+    /// if the options are created with this function. A type with sensitive fields
+    /// must derive [RustyType] as well, otherwise the `RustyValue` derive fails to compile.
+    pub fn for_type<T: RustyType>() -> Self {
+        Self {
+            queries: T::rusty_type().sensitive_queries(),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn matches_field(&self, name: &str) -> bool {
+        self.field_patterns
+            .iter()
+            .any(|pattern| matches_pattern(pattern, name))
+    }
+}
+
+/// Used by the `RustyValue` derive to check that types with sensitive fields implement [RustyType]
+#[doc(hidden)]
+pub fn assert_sensitive_fields<T: SensitiveFields + ?Sized>() {}
+
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` has fields marked with `#[rusty_value(sensitive)]` but doesn't implement `RustyType`",
+    label = "sensitive fields are only redacted through the `RustyType` schema",
+    note = "add `#[derive(RustyType)]` and create the redact options with `RedactOptions::for_type`"
+)]
+pub trait SensitiveFields {}
+
+impl<T: RustyType + ?Sized> SensitiveFields for T {}
+
+impl Value {
+    /// Replaces all values that are selected by the options with the marker string.
+    /// [Value::None] is kept since it doesn't contain anything to hide.
+    pub fn redact(&mut self, options: &RedactOptions) {
+        let paths = options
+            .queries
+            .iter()
+            .flat_map(|query| query.select(self))
+            .map(|m| m.path)
+            .collect::<Vec<_>>();
+
+        for path in paths {
+            // values inside of a value that was already redacted don't exist anymore
+            if let Some(value) = self.get_mut(&path) {
+                redact_value(value, options);
+            }
+        }
+        if !options.field_patterns.is_empty() {
+            redact_fields(self, options);
+        }
+    }
+
+    /// Returns a copy of the value with all values that are selected by the options
+    /// replaced with the marker string
+    pub fn redacted(&self, options: &RedactOptions) -> Value {
+        let mut value = self.clone();
+        value.redact(options);
+
+        value
+    }
+}
+
+impl TypeSchema {
+    /// Returns queries that select the values of all fields marked with `#[rusty_value(sensitive)]`.
+    ///
+    /// Fields of enum variants are selected by name or index regardless of the variant
+    /// so a field of another variant with the same name is redacted as well.
+    /// Inside of recursive types the fields are selected at any depth, e.g. `$.children[*]..password`,
+    /// which also selects fields with the same name of other types nested in them.
+    pub fn sensitive_queries(&self) -> Vec<Query> {
+        let mut queries = Vec::new();
+        collect_sensitive(
+            self,
+            &mut String::from("$"),
+            &mut queries,
+            &mut Some(Vec::new()),
+        );
+        queries.sort();
+        queries.dedup();
+
+        queries
+            .iter()
+            .map(|q| q.parse().expect("queries of sensitive fields are valid"))
+            .collect()
+    }
+}
+
+/// Collects the queries of the sensitive values in the schema.
+/// `types` contains the enclosing structs and enums to resolve [TypeSchema::Ref]s.
+/// It's `None` if references are skipped because the queries already select at any depth.
+fn collect_sensitive<'s>(
+    schema: &'s TypeSchema,
+    query: &mut String,
+    queries: &mut Vec<String>,
+    types: &mut Option<Vec<&'s TypeSchema>>,
+) {
+    match schema {
+        TypeSchema::Sensitive(_) => queries.push(query.clone()),
+        TypeSchema::Struct(_) | TypeSchema::Enum(_) => {
+            if let Some(types) = types {
+                types.push(schema);
+            }
+            match schema {
+                TypeSchema::Struct(s) => collect_sensitive_fields(&s.fields, query, queries, types),
+                TypeSchema::Enum(e) => {
+                    for variant in &e.variants {
+                        collect_sensitive_fields(&variant.fields, query, queries, types);
+                    }
+                }
+                _ => unreachable!(),
+            }
+            if let Some(types) = types {
+                types.pop();
+            }
+        }
+        TypeSchema::Ref { type_name, .. } => {
+            let Some(target) = types
+                .iter()
+                .flatten()
+                .rev()
+                .find(|t| t.type_name() == Some(type_name))
+            else {
+                return;
+            };
+            let mut relative = Vec::new();
+            collect_sensitive(target, &mut String::new(), &mut relative, &mut None);
+
+            for suffix in relative {
+                let suffix = suffix.strip_prefix('.').unwrap_or(&suffix);
+                queries.push(format!("{query}..{suffix}"));
+            }
+        }
+        TypeSchema::Option(inner) => collect_sensitive(inner, query, queries, types),
+        TypeSchema::List(inner) | TypeSchema::Map { value: inner, .. } => {
+            let len = query.len();
+            query.push_str("[*]");
+            collect_sensitive(inner, query, queries, types);
+            query.truncate(len);
+        }
+        TypeSchema::Primitive(_) | TypeSchema::Unit | TypeSchema::Any => {}
+    }
+}
+
+fn collect_sensitive_fields<'s>(
+    fields: &'s FieldsSchema,
+    query: &mut String,
+    queries: &mut Vec<String>,
+    types: &mut Option<Vec<&'s TypeSchema>>,
+) {
+    let len = query.len();
+
+    match fields {
+        FieldsSchema::Named(fields) => {
+            for field in fields {
+                if is_identifier(field.name) {
+                    query.push('.');
+                    query.push_str(field.name);
+                } else {
+                    query.push_str(&format!("[\"{}\"]", field.name.escape_debug()));
+                }
+                collect_sensitive(&field.schema, query, queries, types);
+                query.truncate(len);
+            }
+        }
+        FieldsSchema::Unnamed(fields) => {
+            for (index, schema) in fields.iter().enumerate() {
+                query.push_str(&format!("[{index}]"));
+                collect_sensitive(schema, query, queries, types);
+                query.truncate(len);
+            }
+        }
+        FieldsSchema::Unit => {}
+    }
+}
+
+fn redact_fields(value: &mut Value, options: &RedactOptions) {
+    match value {
+        Value::Struct(Struct { fields, .. }) | Value::Enum(Enum { fields, .. }) => match fields {
+            Fields::Named(named) => {
+                for (name, value) in named.iter_mut() {
+                    if options.matches_field(name) {
+                        redact_value(value, options);
+                    } else {
+                        redact_fields(value, options);
+                    }
+                }
+            }
+            Fields::Unnamed(unnamed) => {
+                for value in unnamed {
+                    redact_fields(value, options);
+                }
+            }
+            Fields::Unit => {}
+        },
+        Value::Map(map) => {
+            for (key, value) in map.iter_mut() {
+                match key {
+                    HashableValue::Primitive(HashablePrimitive::String(name))
+                        if options.matches_field(name) =>
+                    {
+                        redact_value(value, options)
+                    }
+                    _ => redact_fields(value, options),
+                }
+            }
+        }
+        Value::List(list) => {
+            for value in list {
+                redact_fields(value, options);
+            }
+        }
+        Value::Primitive(_) | Value::None => {}
+    }
+}
+
+/// Replaces the value with the marker unless it's none
+fn redact_value(value: &mut Value, options: &RedactOptions) {
+    if !matches!(value, Value::None) {
+        *value = Value::Primitive(Primitive::String(options.marker.clone()));
+    }
+}
+
+/// Returns whether the name matches the pattern where `*` matches any number of characters.
+/// The case is ignored.
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let name = name.to_lowercase();
+    let mut parts = pattern.split('*');
+
+    let Some(mut rest) = name.strip_prefix(parts.next().unwrap_or_default()) else {
+        return false;
+    };
+    let parts = parts.collect::<Vec<_>>();
+    let Some((last, middle)) = parts.split_last() else {
+        // the pattern doesn't contain a wildcard
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::pretty::{self, PrettyOptions};
+    use crate::redact::matches_pattern;
+    use crate::*;
+
+    #[derive(RustyType, RustyValue)]
+    struct Config {
+        user: String,
+        #[rusty_value(sensitive)]
+        password: String,
+        servers: Vec<Server>,
+        auth: Auth,
+    }
+
+    #[derive(RustyType, RustyValue)]
+    #[rusty_value(stream)]
+    struct Server {
+        host: String,
+        #[rusty_value(sensitive)]
+        api_token: Option<String>,
+    }
+
+    #[derive(RustyType, RustyValue)]
+    enum Auth {
+        None,
+        Key(#[rusty_value(sensitive)] Vec<u8>),
+    }
+
+    #[derive(RustyType, RustyValue)]
+    struct Account {
+        #[rusty_value(sensitive)]
+        secret: String,
+        children: Vec<Account>,
+    }
+
+    fn config() -> Config {
+        Config {
+            user: String::from("admin"),
+            password: String::from("hunter2"),
+            servers: vec![Server {
+                host: String::from("localhost"),
+                api_token: Some(String::from("abc")),
+            }],
+            auth: Auth::Key(vec![1, 2]),
+        }
+    }
+
+    #[test]
+    fn it_redacts_sensitive_fields() {
+        let options = RedactOptions::for_type::<Config>();
+        assert_eq!(
+            options
+                .queries
+                .iter()
+                .map(Query::to_string)
+                .collect::<Vec<_>>(),
+            vec!["$.auth[0]", "$.password", "$.servers[*].api_token"]
+        );
+
+        assert_eq!(
+            config().into_rusty_value().redacted(&options),
+            rusty_value!(struct Config {
+                user: "admin",
+                password: "<redacted>",
+                servers: [struct Server {
+                    host: "localhost",
+                    api_token: "<redacted>",
+                }],
+                auth: enum Auth::Key("<redacted>"),
+            })
+        );
+    }
+
+    #[test]
+    fn it_redacts_recursive_types() {
+        let options = RedactOptions::for_type::<Account>();
+        assert_eq!(
+            options
+                .queries
+                .iter()
+                .map(Query::to_string)
+                .collect::<Vec<_>>(),
+            vec!["$.children[*]..secret", "$.secret"]
+        );
+
+        let account = Account {
+            secret: String::from("a"),
+            children: vec![Account {
+                secret: String::from("b"),
+                children: vec![Account {
+                    secret: String::from("c"),
+                    children: Vec::new(),
+                }],
+            }],
+        };
+        assert_eq!(
+            account.into_rusty_value().redacted(&options),
+            rusty_value!(struct Account {
+                secret: "<redacted>",
+                children: [struct Account {
+                    secret: "<redacted>",
+                    children: [struct Account {
+                        secret: "<redacted>",
+                        children: [],
+                    }],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn it_redacts_fields_by_name_and_query() {
+        let options = RedactOptions {
+            marker: String::from("***"),
+            field_patterns: vec![String::from("*TOKEN*"), String::from("authorization")],
+            queries: vec!["$.user".parse().unwrap()],
+        };
+        let mut value = rusty_value!({
+            "user" => "admin",
+            "headers" => {
+                "Authorization" => "Bearer abc",
+                "Accept" => "*/*",
+            },
+            "session" => struct Session { refresh_token: 1u8, tokens: [2u8] },
+        });
+        value.redact(&options);
+
+        assert_eq!(
+            value,
+            rusty_value!({
+                "user" => "***",
+                "headers" => {
+                    "Authorization" => "***",
+                    "Accept" => "*/*",
+                },
+                "session" => struct Session { refresh_token: "***", tokens: "***" },
+            })
+        );
+    }
+
+    #[test]
+    fn it_matches_field_patterns() {
+        assert!(matches_pattern("password", "Password"));
+        assert!(!matches_pattern("password", "passwords"));
+        assert!(matches_pattern("*_key", "api_key"));
+        assert!(matches_pattern("a*b*c", "abc"));
+        assert!(!matches_pattern("a*b*c", "acb"));
+        assert!(matches_pattern("*", ""));
+    }
+
+    #[test]
+    fn it_redacts_pretty_printed_values() {
+        let options = PrettyOptions {
+            type_names: false,
+            redact: Some(RedactOptions::for_type::<Config>()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            pretty::to_string(&config().into_rusty_value(), &options),
+            r#"{ auth: Key("<redacted>"), password: "<redacted>", servers: [{ api_token: "<redacted>", host: "localhost" }], user: "admin" }"#
+        );
+    }
+
+    #[cfg(feature = "json-writer")]
+    #[test]
+    fn it_redacts_json() {
+        use crate::json::{self, IntoJsonOptions};
+
+        let options = IntoJsonOptions {
+            redact: Some(RedactOptions {
+                field_patterns: vec![String::from("password")],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let server = Server {
+            host: String::from("localhost"),
+            api_token: None,
+        };
+
+        assert!(json::to_string(&config().into_rusty_value(), &options)
+            .unwrap()
+            .contains(r#""password":"<redacted>""#));
+        assert_eq!(
+            json::stream_to_string(
+                &server,
+                &IntoJsonOptions {
+                    redact: Some(RedactOptions::for_type::<Server>()),
+                    ..Default::default()
+                }
+            )
+            .unwrap(),
+            r#"{"api_token":null,"host":"localhost"}"#
+        );
+    }
+}
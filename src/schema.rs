@@ -0,0 +1,427 @@
+use std::{cell::RefCell, collections::HashMap, ffi::OsString, fmt::Display, path::PathBuf};
+
+use crate::{HashableValue, Value};
+
+/// Describes the shape of a type without needing an instance of it
+pub trait RustyType {
+    /// Returns the schema of the type.
+    /// A type that contains itself is described by a [TypeSchema::Ref] where it recurses,
+    /// see [TypeSchema::named].
+    fn rusty_type() -> TypeSchema;
+}
+
+/// The shape of a type as it's represented by a [crate::Value]
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeSchema {
+    Primitive(PrimitiveKind),
+    Struct(StructSchema),
+    Enum(EnumSchema),
+    /// A value that is either [crate::Value::None] or matches the inner schema
+    Option(Box<TypeSchema>),
+    List(Box<TypeSchema>),
+    Map {
+        key: Box<TypeSchema>,
+        value: Box<TypeSchema>,
+    },
+    /// The unit type which is represented as [crate::Value::None]
+    Unit,
+    /// Any value, e.g. a [crate::Value] itself
+    Any,
+    /// A field marked with `#[rusty_value(sensitive)]` whose value matches the
+    /// inner schema but should be redacted before it's written anywhere
+    Sensitive(Box<TypeSchema>),
+    /// A struct or enum that contains itself.
+    /// It refers to the closest enclosing struct or enum schema with the same `type_name`,
+    /// so different instances of a generic type are told apart.
+    Ref {
+        name: &'static str,
+        type_name: &'static str,
+    },
+}
+
+/// The kind of a primitive value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PrimitiveKind {
+    USize,
+    ISize,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    U128,
+    I128,
+    F32,
+    F64,
+    String,
+    OsString,
+    Char,
+    Bool,
+}
+
+/// The schema of a struct
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructSchema {
+    pub name: &'static str,
+    /// The full name of the type with its generic arguments, see [std::any::type_name]
+    pub type_name: &'static str,
+    pub fields: FieldsSchema,
+}
+
+/// The schema of an enum with all of its variants
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumSchema {
+    pub name: &'static str,
+    /// The full name of the type with its generic arguments, see [std::any::type_name]
+    pub type_name: &'static str,
+    pub variants: Vec<VariantSchema>,
+}
+
+/// The schema of a single enum variant
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariantSchema {
+    pub name: &'static str,
+    pub fields: FieldsSchema,
+}
+
+/// The fields of a struct or enum variant in the order they are declared
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldsSchema {
+    Named(Vec<FieldSchema>),
+    Unnamed(Vec<TypeSchema>),
+    Unit,
+}
+
+/// The schema of a named field
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldSchema {
+    pub name: &'static str,
+    pub schema: TypeSchema,
+}
+
+thread_local! {
+    /// The types whose schemas are currently being built by [TypeSchema::named]
+    static BUILDING: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+impl TypeSchema {
+    /// Builds the schema of the struct or enum `T` with the given name.
+    /// Returns a [TypeSchema::Ref] instead if the schema of `T` is already being built,
+    /// i.e. if `T` contains itself. The derived [RustyType] impls use this.
+    pub fn named<T: ?Sized>(name: &'static str, build: impl FnOnce() -> TypeSchema) -> TypeSchema {
+        let type_name = std::any::type_name::<T>();
+
+        if BUILDING.with(|building| building.borrow().contains(&type_name)) {
+            return TypeSchema::Ref { name, type_name };
+        }
+        BUILDING.with(|building| building.borrow_mut().push(type_name));
+        // the type is removed again even if building the schema panics
+        let _guard = BuildingGuard;
+
+        build()
+    }
+
+    /// Returns the full type name of the struct or enum the schema describes
+    pub(crate) fn type_name(&self) -> Option<&'static str> {
+        match self {
+            TypeSchema::Struct(s) => Some(s.type_name),
+            TypeSchema::Enum(e) => Some(e.type_name),
+            _ => None,
+        }
+    }
+}
+
+struct BuildingGuard;
+
+impl Drop for BuildingGuard {
+    fn drop(&mut self) {
+        BUILDING.with(|building| building.borrow_mut().pop());
+    }
+}
+
+impl EnumSchema {
+    /// Returns the variant with the given name
+    pub fn variant(&self, name: &str) -> Option<&VariantSchema> {
+        self.variants.iter().find(|v| v.name == name)
+    }
+}
+
+impl FieldsSchema {
+    /// Returns the named field with the given name
+    pub fn field(&self, name: &str) -> Option<&FieldSchema> {
+        match self {
+            FieldsSchema::Named(fields) => fields.iter().find(|f| f.name == name),
+            _ => None,
+        }
+    }
+}
+
+/// Writes the schema like the rust type it describes, e.g. `Option<Vec<u8>>`
+impl Display for TypeSchema {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TypeSchema::Primitive(p) => p.fmt(f),
+            TypeSchema::Struct(s) => s.name.fmt(f),
+            TypeSchema::Enum(e) => e.name.fmt(f),
+            TypeSchema::Option(inner) => write!(f, "Option<{inner}>"),
+            TypeSchema::List(inner) => write!(f, "Vec<{inner}>"),
+            TypeSchema::Map { key, value } => write!(f, "HashMap<{key}, {value}>"),
+            TypeSchema::Unit => write!(f, "()"),
+            TypeSchema::Any => write!(f, "Value"),
+            TypeSchema::Sensitive(inner) => inner.fmt(f),
+            TypeSchema::Ref { name, .. } => name.fmt(f),
+        }
+    }
+}
+
+impl Display for PrimitiveKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            PrimitiveKind::USize => "usize",
+            PrimitiveKind::ISize => "isize",
+            PrimitiveKind::U8 => "u8",
+            PrimitiveKind::I8 => "i8",
+            PrimitiveKind::U16 => "u16",
+            PrimitiveKind::I16 => "i16",
+            PrimitiveKind::U32 => "u32",
+            PrimitiveKind::I32 => "i32",
+            PrimitiveKind::U64 => "u64",
+            PrimitiveKind::I64 => "i64",
+            PrimitiveKind::U128 => "u128",
+            PrimitiveKind::I128 => "i128",
+            PrimitiveKind::F32 => "f32",
+            PrimitiveKind::F64 => "f64",
+            PrimitiveKind::String => "String",
+            PrimitiveKind::OsString => "OsString",
+            PrimitiveKind::Char => "char",
+            PrimitiveKind::Bool => "bool",
+        };
+        name.fmt(f)
+    }
+}
+
+macro_rules! primitive_type {
+    ($($ty:ty => $kind:ident),*) => {
+        $(
+            impl RustyType for $ty {
+                #[inline]
+                fn rusty_type() -> TypeSchema {
+                    TypeSchema::Primitive(PrimitiveKind::$kind)
+                }
+            }
+        )*
+    };
+}
+
+primitive_type!(
+    usize => USize, isize => ISize,
+    u8 => U8, i8 => I8,
+    u16 => U16, i16 => I16,
+    u32 => U32, i32 => I32,
+    u64 => U64, i64 => I64,
+    u128 => U128, i128 => I128,
+    f32 => F32, f64 => F64,
+    String => String, &str => String,
+    OsString => OsString,
+    char => Char,
+    bool => Bool
+);
+
+impl RustyType for () {
+    #[inline]
+    fn rusty_type() -> TypeSchema {
+        TypeSchema::Unit
+    }
+}
+
+impl RustyType for PathBuf {
+    fn rusty_type() -> TypeSchema {
+        TypeSchema::Struct(StructSchema {
+            name: "PathBuf",
+            type_name: std::any::type_name::<PathBuf>(),
+            fields: FieldsSchema::Unnamed(vec![OsString::rusty_type()]),
+        })
+    }
+}
+
+impl<T: RustyType> RustyType for Option<T> {
+    #[inline]
+    fn rusty_type() -> TypeSchema {
+        TypeSchema::Option(Box::new(T::rusty_type()))
+    }
+}
+
+impl<T: RustyType> RustyType for Vec<T> {
+    #[inline]
+    fn rusty_type() -> TypeSchema {
+        TypeSchema::List(Box::new(T::rusty_type()))
+    }
+}
+
+impl<K: RustyType, V: RustyType> RustyType for HashMap<K, V> {
+    #[inline]
+    fn rusty_type() -> TypeSchema {
+        TypeSchema::Map {
+            key: Box::new(K::rusty_type()),
+            value: Box::new(V::rusty_type()),
+        }
+    }
+}
+
+impl RustyType for Value {
+    #[inline]
+    fn rusty_type() -> TypeSchema {
+        TypeSchema::Any
+    }
+}
+
+impl RustyType for HashableValue {
+    #[inline]
+    fn rusty_type() -> TypeSchema {
+        TypeSchema::Any
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::*;
+
+    #[derive(RustyType)]
+    struct Server {
+        name: String,
+        port: Option<u16>,
+        tags: Vec<String>,
+        mode: Mode,
+    }
+
+    #[derive(RustyType)]
+    enum Mode {
+        Fast,
+        Slow { delay: u32 },
+        Custom(HashMap<String, f64>, Unit),
+    }
+
+    #[derive(RustyType)]
+    struct Unit;
+
+    #[derive(RustyType)]
+    struct Wrapper<T>(T);
+
+    #[test]
+    fn it_describes_structs() {
+        let schema = Server::rusty_type();
+        let TypeSchema::Struct(s) = &schema else {
+            panic!("the schema isn't a struct");
+        };
+
+        assert_eq!(s.name, "Server");
+        assert_eq!(
+            s.fields
+                .field("port")
+                .map(|f| f.schema.to_string())
+                .as_deref(),
+            Some("Option<u16>")
+        );
+        assert_eq!(
+            s.fields.field("tags").map(|f| &f.schema),
+            Some(&TypeSchema::List(Box::new(TypeSchema::Primitive(
+                PrimitiveKind::String
+            ))))
+        );
+        assert_eq!(
+            Wrapper::<u8>::rusty_type(),
+            TypeSchema::Struct(StructSchema {
+                name: "Wrapper",
+                type_name: std::any::type_name::<Wrapper<u8>>(),
+                fields: FieldsSchema::Unnamed(vec![TypeSchema::Primitive(PrimitiveKind::U8)]),
+            })
+        );
+    }
+
+    #[derive(RustyType)]
+    struct Node {
+        name: String,
+        children: Vec<Node>,
+        leaf: Leaf<Vec<Node>>,
+    }
+
+    #[derive(RustyType)]
+    enum Leaf<T> {
+        Empty,
+        Value(T),
+    }
+
+    #[test]
+    fn it_describes_recursive_types() {
+        let TypeSchema::Struct(s) = Node::rusty_type() else {
+            panic!("the schema isn't a struct");
+        };
+        let node_ref = TypeSchema::Ref {
+            name: "Node",
+            type_name: std::any::type_name::<Node>(),
+        };
+
+        assert_eq!(
+            s.fields.field("children").unwrap().schema,
+            TypeSchema::List(Box::new(node_ref.clone()))
+        );
+        let TypeSchema::Enum(leaf) = &s.fields.field("leaf").unwrap().schema else {
+            panic!("the schema isn't an enum");
+        };
+        assert_eq!(
+            leaf.variant("Value").unwrap().fields,
+            FieldsSchema::Unnamed(vec![TypeSchema::List(Box::new(node_ref))])
+        );
+        assert_eq!(
+            s.fields.field("children").unwrap().schema.to_string(),
+            "Vec<Node>"
+        );
+        // different instances of a generic type don't refer to each other
+        let TypeSchema::Struct(wrapper) = Wrapper::<Wrapper<u8>>::rusty_type() else {
+            panic!("the schema isn't a struct");
+        };
+        assert!(matches!(
+            &wrapper.fields,
+            FieldsSchema::Unnamed(fields) if matches!(fields[0], TypeSchema::Struct(_))
+        ));
+    }
+
+    #[test]
+    fn it_describes_enums() {
+        let TypeSchema::Enum(e) = Mode::rusty_type() else {
+            panic!("the schema isn't an enum");
+        };
+
+        assert_eq!(
+            e.variants.iter().map(|v| v.name).collect::<Vec<_>>(),
+            vec!["Fast", "Slow", "Custom"]
+        );
+        assert_eq!(e.variant("Fast").unwrap().fields, FieldsSchema::Unit);
+        assert_eq!(
+            e.variant("Custom").unwrap().fields,
+            FieldsSchema::Unnamed(vec![
+                TypeSchema::Map {
+                    key: Box::new(TypeSchema::Primitive(PrimitiveKind::String)),
+                    value: Box::new(TypeSchema::Primitive(PrimitiveKind::F64)),
+                },
+                Unit::rusty_type(),
+            ])
+        );
+        assert_eq!(
+            e.variant("Slow")
+                .unwrap()
+                .fields
+                .field("delay")
+                .unwrap()
+                .schema,
+            TypeSchema::Primitive(PrimitiveKind::U32)
+        );
+    }
+}
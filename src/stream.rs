@@ -0,0 +1,808 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    fmt::Display,
+    path::PathBuf,
+};
+
+use crate::{
+    Enum, Fields, Float, HashablePrimitive, HashableValue, Integer, Primitive, PrimitiveRef,
+    Struct, Value,
+};
+
+/// The kind of fields a struct or enum variant has
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldsKind {
+    Named,
+    Unnamed,
+    Unit,
+}
+
+/// Receives a value piece by piece without it being built as a [crate::Value] first.
+///
+/// Named fields are announced with [Sink::field] followed by their value.
+/// Unnamed fields and list items are passed as values directly.
+/// Map entries are passed as the key followed by the value.
+///
+/// Type, variant and field names are passed like they're stored in a [crate::Value],
+/// so derived types pass their `&'static str` names that sinks can keep without copying.
+pub trait Sink<'a> {
+    type Error;
+
+    fn integer(&mut self, value: Integer) -> Result<(), Self::Error>;
+
+    fn float(&mut self, value: Float) -> Result<(), Self::Error>;
+
+    fn str(&mut self, value: &'a str) -> Result<(), Self::Error>;
+
+    fn os_str(&mut self, value: &'a OsStr) -> Result<(), Self::Error>;
+
+    fn char(&mut self, value: char) -> Result<(), Self::Error>;
+
+    fn bool(&mut self, value: bool) -> Result<(), Self::Error>;
+
+    fn none(&mut self) -> Result<(), Self::Error>;
+
+    fn begin_struct(
+        &mut self,
+        name: Cow<'static, str>,
+        kind: FieldsKind,
+        len: usize,
+    ) -> Result<(), Self::Error>;
+
+    /// Announces the named field whose value is passed next
+    fn field(&mut self, name: Cow<'static, str>) -> Result<(), Self::Error>;
+
+    fn end_struct(&mut self) -> Result<(), Self::Error>;
+
+    fn begin_enum(
+        &mut self,
+        name: Cow<'static, str>,
+        variant: Cow<'static, str>,
+        kind: FieldsKind,
+        len: usize,
+    ) -> Result<(), Self::Error>;
+
+    fn end_enum(&mut self) -> Result<(), Self::Error>;
+
+    fn begin_list(&mut self, len: usize) -> Result<(), Self::Error>;
+
+    fn end_list(&mut self) -> Result<(), Self::Error>;
+
+    fn begin_map(&mut self, len: usize) -> Result<(), Self::Error>;
+
+    fn end_map(&mut self) -> Result<(), Self::Error>;
+}
+
+/// The streaming counterpart of [crate::RustyValue].
+/// Passes the value to a [Sink] instead of converting it into a [crate::Value].
+///
+/// `#[derive(RustyValue)]` implements it for types marked with `#[rusty_value(stream)]`,
+/// which requires all fields to implement it.
+pub trait StreamRustyValue {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error>;
+}
+
+macro_rules! stream_integer {
+    ($($ty:ty => $variant:ident),*) => {
+        $(
+            impl StreamRustyValue for $ty {
+                #[inline]
+                fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+                    sink.integer(Integer::$variant(*self))
+                }
+            }
+        )*
+    };
+}
+
+stream_integer!(
+    usize => USize, isize => ISize,
+    u8 => U8, i8 => I8,
+    u16 => U16, i16 => I16,
+    u32 => U32, i32 => I32,
+    u64 => U64, i64 => I64,
+    u128 => U128, i128 => I128
+);
+
+impl StreamRustyValue for f32 {
+    #[inline]
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.float(Float::F32(*self))
+    }
+}
+
+impl StreamRustyValue for f64 {
+    #[inline]
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.float(Float::F64(*self))
+    }
+}
+
+impl StreamRustyValue for String {
+    #[inline]
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.str(self)
+    }
+}
+
+impl StreamRustyValue for &str {
+    #[inline]
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.str(self)
+    }
+}
+
+impl StreamRustyValue for bool {
+    #[inline]
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.bool(*self)
+    }
+}
+
+impl StreamRustyValue for () {
+    #[inline]
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.none()
+    }
+}
+
+impl StreamRustyValue for OsString {
+    #[inline]
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.os_str(self)
+    }
+}
+
+impl StreamRustyValue for PathBuf {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.begin_struct(Cow::Borrowed("PathBuf"), FieldsKind::Unnamed, 1)?;
+        sink.os_str(self.as_os_str())?;
+        sink.end_struct()
+    }
+}
+
+impl<T: StreamRustyValue> StreamRustyValue for Option<T> {
+    #[inline]
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        match self {
+            Some(val) => val.stream_rusty_value(sink),
+            None => sink.none(),
+        }
+    }
+}
+
+impl<T: StreamRustyValue> StreamRustyValue for Vec<T> {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.begin_list(self.len())?;
+        for item in self {
+            item.stream_rusty_value(sink)?;
+        }
+        sink.end_list()
+    }
+}
+
+impl<K: StreamRustyValue, V: StreamRustyValue> StreamRustyValue for HashMap<K, V> {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        sink.begin_map(self.len())?;
+        for (key, value) in self {
+            key.stream_rusty_value(sink)?;
+            value.stream_rusty_value(sink)?;
+        }
+        sink.end_map()
+    }
+}
+
+impl StreamRustyValue for HashableValue {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        match self {
+            HashableValue::Primitive(p) => match p {
+                HashablePrimitive::Integer(i) => sink.integer(i.clone()),
+                HashablePrimitive::String(s) => sink.str(s),
+                HashablePrimitive::OsString(o) => sink.os_str(o),
+                HashablePrimitive::Char(c) => sink.char(*c),
+                HashablePrimitive::Bool(b) => sink.bool(*b),
+            },
+            HashableValue::List(l) => l.stream_rusty_value(sink),
+            HashableValue::None => sink.none(),
+        }
+    }
+}
+
+impl StreamRustyValue for Value {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        match self {
+            Value::Primitive(p) => match p {
+                Primitive::Integer(i) => sink.integer(i.clone()),
+                Primitive::Float(f) => sink.float(f.clone()),
+                Primitive::String(s) => sink.str(s),
+                Primitive::OsString(o) => sink.os_str(o),
+                Primitive::Char(c) => sink.char(*c),
+                Primitive::Bool(b) => sink.bool(*b),
+            },
+            Value::Struct(s) => {
+                sink.begin_struct(
+                    s.name.clone(),
+                    fields_kind(&s.fields),
+                    fields_len(&s.fields),
+                )?;
+                stream_fields(&s.fields, sink)?;
+                sink.end_struct()
+            }
+            Value::Enum(e) => {
+                sink.begin_enum(
+                    e.name.clone(),
+                    e.variant.clone(),
+                    fields_kind(&e.fields),
+                    fields_len(&e.fields),
+                )?;
+                stream_fields(&e.fields, sink)?;
+                sink.end_enum()
+            }
+            Value::Map(m) => {
+                let mut entries = m.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(k, _)| *k);
+
+                sink.begin_map(entries.len())?;
+                for (key, value) in entries {
+                    key.stream_rusty_value(sink)?;
+                    value.stream_rusty_value(sink)?;
+                }
+                sink.end_map()
+            }
+            Value::List(l) => l.stream_rusty_value(sink),
+            Value::None => sink.none(),
+        }
+    }
+}
+
+fn fields_kind(fields: &Fields) -> FieldsKind {
+    match fields {
+        Fields::Named(_) => FieldsKind::Named,
+        Fields::Unnamed(_) => FieldsKind::Unnamed,
+        Fields::Unit => FieldsKind::Unit,
+    }
+}
+
+fn fields_len(fields: &Fields) -> usize {
+    match fields {
+        Fields::Named(n) => n.len(),
+        Fields::Unnamed(u) => u.len(),
+        Fields::Unit => 0,
+    }
+}
+
+fn stream_fields<'a, S: Sink<'a>>(fields: &'a Fields, sink: &mut S) -> Result<(), S::Error> {
+    match fields {
+        Fields::Named(named) => {
+            let mut named = named.iter().collect::<Vec<_>>();
+            named.sort_by_key(|(k, _)| *k);
+
+            for (name, value) in named {
+                sink.field(name.clone())?;
+                value.stream_rusty_value(sink)?;
+            }
+        }
+        Fields::Unnamed(unnamed) => {
+            for value in unnamed {
+                value.stream_rusty_value(sink)?;
+            }
+        }
+        Fields::Unit => {}
+    }
+
+    Ok(())
+}
+
+impl Value {
+    /// Builds the value by streaming the given type into a [ValueSink].
+    /// This results in the same value as [crate::RustyValue::into_rusty_value]
+    /// without consuming the original.
+    pub fn from_stream<T: StreamRustyValue + ?Sized>(value: &T) -> Result<Value, StreamError> {
+        let mut sink = ValueSink::new();
+        value.stream_rusty_value(&mut sink)?;
+
+        sink.finish()
+    }
+}
+
+/// A [Sink] that builds a [crate::Value] from the streamed pieces
+pub type ValueSink<'a> = TreeSink<'a, Value>;
+
+/// A [Sink] that builds a tree of values from the streamed pieces.
+/// It's used as a [ValueSink] or a [crate::ValueRefSink].
+#[derive(Debug)]
+pub struct TreeSink<'a, T: StreamNode<'a>> {
+    stack: Vec<Frame<'a, T>>,
+    value: Option<T>,
+}
+
+pub(crate) use node::{StreamNode, StreamedFields};
+
+mod node {
+    use std::{borrow::Cow, fmt::Debug};
+
+    use crate::{PrimitiveRef, StreamError};
+
+    /// A value that a [super::TreeSink] builds from the streamed pieces
+    pub trait StreamNode<'a>: Sized + Debug {
+        /// The type of map keys
+        type Key: Debug;
+
+        fn primitive(primitive: PrimitiveRef<'a>) -> Self;
+
+        fn none() -> Self;
+
+        fn structure(name: Cow<'static, str>, fields: StreamedFields<Self>) -> Self;
+
+        fn enumeration(
+            name: Cow<'static, str>,
+            variant: Cow<'static, str>,
+            fields: StreamedFields<Self>,
+        ) -> Self;
+
+        fn list(items: Vec<Self>) -> Self;
+
+        fn map(entries: Vec<(Self::Key, Self)>) -> Self;
+
+        /// Converts the value into a map key
+        fn into_key(self) -> Result<Self::Key, StreamError>;
+    }
+
+    /// The fields of a struct or enum variant in the order they were streamed
+    #[derive(Debug)]
+    pub enum StreamedFields<T> {
+        Named(Vec<(Cow<'static, str>, T)>),
+        Unnamed(Vec<T>),
+        Unit,
+    }
+}
+
+#[derive(Debug)]
+enum Frame<'a, T: StreamNode<'a>> {
+    Struct {
+        name: Cow<'static, str>,
+        fields: PendingFields<T>,
+    },
+    Enum {
+        name: Cow<'static, str>,
+        variant: Cow<'static, str>,
+        fields: PendingFields<T>,
+    },
+    List(Vec<T>),
+    Map {
+        entries: Vec<(T::Key, T)>,
+        key: Option<T::Key>,
+    },
+}
+
+#[derive(Debug)]
+enum PendingFields<T> {
+    Named {
+        fields: Vec<(Cow<'static, str>, T)>,
+        next: Option<Cow<'static, str>>,
+    },
+    Unnamed(Vec<T>),
+    Unit,
+}
+
+impl<T> PendingFields<T> {
+    fn new(kind: FieldsKind, len: usize) -> Self {
+        match kind {
+            FieldsKind::Named => Self::Named {
+                fields: Vec::with_capacity(len),
+                next: None,
+            },
+            FieldsKind::Unnamed => Self::Unnamed(Vec::with_capacity(len)),
+            FieldsKind::Unit => Self::Unit,
+        }
+    }
+
+    fn push(&mut self, value: T) -> Result<(), StreamError> {
+        match self {
+            PendingFields::Named { fields, next } => {
+                let name = next.take().ok_or(StreamError::UnexpectedValue)?;
+                fields.push((name, value));
+            }
+            PendingFields::Unnamed(fields) => fields.push(value),
+            PendingFields::Unit => return Err(StreamError::UnexpectedValue),
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> StreamedFields<T> {
+        match self {
+            PendingFields::Named { fields, .. } => StreamedFields::Named(fields),
+            PendingFields::Unnamed(fields) => StreamedFields::Unnamed(fields),
+            PendingFields::Unit => StreamedFields::Unit,
+        }
+    }
+}
+
+impl<'a, T: StreamNode<'a>> Default for TreeSink<'a, T> {
+    fn default() -> Self {
+        Self {
+            stack: Vec::new(),
+            value: None,
+        }
+    }
+}
+
+impl<'a, T: StreamNode<'a>> TreeSink<'a, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value once it has been streamed completely
+    pub fn finish(self) -> Result<T, StreamError> {
+        match self.value {
+            Some(value) if self.stack.is_empty() => Ok(value),
+            _ => Err(StreamError::Incomplete),
+        }
+    }
+
+    /// Returns whether a complete value has been streamed
+    pub fn is_complete(&self) -> bool {
+        self.stack.is_empty() && self.value.is_some()
+    }
+
+    fn push(&mut self, value: T) -> Result<(), StreamError> {
+        match self.stack.last_mut() {
+            Some(Frame::Struct { fields, .. } | Frame::Enum { fields, .. }) => fields.push(value),
+            Some(Frame::List(list)) => {
+                list.push(value);
+                Ok(())
+            }
+            Some(Frame::Map { entries, key }) => {
+                match key.take() {
+                    Some(key) => entries.push((key, value)),
+                    None => *key = Some(value.into_key()?),
+                }
+                Ok(())
+            }
+            None if self.value.is_none() => {
+                self.value = Some(value);
+                Ok(())
+            }
+            None => Err(StreamError::UnexpectedValue),
+        }
+    }
+
+    fn push_primitive(&mut self, primitive: PrimitiveRef<'a>) -> Result<(), StreamError> {
+        self.push(T::primitive(primitive))
+    }
+
+    /// Checks that the innermost frame is the one that is ended
+    /// and that no field or map key is still waiting for its value.
+    /// The frame is left on the stack if it can't be ended.
+    fn check_end<F: FnOnce(&Frame<'a, T>) -> bool>(&self, is_ended: F) -> Result<(), StreamError> {
+        match self.stack.last() {
+            Some(frame) if is_ended(frame) => match frame {
+                Frame::Struct { fields, .. } | Frame::Enum { fields, .. }
+                    if matches!(fields, PendingFields::Named { next: Some(_), .. }) =>
+                {
+                    Err(StreamError::MissingValue)
+                }
+                Frame::Map { key: Some(_), .. } => Err(StreamError::MissingValue),
+                _ => Ok(()),
+            },
+            _ => Err(StreamError::UnexpectedEnd),
+        }
+    }
+}
+
+impl<'a, T: StreamNode<'a>> Sink<'a> for TreeSink<'a, T> {
+    type Error = StreamError;
+
+    fn integer(&mut self, value: Integer) -> Result<(), Self::Error> {
+        self.push_primitive(PrimitiveRef::Integer(value))
+    }
+
+    fn float(&mut self, value: Float) -> Result<(), Self::Error> {
+        self.push_primitive(PrimitiveRef::Float(value))
+    }
+
+    fn str(&mut self, value: &'a str) -> Result<(), Self::Error> {
+        self.push_primitive(PrimitiveRef::String(value))
+    }
+
+    fn os_str(&mut self, value: &'a OsStr) -> Result<(), Self::Error> {
+        self.push_primitive(PrimitiveRef::OsString(value))
+    }
+
+    fn char(&mut self, value: char) -> Result<(), Self::Error> {
+        self.push_primitive(PrimitiveRef::Char(value))
+    }
+
+    fn bool(&mut self, value: bool) -> Result<(), Self::Error> {
+        self.push_primitive(PrimitiveRef::Bool(value))
+    }
+
+    fn none(&mut self) -> Result<(), Self::Error> {
+        self.push(T::none())
+    }
+
+    fn begin_struct(
+        &mut self,
+        name: Cow<'static, str>,
+        kind: FieldsKind,
+        len: usize,
+    ) -> Result<(), Self::Error> {
+        self.stack.push(Frame::Struct {
+            name,
+            fields: PendingFields::new(kind, len),
+        });
+        Ok(())
+    }
+
+    fn field(&mut self, name: Cow<'static, str>) -> Result<(), Self::Error> {
+        match self.stack.last_mut() {
+            Some(Frame::Struct { fields, .. } | Frame::Enum { fields, .. }) => match fields {
+                PendingFields::Named { next, .. } if next.is_none() => {
+                    *next = Some(name);
+                    Ok(())
+                }
+                _ => Err(StreamError::UnexpectedField),
+            },
+            _ => Err(StreamError::UnexpectedField),
+        }
+    }
+
+    fn end_struct(&mut self) -> Result<(), Self::Error> {
+        self.check_end(|f| matches!(f, Frame::Struct { .. }))?;
+        match self.stack.pop() {
+            Some(Frame::Struct { name, fields }) => self.push(T::structure(name, fields.finish())),
+            _ => unreachable!("the frame was checked before"),
+        }
+    }
+
+    fn begin_enum(
+        &mut self,
+        name: Cow<'static, str>,
+        variant: Cow<'static, str>,
+        kind: FieldsKind,
+        len: usize,
+    ) -> Result<(), Self::Error> {
+        self.stack.push(Frame::Enum {
+            name,
+            variant,
+            fields: PendingFields::new(kind, len),
+        });
+        Ok(())
+    }
+
+    fn end_enum(&mut self) -> Result<(), Self::Error> {
+        self.check_end(|f| matches!(f, Frame::Enum { .. }))?;
+        match self.stack.pop() {
+            Some(Frame::Enum {
+                name,
+                variant,
+                fields,
+            }) => self.push(T::enumeration(name, variant, fields.finish())),
+            _ => unreachable!("the frame was checked before"),
+        }
+    }
+
+    fn begin_list(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.stack.push(Frame::List(Vec::with_capacity(len)));
+        Ok(())
+    }
+
+    fn end_list(&mut self) -> Result<(), Self::Error> {
+        self.check_end(|f| matches!(f, Frame::List(_)))?;
+        match self.stack.pop() {
+            Some(Frame::List(list)) => self.push(T::list(list)),
+            _ => unreachable!("the frame was checked before"),
+        }
+    }
+
+    fn begin_map(&mut self, len: usize) -> Result<(), Self::Error> {
+        self.stack.push(Frame::Map {
+            entries: Vec::with_capacity(len),
+            key: None,
+        });
+        Ok(())
+    }
+
+    fn end_map(&mut self) -> Result<(), Self::Error> {
+        self.check_end(|f| matches!(f, Frame::Map { .. }))?;
+        match self.stack.pop() {
+            Some(Frame::Map { entries, .. }) => self.push(T::map(entries)),
+            _ => unreachable!("the frame was checked before"),
+        }
+    }
+}
+
+impl<'a> StreamNode<'a> for Value {
+    type Key = HashableValue;
+
+    fn primitive(primitive: PrimitiveRef<'a>) -> Self {
+        Value::Primitive(primitive.into_owned())
+    }
+
+    fn none() -> Self {
+        Value::None
+    }
+
+    fn structure(name: Cow<'static, str>, fields: StreamedFields<Self>) -> Self {
+        Value::Struct(Struct {
+            name,
+            fields: streamed_fields(fields),
+        })
+    }
+
+    fn enumeration(
+        name: Cow<'static, str>,
+        variant: Cow<'static, str>,
+        fields: StreamedFields<Self>,
+    ) -> Self {
+        Value::Enum(Enum {
+            name,
+            variant,
+            fields: streamed_fields(fields),
+        })
+    }
+
+    fn list(items: Vec<Self>) -> Self {
+        Value::List(items)
+    }
+
+    fn map(entries: Vec<(Self::Key, Self)>) -> Self {
+        Value::Map(entries.into_iter().collect())
+    }
+
+    fn into_key(self) -> Result<Self::Key, StreamError> {
+        into_hashable(self)
+    }
+}
+
+fn streamed_fields(fields: StreamedFields<Value>) -> Fields {
+    match fields {
+        StreamedFields::Named(named) => Fields::Named(named.into_iter().collect()),
+        StreamedFields::Unnamed(unnamed) => Fields::Unnamed(unnamed),
+        StreamedFields::Unit => Fields::Unit,
+    }
+}
+
+/// Converts a streamed map key into a hashable value
+pub(crate) fn into_hashable(value: Value) -> Result<HashableValue, StreamError> {
+    let hashable = match value {
+        Value::Primitive(p) => HashableValue::Primitive(match p {
+            Primitive::Integer(i) => HashablePrimitive::Integer(i),
+            Primitive::String(s) => HashablePrimitive::String(s),
+            Primitive::OsString(o) => HashablePrimitive::OsString(o),
+            Primitive::Char(c) => HashablePrimitive::Char(c),
+            Primitive::Bool(b) => HashablePrimitive::Bool(b),
+            Primitive::Float(_) => return Err(StreamError::UnhashableKey),
+        }),
+        Value::List(l) => {
+            HashableValue::List(l.into_iter().map(into_hashable).collect::<Result<_, _>>()?)
+        }
+        Value::None => HashableValue::None,
+        Value::Struct(_) | Value::Enum(_) | Value::Map(_) => {
+            return Err(StreamError::UnhashableKey)
+        }
+    };
+
+    Ok(hashable)
+}
+
+/// An error that occurred because a [Sink] received the pieces of a value in an invalid order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StreamError {
+    /// A value was passed where none was expected
+    UnexpectedValue,
+    /// A field name was passed outside of named fields or twice in a row
+    UnexpectedField,
+    /// A struct, enum, list or map was ended without being started
+    UnexpectedEnd,
+    /// A struct, enum or map was ended while a field name or map key was waiting for its value
+    MissingValue,
+    /// A map key can't be used as a [crate::HashableValue]
+    UnhashableKey,
+    /// The value hasn't been streamed completely
+    Incomplete,
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::UnexpectedValue => write!(f, "unexpected value"),
+            StreamError::UnexpectedField => write!(f, "unexpected field name"),
+            StreamError::UnexpectedEnd => write!(f, "unexpected end of a value"),
+            StreamError::MissingValue => write!(f, "a field or map key is missing its value"),
+            StreamError::UnhashableKey => write!(f, "the map key isn't hashable"),
+            StreamError::Incomplete => write!(f, "the value is incomplete"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+#[cfg(test)]
+mod test {
+    use std::{borrow::Cow, collections::HashMap};
+
+    use crate::*;
+
+    fn test_value() -> Value {
+        let mut value = rusty_value!(struct Config {
+            name: "test",
+            ports: [80u16, 443u16],
+            ratio: 0.5f32,
+            hosts: { "a" => None },
+            mode: enum Mode::Slow { delay: 1u32 },
+            nested: enum Nested::Pair(1u8, struct Unit),
+        });
+        value
+            .set(
+                &"tuple".parse().unwrap(),
+                Value::Struct(Struct {
+                    name: "Tuple".into(),
+                    fields: Fields::Unnamed(vec![Value::Primitive(Primitive::Char('c'))]),
+                }),
+            )
+            .unwrap();
+
+        value
+    }
+
+    #[test]
+    fn it_rebuilds_values() {
+        let value = test_value();
+        assert_eq!(Value::from_stream(&value).as_ref(), Ok(&value));
+
+        let Ok(Value::Struct(rebuilt)) = Value::from_stream(&value) else {
+            panic!("expected a struct");
+        };
+        let Fields::Named(fields) = &rebuilt.fields else {
+            panic!("expected named fields");
+        };
+        // static names are passed through without being copied
+        assert!(matches!(rebuilt.name, Cow::Borrowed("Config")));
+        assert!(fields.keys().any(|k| matches!(k, Cow::Borrowed("ports"))));
+    }
+
+    #[test]
+    fn it_streams_std_types() {
+        let mut map = HashMap::new();
+        map.insert(String::from("a"), Some(vec![1u8, 2]));
+        map.insert(String::from("b"), None);
+
+        assert_eq!(Value::from_stream(&map), Ok(map.clone().into_rusty_value()));
+        assert_eq!(
+            Value::from_stream(&std::path::PathBuf::from("/tmp")),
+            Ok(std::path::PathBuf::from("/tmp").into_rusty_value())
+        );
+    }
+
+    #[test]
+    fn it_rejects_invalid_streams() {
+        let mut sink = ValueSink::new();
+        sink.begin_struct("A".into(), FieldsKind::Named, 1).unwrap();
+        assert_eq!(sink.bool(true), Err(StreamError::UnexpectedValue));
+        assert_eq!(sink.end_list(), Err(StreamError::UnexpectedEnd));
+
+        let mut sink = ValueSink::new();
+        sink.begin_struct("A".into(), FieldsKind::Named, 1).unwrap();
+        sink.field("a".into()).unwrap();
+        assert_eq!(sink.end_struct(), Err(StreamError::MissingValue));
+        sink.bool(true).unwrap();
+        assert_eq!(sink.end_enum(), Err(StreamError::UnexpectedEnd));
+        // the struct is still open after ending it failed
+        assert_eq!(sink.end_struct(), Ok(()));
+        assert_eq!(sink.finish(), Ok(rusty_value!(struct A { a: true })));
+
+        let mut sink = ValueSink::new();
+        sink.begin_map(1).unwrap();
+        sink.str("a").unwrap();
+        assert_eq!(sink.end_map(), Err(StreamError::MissingValue));
+
+        let mut sink = ValueSink::new();
+        sink.begin_map(1).unwrap();
+        assert_eq!(sink.float(Float::F32(1.0)), Err(StreamError::UnhashableKey));
+        assert_eq!(ValueSink::new().finish(), Err(StreamError::Incomplete));
+    }
+}
@@ -0,0 +1,1060 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    path::integer_to_i128, Fields, FieldsKind, FieldsSchema, Float, HashableValue, Integer, Path,
+    PathSegment, Primitive, PrimitiveKind, PrimitiveRef, RustyValue, StreamError, StreamRustyValue,
+    TypeSchema, Value, ValueRef,
+};
+
+/// A value that doesn't match what was expected
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    /// The path of the value inside the validated value
+    pub path: Path,
+    pub kind: ViolationKind,
+}
+
+/// The reason why a value doesn't match what was expected
+#[derive(Clone, Debug, PartialEq)]
+pub enum ViolationKind {
+    /// The value has a different kind than the schema
+    TypeMismatch {
+        expected: String,
+        found: &'static str,
+    },
+    /// The integer doesn't fit into the integer type of the schema
+    OutOfRange {
+        expected: PrimitiveKind,
+        value: Integer,
+    },
+    /// The struct or enum has a different name than the schema
+    NameMismatch {
+        expected: &'static str,
+        found: String,
+    },
+    /// The enum doesn't have a variant with that name
+    UnknownVariant(String),
+    /// The struct or enum variant has different kinds of fields than the schema
+    FieldsMismatch {
+        expected: FieldsKind,
+        found: FieldsKind,
+    },
+    /// A named field of the schema is missing
+    MissingField(&'static str),
+    /// The named field doesn't exist in the schema
+    UnknownField(String),
+    /// The number of unnamed fields differs from the schema
+    FieldCount { expected: usize, found: usize },
+    /// The number is outside of the inclusive range of a [Constraint::Range]
+    NotInRange {
+        min: Option<Number>,
+        max: Option<Number>,
+        value: Number,
+    },
+    /// The length is outside of the inclusive range of a [Constraint::Length]
+    InvalidLength {
+        min: Option<usize>,
+        max: Option<usize>,
+        length: usize,
+    },
+    /// The value is empty or none
+    Empty,
+    /// The string doesn't match the regex pattern
+    PatternMismatch(String),
+    /// A custom validation function returned an error with this message
+    Custom(String),
+    /// The field couldn't be converted into a value to check its constraints
+    Stream(StreamError),
+}
+
+impl Value {
+    /// Checks that the value conforms to the type schema.
+    /// Named fields with an `Option` schema may be missing.
+    ///
+    /// Returns every violation instead of stopping at the first one.
+    /// Values that don't match the kind of their schema aren't checked any further.
+    pub fn validate(&self, schema: &TypeSchema) -> Result<(), Vec<Violation>> {
+        let mut validator = Validator {
+            path: Path::new(),
+            violations: Vec::new(),
+            types: Vec::new(),
+        };
+        validator.validate(self, schema);
+
+        if validator.violations.is_empty() {
+            Ok(())
+        } else {
+            Err(validator.violations)
+        }
+    }
+}
+
+struct Validator<'s> {
+    path: Path,
+    violations: Vec<Violation>,
+    /// The enclosing struct and enum schemas that [TypeSchema::Ref]s refer to
+    types: Vec<&'s TypeSchema>,
+}
+
+impl<'s> Validator<'s> {
+    fn validate(&mut self, value: &Value, schema: &'s TypeSchema) {
+        match (schema, value) {
+            (TypeSchema::Ref { type_name, .. }, value) => {
+                let target = self
+                    .types
+                    .iter()
+                    .rev()
+                    .find(|t| t.type_name() == Some(type_name))
+                    .copied();
+                // a reference without an enclosing type can't be checked
+                if let Some(target) = target {
+                    self.validate(value, target);
+                }
+            }
+            (TypeSchema::Any, _) | (TypeSchema::Unit, Value::None) => {}
+            (TypeSchema::Option(_), Value::None) => {}
+            (TypeSchema::Option(inner), value) => self.validate(value, inner),
+            (TypeSchema::Sensitive(inner), value) => self.validate(value, inner),
+            (TypeSchema::Primitive(kind), Value::Primitive(p)) => {
+                self.validate_primitive(*kind, p, schema)
+            }
+            (TypeSchema::Struct(s), Value::Struct(v)) => {
+                if s.name != v.name {
+                    self.violation(ViolationKind::NameMismatch {
+                        expected: s.name,
+                        found: v.name.to_string(),
+                    });
+                } else {
+                    self.types.push(schema);
+                    self.validate_fields(&v.fields, &s.fields);
+                    self.types.pop();
+                }
+            }
+            (TypeSchema::Enum(e), Value::Enum(v)) => {
+                if e.name != v.name {
+                    self.violation(ViolationKind::NameMismatch {
+                        expected: e.name,
+                        found: v.name.to_string(),
+                    });
+                } else if let Some(variant) = e.variant(&v.variant) {
+                    self.types.push(schema);
+                    self.validate_fields(&v.fields, &variant.fields);
+                    self.types.pop();
+                } else {
+                    self.violation(ViolationKind::UnknownVariant(v.variant.to_string()));
+                }
+            }
+            (TypeSchema::List(inner), Value::List(l)) => self.validate_list(l, inner),
+            (TypeSchema::Map { key, value }, Value::Map(m)) => self.validate_map(m, key, value),
+            (schema, value) => self.type_mismatch(schema, value),
+        }
+    }
+
+    fn validate_primitive(
+        &mut self,
+        kind: PrimitiveKind,
+        primitive: &Primitive,
+        schema: &TypeSchema,
+    ) {
+        let matches = match (kind, primitive) {
+            (PrimitiveKind::F32 | PrimitiveKind::F64, Primitive::Float(_)) => true,
+            (PrimitiveKind::String, Primitive::String(_)) => true,
+            (PrimitiveKind::OsString, Primitive::OsString(_) | Primitive::String(_)) => true,
+            (PrimitiveKind::Char, Primitive::Char(_)) => true,
+            (PrimitiveKind::Bool, Primitive::Bool(_)) => true,
+            (kind, Primitive::Integer(i)) if is_integer(kind) => {
+                if !fits(kind, i) {
+                    self.violation(ViolationKind::OutOfRange {
+                        expected: kind,
+                        value: i.clone(),
+                    });
+                }
+                true
+            }
+            _ => false,
+        };
+
+        if !matches {
+            self.violation(ViolationKind::TypeMismatch {
+                expected: schema.to_string(),
+                found: primitive_kind_name(primitive),
+            });
+        }
+    }
+
+    fn validate_fields(&mut self, fields: &Fields, schema: &'s FieldsSchema) {
+        match (schema, fields) {
+            (FieldsSchema::Named(schema), Fields::Named(fields)) => {
+                for field in schema {
+                    match fields.get(field.name) {
+                        Some(value) => {
+                            self.path.push(PathSegment::Field(field.name.to_owned()));
+                            self.validate(value, &field.schema);
+                            self.path.pop();
+                        }
+                        None if is_optional(&field.schema) => {}
+                        None => self.violation(ViolationKind::MissingField(field.name)),
+                    }
+                }
+
+                let mut unknown = fields
+                    .keys()
+                    .filter(|name| !schema.iter().any(|f| f.name == *name))
+                    .collect::<Vec<_>>();
+                unknown.sort();
+
+                for name in unknown {
+                    self.violation(ViolationKind::UnknownField(name.to_string()));
+                }
+            }
+            (FieldsSchema::Unnamed(schema), Fields::Unnamed(fields)) => {
+                if schema.len() != fields.len() {
+                    self.violation(ViolationKind::FieldCount {
+                        expected: schema.len(),
+                        found: fields.len(),
+                    });
+                }
+                for (index, (value, schema)) in fields.iter().zip(schema).enumerate() {
+                    self.path.push(PathSegment::Index(index));
+                    self.validate(value, schema);
+                    self.path.pop();
+                }
+            }
+            (FieldsSchema::Unit, Fields::Unit) => {}
+            (schema, fields) => self.violation(ViolationKind::FieldsMismatch {
+                expected: match schema {
+                    FieldsSchema::Named(_) => FieldsKind::Named,
+                    FieldsSchema::Unnamed(_) => FieldsKind::Unnamed,
+                    FieldsSchema::Unit => FieldsKind::Unit,
+                },
+                found: match fields {
+                    Fields::Named(_) => FieldsKind::Named,
+                    Fields::Unnamed(_) => FieldsKind::Unnamed,
+                    Fields::Unit => FieldsKind::Unit,
+                },
+            }),
+        }
+    }
+
+    fn validate_list(&mut self, list: &[Value], schema: &'s TypeSchema) {
+        for (index, value) in list.iter().enumerate() {
+            self.path.push(PathSegment::Index(index));
+            self.validate(value, schema);
+            self.path.pop();
+        }
+    }
+
+    fn validate_map(
+        &mut self,
+        map: &HashMap<HashableValue, Value>,
+        key_schema: &'s TypeSchema,
+        value_schema: &'s TypeSchema,
+    ) {
+        let mut entries = map.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(k, _)| *k);
+
+        for (key, value) in entries {
+            self.path.push(PathSegment::Key(key.clone()));
+            self.validate(&key.clone().into_rusty_value(), key_schema);
+            self.validate(value, value_schema);
+            self.path.pop();
+        }
+    }
+
+    fn type_mismatch(&mut self, schema: &TypeSchema, value: &Value) {
+        let found = match value {
+            Value::Primitive(p) => primitive_kind_name(p),
+            Value::Struct(_) => "struct",
+            Value::Enum(_) => "enum",
+            Value::Map(_) => "map",
+            Value::List(_) => "list",
+            Value::None => "none",
+        };
+        self.violation(ViolationKind::TypeMismatch {
+            expected: schema.to_string(),
+            found,
+        });
+    }
+
+    fn violation(&mut self, kind: ViolationKind) {
+        self.violations.push(Violation {
+            path: self.path.clone(),
+            kind,
+        });
+    }
+}
+
+/// Returns whether a named field with the schema may be missing
+fn is_optional(schema: &TypeSchema) -> bool {
+    match schema {
+        TypeSchema::Option(_) => true,
+        TypeSchema::Sensitive(inner) => is_optional(inner),
+        _ => false,
+    }
+}
+
+fn primitive_kind_name(primitive: &Primitive) -> &'static str {
+    match primitive {
+        Primitive::Integer(_) => "integer",
+        Primitive::Float(_) => "float",
+        Primitive::String(_) => "string",
+        Primitive::OsString(_) => "os string",
+        Primitive::Char(_) => "char",
+        Primitive::Bool(_) => "bool",
+    }
+}
+
+fn is_integer(kind: PrimitiveKind) -> bool {
+    !matches!(
+        kind,
+        PrimitiveKind::F32
+            | PrimitiveKind::F64
+            | PrimitiveKind::String
+            | PrimitiveKind::OsString
+            | PrimitiveKind::Char
+            | PrimitiveKind::Bool
+    )
+}
+
+/// Returns whether the integer fits into the integer type of the given kind
+fn fits(kind: PrimitiveKind, integer: &Integer) -> bool {
+    let Some(n) = integer_to_i128(integer) else {
+        // only u128 values above i128::MAX don't fit into an i128
+        return kind == PrimitiveKind::U128;
+    };
+
+    match kind {
+        PrimitiveKind::USize => usize::try_from(n).is_ok(),
+        PrimitiveKind::ISize => isize::try_from(n).is_ok(),
+        PrimitiveKind::U8 => u8::try_from(n).is_ok(),
+        PrimitiveKind::I8 => i8::try_from(n).is_ok(),
+        PrimitiveKind::U16 => u16::try_from(n).is_ok(),
+        PrimitiveKind::I16 => i16::try_from(n).is_ok(),
+        PrimitiveKind::U32 => u32::try_from(n).is_ok(),
+        PrimitiveKind::I32 => i32::try_from(n).is_ok(),
+        PrimitiveKind::U64 => u64::try_from(n).is_ok(),
+        PrimitiveKind::I64 => i64::try_from(n).is_ok(),
+        PrimitiveKind::U128 => n >= 0,
+        _ => true,
+    }
+}
+
+/// Checks the constraints that are declared next to the fields of a type with
+/// `#[rusty_value(validate(...))]`.
+///
+/// Implemented by the `RustyValue` derive for types where at least one field has
+/// validations, other types may implement it themselves. The supported constraints are
+/// `range(min = .., max = ..)`, `length(min = .., max = ..)`, `non_empty`,
+/// `regex = ".."` (requires the `regex` feature, the pattern is checked at compile time)
+/// and `custom = "path::to::fn"`
+/// which calls a `fn(&T) -> Result<(), E: Display>` with the field.
+/// Fields marked with `nested` are validated with their own [Validate] impl.
+pub trait Validate {
+    /// Checks the value and returns every violated constraint with the path of its field
+    fn validate(&self) -> Result<(), Vec<Violation>> {
+        let mut violations = Vec::new();
+        self.validate_at(&mut Path::new(), &mut violations);
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Adds the violations of the value to the list with paths relative to the given one
+    fn validate_at(&self, path: &mut Path, violations: &mut Vec<Violation>);
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate_at(&self, path: &mut Path, violations: &mut Vec<Violation>) {
+        if let Some(value) = self {
+            value.validate_at(path, violations)
+        }
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_at(&self, path: &mut Path, violations: &mut Vec<Violation>) {
+        for (index, value) in self.iter().enumerate() {
+            path.push(PathSegment::Index(index));
+            value.validate_at(path, violations);
+            path.pop();
+        }
+    }
+}
+
+impl<T: Validate + ?Sized> Validate for Box<T> {
+    fn validate_at(&self, path: &mut Path, violations: &mut Vec<Violation>) {
+        (**self).validate_at(path, violations)
+    }
+}
+
+/// A constraint on the value of a single field.
+/// None values only violate [Constraint::NonEmpty].
+#[derive(Clone, Debug)]
+pub enum Constraint {
+    /// An integer or float inside of the inclusive range
+    Range {
+        min: Option<Number>,
+        max: Option<Number>,
+    },
+    /// A string with a number of chars, or a list or map with a number of
+    /// entries inside of the inclusive range
+    Length {
+        min: Option<usize>,
+        max: Option<usize>,
+    },
+    /// A string, list or map that isn't empty, or any other value that isn't none
+    NonEmpty,
+    /// A string that matches the pattern
+    #[cfg(feature = "regex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "regex")))]
+    Regex(regex::Regex),
+}
+
+impl Constraint {
+    /// Creates a [Constraint::Regex] from the pattern
+    #[cfg(feature = "regex")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "regex")))]
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self::Regex(regex::Regex::new(pattern)?))
+    }
+
+    /// Checks the value against the constraint
+    pub fn check(&self, value: &ValueRef) -> Result<(), ViolationKind> {
+        if let ValueRef::None = value {
+            return match self {
+                Constraint::NonEmpty => Err(ViolationKind::Empty),
+                _ => Ok(()),
+            };
+        }
+
+        match self {
+            Constraint::Range { min, max } => {
+                let n = match value {
+                    ValueRef::Primitive(PrimitiveRef::Integer(i)) => Number::from(i),
+                    ValueRef::Primitive(PrimitiveRef::Float(Float::F32(f))) => Number::from(*f),
+                    ValueRef::Primitive(PrimitiveRef::Float(Float::F64(f))) => Number::from(*f),
+                    value => return Err(constraint_mismatch("number", value)),
+                };
+                let in_range = min.is_none_or(|min| n >= min) && max.is_none_or(|max| n <= max);
+
+                if in_range {
+                    Ok(())
+                } else {
+                    Err(ViolationKind::NotInRange {
+                        min: *min,
+                        max: *max,
+                        value: n,
+                    })
+                }
+            }
+            Constraint::Length { min, max } => {
+                let length = match value {
+                    ValueRef::Primitive(PrimitiveRef::String(s)) => s.chars().count(),
+                    ValueRef::Primitive(PrimitiveRef::OsString(s)) => s.len(),
+                    ValueRef::List(l) => l.len(),
+                    ValueRef::Map(m) => m.len(),
+                    value => return Err(constraint_mismatch("string, list or map", value)),
+                };
+
+                if min.is_none_or(|min| length >= min) && max.is_none_or(|max| length <= max) {
+                    Ok(())
+                } else {
+                    Err(ViolationKind::InvalidLength {
+                        min: *min,
+                        max: *max,
+                        length,
+                    })
+                }
+            }
+            Constraint::NonEmpty => {
+                let empty = match value {
+                    ValueRef::Primitive(PrimitiveRef::String(s)) => s.is_empty(),
+                    ValueRef::Primitive(PrimitiveRef::OsString(s)) => s.is_empty(),
+                    ValueRef::List(l) => l.is_empty(),
+                    ValueRef::Map(m) => m.is_empty(),
+                    _ => false,
+                };
+
+                if empty {
+                    Err(ViolationKind::Empty)
+                } else {
+                    Ok(())
+                }
+            }
+            #[cfg(feature = "regex")]
+            Constraint::Regex(regex) => match value {
+                ValueRef::Primitive(PrimitiveRef::String(s)) if regex.is_match(s) => Ok(()),
+                ValueRef::Primitive(PrimitiveRef::String(_)) => {
+                    Err(ViolationKind::PatternMismatch(regex.as_str().to_owned()))
+                }
+                value => Err(constraint_mismatch("string", value)),
+            },
+        }
+    }
+
+    /// Streams the field and adds a violation for every constraint it doesn't satisfy
+    pub fn check_field<T: StreamRustyValue + ?Sized>(
+        constraints: &[Constraint],
+        field: &T,
+        path: &Path,
+        violations: &mut Vec<Violation>,
+    ) {
+        let kinds = match ValueRef::from_stream(field) {
+            Ok(value) => constraints
+                .iter()
+                .filter_map(|c| c.check(&value).err())
+                .collect(),
+            Err(e) => vec![ViolationKind::Stream(e)],
+        };
+
+        violations.extend(kinds.into_iter().map(|kind| Violation {
+            path: path.clone(),
+            kind,
+        }));
+    }
+}
+
+/// Used by the `RustyValue` derive to create a [Constraint::Regex] from `validate(regex = "..")`
+#[cfg(feature = "regex")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __regex_constraint {
+    ($pattern:literal) => {
+        $crate::Constraint::regex($pattern)
+            .expect("invalid pattern in #[rusty_value(validate(regex))]")
+    };
+}
+
+/// Used by the `RustyValue` derive to reject `validate(regex = "..")` without the `regex` feature
+#[cfg(not(feature = "regex"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __regex_constraint {
+    ($pattern:literal) => {
+        compile_error!(
+            "`#[rusty_value(validate(regex = \"..\"))]` requires the `regex` feature of rusty-value"
+        )
+    };
+}
+
+/// A bound of a [Constraint::Range] or the number that is checked against it.
+/// Two integers are compared exactly, everything else is compared as `f64`.
+#[derive(Clone, Copy, Debug)]
+pub enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Integer(n) => n as f64,
+            Number::Float(f) => f,
+        }
+    }
+}
+
+impl PartialEq for Number {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(std::cmp::Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (Number::Integer(a), Number::Integer(b)) => a.partial_cmp(b),
+            (a, b) => a.as_f64().partial_cmp(&b.as_f64()),
+        }
+    }
+}
+
+impl Display for Number {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Number::Integer(n) => n.fmt(f),
+            Number::Float(n) => n.fmt(f),
+        }
+    }
+}
+
+macro_rules! number_from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Number {
+                #[inline]
+                fn from(n: $t) -> Self {
+                    Number::Integer(n as i128)
+                }
+            }
+        )*
+    };
+}
+
+number_from_integer!(usize, isize, u8, i8, u16, i16, u32, i32, u64, i64, i128);
+
+/// Integers above [i128::MAX] are compared as `f64`
+impl From<u128> for Number {
+    fn from(n: u128) -> Self {
+        i128::try_from(n).map_or(Number::Float(n as f64), Number::Integer)
+    }
+}
+
+impl From<f32> for Number {
+    #[inline]
+    fn from(f: f32) -> Self {
+        Number::Float(f as f64)
+    }
+}
+
+impl From<f64> for Number {
+    #[inline]
+    fn from(f: f64) -> Self {
+        Number::Float(f)
+    }
+}
+
+impl From<&Integer> for Number {
+    fn from(integer: &Integer) -> Self {
+        match *integer {
+            Integer::U128(n) => Number::from(n),
+            _ => Number::Integer(integer_to_i128(integer).expect("only u128 can exceed i128")),
+        }
+    }
+}
+
+fn constraint_mismatch(expected: &str, value: &ValueRef) -> ViolationKind {
+    let found = match value {
+        ValueRef::Primitive(PrimitiveRef::Integer(_)) => "integer",
+        ValueRef::Primitive(PrimitiveRef::Float(_)) => "float",
+        ValueRef::Primitive(PrimitiveRef::String(_)) => "string",
+        ValueRef::Primitive(PrimitiveRef::OsString(_)) => "os string",
+        ValueRef::Primitive(PrimitiveRef::Char(_)) => "char",
+        ValueRef::Primitive(PrimitiveRef::Bool(_)) => "bool",
+        ValueRef::Struct(_) => "struct",
+        ValueRef::Enum(_) => "enum",
+        ValueRef::Map(_) => "map",
+        ValueRef::List(_) => "list",
+        ValueRef::None => "none",
+    };
+
+    ViolationKind::TypeMismatch {
+        expected: expected.to_owned(),
+        found,
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "$: {}", self.kind)
+        } else {
+            write!(f, "{}: {}", self.path, self.kind)
+        }
+    }
+}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ViolationKind::TypeMismatch { expected, found } => {
+                write!(f, "expected `{expected}` but found {found}")
+            }
+            ViolationKind::OutOfRange { expected, value } => {
+                write!(f, "{value} doesn't fit into `{expected}`")
+            }
+            ViolationKind::NameMismatch { expected, found } => {
+                write!(f, "expected `{expected}` but found `{found}`")
+            }
+            ViolationKind::UnknownVariant(variant) => write!(f, "unknown variant `{variant}`"),
+            ViolationKind::FieldsMismatch { expected, found } => {
+                write!(f, "expected {expected:?} fields but found {found:?} fields")
+            }
+            ViolationKind::MissingField(name) => write!(f, "missing field `{name}`"),
+            ViolationKind::UnknownField(name) => write!(f, "unknown field `{name}`"),
+            ViolationKind::FieldCount { expected, found } => {
+                write!(f, "expected {expected} fields but found {found}")
+            }
+            ViolationKind::NotInRange { min, max, value } => {
+                write!(f, "{value} isn't in the range ")?;
+                write_range(f, min, max)
+            }
+            ViolationKind::InvalidLength { min, max, length } => {
+                write!(f, "length {length} isn't in the range ")?;
+                write_range(f, min, max)
+            }
+            ViolationKind::Empty => write!(f, "the value is empty"),
+            ViolationKind::PatternMismatch(pattern) => {
+                write!(f, "the string doesn't match `{pattern}`")
+            }
+            ViolationKind::Custom(message) => message.fmt(f),
+            ViolationKind::Stream(e) => e.fmt(f),
+        }
+    }
+}
+
+/// Writes an inclusive range like `1..=10` where missing bounds are left out
+fn write_range<T: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    min: &Option<T>,
+    max: &Option<T>,
+) -> std::fmt::Result {
+    if let Some(min) = min {
+        write!(f, "{min}")?;
+    }
+    match max {
+        Some(max) => write!(f, "..={max}"),
+        None => write!(f, ".."),
+    }
+}
+
+impl std::error::Error for Violation {}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+    #![allow(unused)]
+    use std::collections::HashMap;
+
+    use crate as rusty_value;
+    use crate::*;
+
+    #[derive(RustyType, RustyValue)]
+    struct Server {
+        port: u16,
+        name: Option<String>,
+        tags: Vec<String>,
+        limits: HashMap<String, u8>,
+        mode: Mode,
+    }
+
+    #[derive(RustyType, RustyValue)]
+    enum Mode {
+        Fast,
+        Slow { delay: u32 },
+    }
+
+    #[derive(RustyType)]
+    struct Credentials {
+        user: String,
+        #[rusty_value(sensitive)]
+        token: Option<String>,
+    }
+
+    #[derive(RustyType, RustyValue)]
+    struct Directory {
+        name: String,
+        entries: Vec<Directory>,
+    }
+
+    #[derive(RustyValue)]
+    struct Listener {
+        #[rusty_value(validate(range(min = 1, max = 65535)))]
+        port: u32,
+        #[rusty_value(validate(non_empty, length(max = 8)))]
+        name: String,
+        #[rusty_value(validate(custom = "even"))]
+        workers: u8,
+        #[rusty_value(validate(nested))]
+        backends: Vec<Backend>,
+    }
+
+    #[derive(RustyValue)]
+    enum Backend {
+        Local,
+        Remote(#[rusty_value(validate(length(min = 3)))] String, u16),
+        Socket {
+            #[rusty_value(validate(non_empty))]
+            path: Option<String>,
+        },
+    }
+
+    /// Types with the same names as the ones used by the derived impl
+    mod shadowed {
+        #![allow(dead_code)]
+        use crate as rusty_value;
+        use crate::*;
+        use std::path::Path;
+
+        struct Violation;
+        struct Constraint;
+        trait Validate {}
+
+        #[derive(RustyValue)]
+        pub struct Checked {
+            #[rusty_value(validate(non_empty))]
+            pub name: String,
+        }
+
+        #[derive(RustyValue)]
+        pub struct Manual(pub u8);
+
+        impl rusty_value::Validate for Manual {
+            fn validate_at(
+                &self,
+                path: &mut rusty_value::Path,
+                violations: &mut Vec<rusty_value::Violation>,
+            ) {
+                if self.0 == 0 {
+                    violations.push(rusty_value::Violation {
+                        path: path.clone(),
+                        kind: rusty_value::ViolationKind::Empty,
+                    });
+                }
+            }
+        }
+    }
+
+    fn even(n: &u8) -> Result<(), String> {
+        if n.is_multiple_of(2) {
+            Ok(())
+        } else {
+            Err(format!("{n} isn't even"))
+        }
+    }
+
+    fn violations(value: Value) -> Vec<String> {
+        value
+            .validate(&Server::rusty_type())
+            .unwrap_err()
+            .iter()
+            .map(Violation::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn it_accepts_matching_values() {
+        let value = Server {
+            port: 80,
+            name: None,
+            tags: vec![String::from("a")],
+            limits: HashMap::new(),
+            mode: Mode::Slow { delay: 1 },
+        }
+        .into_rusty_value();
+
+        assert_eq!(value.validate(&Server::rusty_type()), Ok(()));
+        assert_eq!(
+            rusty_value!(struct Server {
+                port: 443u64,
+                tags: [],
+                limits: { "cpu" => 2u32 },
+                mode: enum Mode::Fast,
+            })
+            .validate(&Server::rusty_type()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn it_allows_missing_sensitive_options() {
+        assert_eq!(
+            rusty_value!(struct Credentials { user: "admin" }).validate(&Credentials::rusty_type()),
+            Ok(())
+        );
+        assert!(rusty_value!(struct Credentials { token: "abc" })
+            .validate(&Credentials::rusty_type())
+            .is_err());
+    }
+
+    #[test]
+    fn it_validates_recursive_types() {
+        let schema = Directory::rusty_type();
+        assert_eq!(
+            rusty_value!(struct Directory {
+                name: "a",
+                entries: [struct Directory { name: "b", entries: [] }],
+            })
+            .validate(&schema),
+            Ok(())
+        );
+        assert_eq!(
+            rusty_value!(struct Directory {
+                name: "a",
+                entries: [struct Directory {
+                    name: "b",
+                    entries: [struct Directory { name: 1u8, entries: [] }],
+                }],
+            })
+            .validate(&schema)
+            .unwrap_err()
+            .iter()
+            .map(Violation::to_string)
+            .collect::<Vec<_>>(),
+            vec!["entries[0].entries[0].name: expected `String` but found integer"]
+        );
+    }
+
+    #[test]
+    fn it_only_implements_validate_for_validated_types() {
+        assert!(shadowed::Checked {
+            name: String::new()
+        }
+        .validate()
+        .is_err());
+        assert!(shadowed::Manual(0).validate().is_err());
+        assert_eq!(shadowed::Manual(1).validate(), Ok(()));
+    }
+
+    #[test]
+    fn it_reports_all_violations() {
+        assert_eq!(
+            violations(rusty_value!(struct Server {
+                port: 70000u32,
+                name: 1u8,
+                tags: ["a", true],
+                limits: { "cpu" => -1i8 },
+                extra: None,
+            })),
+            vec![
+                "port: 70000 doesn't fit into `u16`",
+                "name: expected `String` but found integer",
+                "tags[1]: expected `String` but found bool",
+                r#"limits["cpu"]: -1 doesn't fit into `u8`"#,
+                "$: missing field `mode`",
+                "$: unknown field `extra`",
+            ]
+        );
+        assert_eq!(
+            violations(rusty_value!(struct Server {
+                port: 1u16,
+                tags: [],
+                limits: {},
+                mode: enum Mode::Medium,
+            })),
+            vec!["mode: unknown variant `Medium`"]
+        );
+        assert_eq!(
+            violations(rusty_value!(struct Server {
+                port: 1u16,
+                tags: [],
+                limits: {},
+                mode: enum Mode::Slow(1u32),
+            })),
+            vec!["mode: expected Named fields but found Unnamed fields"]
+        );
+        assert_eq!(
+            violations(rusty_value!(struct Client)),
+            vec!["$: expected `Server` but found `Client`"]
+        );
+    }
+
+    #[test]
+    fn it_checks_field_constraints() {
+        let listener = Listener {
+            port: 8080,
+            name: String::from("api"),
+            workers: 4,
+            backends: vec![
+                Backend::Local,
+                Backend::Remote(String::from("db1"), 5432),
+                Backend::Socket {
+                    path: Some(String::from("/run/db.sock")),
+                },
+            ],
+        };
+        assert_eq!(listener.validate(), Ok(()));
+
+        let listener = Listener {
+            port: 0,
+            name: String::new(),
+            workers: 3,
+            backends: vec![
+                Backend::Local,
+                Backend::Remote(String::from("db"), 5432),
+                Backend::Socket { path: None },
+            ],
+        };
+        assert_eq!(
+            listener
+                .validate()
+                .unwrap_err()
+                .iter()
+                .map(Violation::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "port: 0 isn't in the range 1..=65535",
+                "name: the value is empty",
+                "workers: 3 isn't even",
+                "backends[1][0]: length 2 isn't in the range 3..",
+                "backends[2].path: the value is empty",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_compares_integer_bounds_exactly() {
+        #[derive(RustyValue)]
+        struct Ids {
+            #[rusty_value(validate(range(max = 9_007_199_254_740_992u64)))]
+            id: u64,
+            #[rusty_value(validate(range(min = i64::MIN + 1)))]
+            offset: i64,
+            #[rusty_value(validate(range(min = 0, max = 0.5)))]
+            ratio: f32,
+        }
+
+        let ids = Ids {
+            id: 9_007_199_254_740_993,
+            offset: i64::MIN,
+            ratio: 0.5,
+        };
+        assert_eq!(
+            ids.validate()
+                .unwrap_err()
+                .iter()
+                .map(Violation::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "id: 9007199254740993 isn't in the range ..=9007199254740992",
+                "offset: -9223372036854775808 isn't in the range -9223372036854775807..",
+            ]
+        );
+        assert!(Number::from(u128::MAX) > Number::from(i128::MAX));
+        assert_eq!(Number::from(1u8), Number::from(1.0));
+    }
+
+    #[test]
+    fn it_reports_constraints_on_unsupported_values() {
+        let mut violations = Vec::new();
+        Constraint::check_field(
+            &[Constraint::Range {
+                min: Some(Number::Float(0.0)),
+                max: None,
+            }],
+            &"text",
+            &Path::new().field("name"),
+            &mut violations,
+        );
+
+        assert_eq!(
+            violations[0].to_string(),
+            "name: expected `number` but found string"
+        );
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn it_matches_patterns() {
+        #[derive(RustyValue)]
+        struct User {
+            #[rusty_value(validate(regex = "^[a-z]+$"))]
+            name: String,
+        }
+
+        let user = User {
+            name: String::from("bob"),
+        };
+        assert_eq!(user.validate(), Ok(()));
+
+        let user = User {
+            name: String::from("Bob"),
+        };
+        assert_eq!(
+            user.validate().unwrap_err()[0].kind,
+            ViolationKind::PatternMismatch(String::from("^[a-z]+$"))
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, ffi::OsString};
+use std::{borrow::Cow, collections::HashMap, ffi::OsString, fmt::Display};
 
 /// Represents a generic rust value
 #[derive(Clone, Debug, PartialEq)]
@@ -12,25 +12,27 @@ pub enum Value {
 }
 
 /// Represents an enum with a given variant
-/// And fields depending on that variant
+/// And fields depending on that variant.
+/// Names that are known at compile time are borrowed instead of allocated.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Enum {
-    pub name: String,
-    pub variant: String,
+    pub name: Cow<'static, str>,
+    pub variant: Cow<'static, str>,
     pub fields: Fields,
 }
 
-/// Represents a struct with fields
+/// Represents a struct with fields.
+/// Names that are known at compile time are borrowed instead of allocated.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Struct {
-    pub name: String,
+    pub name: Cow<'static, str>,
     pub fields: Fields,
 }
 
 /// Fields of a struct or an enum that are either named, unnamed or not defined (Unit enums/structs)
 #[derive(Clone, Debug, PartialEq)]
 pub enum Fields {
-    Named(HashMap<String, Value>),
+    Named(HashMap<Cow<'static, str>, Value>),
     Unnamed(Vec<Value>),
     Unit,
 }
@@ -88,42 +90,42 @@ pub enum HashablePrimitive {
     Bool(bool),
 }
 
-impl ToString for HashablePrimitive {
-    fn to_string(&self) -> String {
+impl Display for HashablePrimitive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            HashablePrimitive::Integer(i) => i.to_string(),
-            HashablePrimitive::String(s) => s.to_owned(),
-            HashablePrimitive::Char(c) => c.to_string(),
-            HashablePrimitive::Bool(b) => b.to_string(),
-            HashablePrimitive::OsString(o) => o.to_string_lossy().into_owned(),
+            HashablePrimitive::Integer(i) => i.fmt(f),
+            HashablePrimitive::String(s) => s.fmt(f),
+            HashablePrimitive::Char(c) => c.fmt(f),
+            HashablePrimitive::Bool(b) => b.fmt(f),
+            HashablePrimitive::OsString(o) => o.to_string_lossy().fmt(f),
         }
     }
 }
 
-impl ToString for Integer {
-    fn to_string(&self) -> String {
+impl Display for Integer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Integer::USize(n) => n.to_string(),
-            Integer::ISize(n) => n.to_string(),
-            Integer::U8(n) => n.to_string(),
-            Integer::I8(n) => n.to_string(),
-            Integer::U16(n) => n.to_string(),
-            Integer::I16(n) => n.to_string(),
-            Integer::U32(n) => n.to_string(),
-            Integer::I32(n) => n.to_string(),
-            Integer::U64(n) => n.to_string(),
-            Integer::I64(n) => n.to_string(),
-            Integer::U128(n) => n.to_string(),
-            Integer::I128(n) => n.to_string(),
+            Integer::USize(n) => n.fmt(f),
+            Integer::ISize(n) => n.fmt(f),
+            Integer::U8(n) => n.fmt(f),
+            Integer::I8(n) => n.fmt(f),
+            Integer::U16(n) => n.fmt(f),
+            Integer::I16(n) => n.fmt(f),
+            Integer::U32(n) => n.fmt(f),
+            Integer::I32(n) => n.fmt(f),
+            Integer::U64(n) => n.fmt(f),
+            Integer::I64(n) => n.fmt(f),
+            Integer::U128(n) => n.fmt(f),
+            Integer::I128(n) => n.fmt(f),
         }
     }
 }
 
-impl ToString for Float {
-    fn to_string(&self) -> String {
+impl Display for Float {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Float::F32(f) => f.to_string(),
-            Float::F64(f) => f.to_string(),
+            Float::F32(n) => n.fmt(f),
+            Float::F64(n) => n.fmt(f),
         }
     }
 }
@@ -0,0 +1,465 @@
+use std::{borrow::Cow, ffi::OsStr};
+
+use crate::{
+    stream::{StreamNode, StreamedFields},
+    Enum, Fields, FieldsKind, Float, HashablePrimitive, HashableValue, Integer, Primitive, Sink,
+    StreamError, StreamRustyValue, Struct, TreeSink, Value,
+};
+
+/// A [crate::Value] that borrows its strings from the value it was created from.
+/// Type, variant and field names are kept like in [crate::Value] so the `&'static str`
+/// names of derived types are never copied.
+///
+/// Named fields and map entries keep the order in which they were streamed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueRef<'a> {
+    Primitive(PrimitiveRef<'a>),
+    Struct(StructRef<'a>),
+    Enum(EnumRef<'a>),
+    Map(Vec<(HashableValueRef<'a>, ValueRef<'a>)>),
+    List(Vec<ValueRef<'a>>),
+    None,
+}
+
+/// A borrowed [crate::Enum]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnumRef<'a> {
+    pub name: Cow<'static, str>,
+    pub variant: Cow<'static, str>,
+    pub fields: FieldsRef<'a>,
+}
+
+/// A borrowed [crate::Struct]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StructRef<'a> {
+    pub name: Cow<'static, str>,
+    pub fields: FieldsRef<'a>,
+}
+
+/// Borrowed [crate::Fields]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldsRef<'a> {
+    Named(Vec<(Cow<'static, str>, ValueRef<'a>)>),
+    Unnamed(Vec<ValueRef<'a>>),
+    Unit,
+}
+
+/// A borrowed [crate::Primitive]
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub enum PrimitiveRef<'a> {
+    Integer(Integer),
+    Float(Float),
+    String(&'a str),
+    OsString(&'a OsStr),
+    Char(char),
+    Bool(bool),
+}
+
+/// A borrowed [crate::HashableValue]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub enum HashableValueRef<'a> {
+    Primitive(HashablePrimitiveRef<'a>),
+    List(Vec<HashableValueRef<'a>>),
+    None,
+}
+
+/// A borrowed [crate::HashablePrimitive]
+#[derive(Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub enum HashablePrimitiveRef<'a> {
+    Integer(Integer),
+    String(&'a str),
+    OsString(&'a OsStr),
+    Char(char),
+    Bool(bool),
+}
+
+impl<'a> ValueRef<'a> {
+    /// Creates the value by streaming the given type.
+    /// Strings are borrowed from the type instead of being copied.
+    pub fn from_stream<T: StreamRustyValue + ?Sized>(
+        value: &'a T,
+    ) -> Result<ValueRef<'a>, StreamError> {
+        let mut sink = ValueRefSink::new();
+        value.stream_rusty_value(&mut sink)?;
+
+        sink.finish()
+    }
+
+    /// Copies the borrowed data into an owned [crate::Value]
+    pub fn into_owned(self) -> Value {
+        match self {
+            ValueRef::Primitive(p) => Value::Primitive(p.into_owned()),
+            ValueRef::Struct(s) => Value::Struct(Struct {
+                name: s.name,
+                fields: s.fields.into_owned(),
+            }),
+            ValueRef::Enum(e) => Value::Enum(Enum {
+                name: e.name,
+                variant: e.variant,
+                fields: e.fields.into_owned(),
+            }),
+            ValueRef::Map(m) => Value::Map(
+                m.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect(),
+            ),
+            ValueRef::List(l) => Value::List(l.into_iter().map(ValueRef::into_owned).collect()),
+            ValueRef::None => Value::None,
+        }
+    }
+}
+
+impl FieldsRef<'_> {
+    pub fn into_owned(self) -> Fields {
+        match self {
+            FieldsRef::Named(n) => {
+                Fields::Named(n.into_iter().map(|(k, v)| (k, v.into_owned())).collect())
+            }
+            FieldsRef::Unnamed(u) => {
+                Fields::Unnamed(u.into_iter().map(ValueRef::into_owned).collect())
+            }
+            FieldsRef::Unit => Fields::Unit,
+        }
+    }
+}
+
+impl PrimitiveRef<'_> {
+    pub fn into_owned(self) -> Primitive {
+        match self {
+            PrimitiveRef::Integer(i) => Primitive::Integer(i),
+            PrimitiveRef::Float(f) => Primitive::Float(f),
+            PrimitiveRef::String(s) => Primitive::String(s.to_owned()),
+            PrimitiveRef::OsString(o) => Primitive::OsString(o.to_owned()),
+            PrimitiveRef::Char(c) => Primitive::Char(c),
+            PrimitiveRef::Bool(b) => Primitive::Bool(b),
+        }
+    }
+}
+
+impl HashableValueRef<'_> {
+    pub fn into_owned(self) -> HashableValue {
+        match self {
+            HashableValueRef::Primitive(p) => HashableValue::Primitive(match p {
+                HashablePrimitiveRef::Integer(i) => HashablePrimitive::Integer(i),
+                HashablePrimitiveRef::String(s) => HashablePrimitive::String(s.to_owned()),
+                HashablePrimitiveRef::OsString(o) => HashablePrimitive::OsString(o.to_owned()),
+                HashablePrimitiveRef::Char(c) => HashablePrimitive::Char(c),
+                HashablePrimitiveRef::Bool(b) => HashablePrimitive::Bool(b),
+            }),
+            HashableValueRef::List(l) => {
+                HashableValue::List(l.into_iter().map(HashableValueRef::into_owned).collect())
+            }
+            HashableValueRef::None => HashableValue::None,
+        }
+    }
+}
+
+impl<'a> From<&'a Value> for ValueRef<'a> {
+    fn from(value: &'a Value) -> Self {
+        match value {
+            Value::Primitive(p) => ValueRef::Primitive(p.into()),
+            Value::Struct(s) => ValueRef::Struct(StructRef {
+                name: s.name.clone(),
+                fields: (&s.fields).into(),
+            }),
+            Value::Enum(e) => ValueRef::Enum(EnumRef {
+                name: e.name.clone(),
+                variant: e.variant.clone(),
+                fields: (&e.fields).into(),
+            }),
+            Value::Map(m) => {
+                let mut entries = m
+                    .iter()
+                    .map(|(k, v)| (HashableValueRef::from(k), ValueRef::from(v)))
+                    .collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                ValueRef::Map(entries)
+            }
+            Value::List(l) => ValueRef::List(l.iter().map(ValueRef::from).collect()),
+            Value::None => ValueRef::None,
+        }
+    }
+}
+
+impl<'a> From<&'a Fields> for FieldsRef<'a> {
+    fn from(fields: &'a Fields) -> Self {
+        match fields {
+            Fields::Named(n) => {
+                let mut named = n
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.into()))
+                    .collect::<Vec<_>>();
+                named.sort_by(|(a, _), (b, _)| a.cmp(b));
+                FieldsRef::Named(named)
+            }
+            Fields::Unnamed(u) => FieldsRef::Unnamed(u.iter().map(ValueRef::from).collect()),
+            Fields::Unit => FieldsRef::Unit,
+        }
+    }
+}
+
+impl<'a> From<&'a Primitive> for PrimitiveRef<'a> {
+    fn from(primitive: &'a Primitive) -> Self {
+        match primitive {
+            Primitive::Integer(i) => PrimitiveRef::Integer(i.clone()),
+            Primitive::Float(f) => PrimitiveRef::Float(f.clone()),
+            Primitive::String(s) => PrimitiveRef::String(s),
+            Primitive::OsString(o) => PrimitiveRef::OsString(o),
+            Primitive::Char(c) => PrimitiveRef::Char(*c),
+            Primitive::Bool(b) => PrimitiveRef::Bool(*b),
+        }
+    }
+}
+
+impl<'a> From<&'a HashableValue> for HashableValueRef<'a> {
+    fn from(value: &'a HashableValue) -> Self {
+        match value {
+            HashableValue::Primitive(p) => HashableValueRef::Primitive(match p {
+                HashablePrimitive::Integer(i) => HashablePrimitiveRef::Integer(i.clone()),
+                HashablePrimitive::String(s) => HashablePrimitiveRef::String(s),
+                HashablePrimitive::OsString(o) => HashablePrimitiveRef::OsString(o),
+                HashablePrimitive::Char(c) => HashablePrimitiveRef::Char(*c),
+                HashablePrimitive::Bool(b) => HashablePrimitiveRef::Bool(*b),
+            }),
+            HashableValue::List(l) => {
+                HashableValueRef::List(l.iter().map(HashableValueRef::from).collect())
+            }
+            HashableValue::None => HashableValueRef::None,
+        }
+    }
+}
+
+impl<'v> StreamRustyValue for ValueRef<'v> {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        match self {
+            ValueRef::Primitive(p) => match p {
+                PrimitiveRef::Integer(i) => sink.integer(i.clone()),
+                PrimitiveRef::Float(f) => sink.float(f.clone()),
+                PrimitiveRef::String(s) => sink.str(s),
+                PrimitiveRef::OsString(o) => sink.os_str(o),
+                PrimitiveRef::Char(c) => sink.char(*c),
+                PrimitiveRef::Bool(b) => sink.bool(*b),
+            },
+            ValueRef::Struct(s) => {
+                sink.begin_struct(s.name.clone(), s.fields.kind(), s.fields.len())?;
+                s.fields.stream_rusty_value(sink)?;
+                sink.end_struct()
+            }
+            ValueRef::Enum(e) => {
+                sink.begin_enum(
+                    e.name.clone(),
+                    e.variant.clone(),
+                    e.fields.kind(),
+                    e.fields.len(),
+                )?;
+                e.fields.stream_rusty_value(sink)?;
+                sink.end_enum()
+            }
+            ValueRef::Map(m) => {
+                sink.begin_map(m.len())?;
+                for (key, value) in m {
+                    key.stream_rusty_value(sink)?;
+                    value.stream_rusty_value(sink)?;
+                }
+                sink.end_map()
+            }
+            ValueRef::List(l) => {
+                sink.begin_list(l.len())?;
+                for item in l {
+                    item.stream_rusty_value(sink)?;
+                }
+                sink.end_list()
+            }
+            ValueRef::None => sink.none(),
+        }
+    }
+}
+
+impl FieldsRef<'_> {
+    fn kind(&self) -> FieldsKind {
+        match self {
+            FieldsRef::Named(_) => FieldsKind::Named,
+            FieldsRef::Unnamed(_) => FieldsKind::Unnamed,
+            FieldsRef::Unit => FieldsKind::Unit,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            FieldsRef::Named(n) => n.len(),
+            FieldsRef::Unnamed(u) => u.len(),
+            FieldsRef::Unit => 0,
+        }
+    }
+
+    /// Streams only the fields without beginning or ending a struct or enum
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        match self {
+            FieldsRef::Named(named) => {
+                for (name, value) in named {
+                    sink.field(name.clone())?;
+                    value.stream_rusty_value(sink)?;
+                }
+            }
+            FieldsRef::Unnamed(unnamed) => {
+                for value in unnamed {
+                    value.stream_rusty_value(sink)?;
+                }
+            }
+            FieldsRef::Unit => {}
+        }
+
+        Ok(())
+    }
+}
+
+impl<'v> StreamRustyValue for HashableValueRef<'v> {
+    fn stream_rusty_value<'a, S: Sink<'a>>(&'a self, sink: &mut S) -> Result<(), S::Error> {
+        match self {
+            HashableValueRef::Primitive(p) => match p {
+                HashablePrimitiveRef::Integer(i) => sink.integer(i.clone()),
+                HashablePrimitiveRef::String(s) => sink.str(s),
+                HashablePrimitiveRef::OsString(o) => sink.os_str(o),
+                HashablePrimitiveRef::Char(c) => sink.char(*c),
+                HashablePrimitiveRef::Bool(b) => sink.bool(*b),
+            },
+            HashableValueRef::List(l) => {
+                sink.begin_list(l.len())?;
+                for item in l {
+                    item.stream_rusty_value(sink)?;
+                }
+                sink.end_list()
+            }
+            HashableValueRef::None => sink.none(),
+        }
+    }
+}
+
+/// A [Sink] that builds a [ValueRef] borrowing the streamed strings
+pub type ValueRefSink<'a> = TreeSink<'a, ValueRef<'a>>;
+
+impl<'a> StreamNode<'a> for ValueRef<'a> {
+    type Key = HashableValueRef<'a>;
+
+    fn primitive(primitive: PrimitiveRef<'a>) -> Self {
+        ValueRef::Primitive(primitive)
+    }
+
+    fn none() -> Self {
+        ValueRef::None
+    }
+
+    fn structure(name: Cow<'static, str>, fields: StreamedFields<Self>) -> Self {
+        ValueRef::Struct(StructRef {
+            name,
+            fields: streamed_fields(fields),
+        })
+    }
+
+    fn enumeration(
+        name: Cow<'static, str>,
+        variant: Cow<'static, str>,
+        fields: StreamedFields<Self>,
+    ) -> Self {
+        ValueRef::Enum(EnumRef {
+            name,
+            variant,
+            fields: streamed_fields(fields),
+        })
+    }
+
+    fn list(items: Vec<Self>) -> Self {
+        ValueRef::List(items)
+    }
+
+    fn map(entries: Vec<(Self::Key, Self)>) -> Self {
+        ValueRef::Map(entries)
+    }
+
+    fn into_key(self) -> Result<Self::Key, StreamError> {
+        into_hashable_ref(self)
+    }
+}
+
+fn streamed_fields(fields: StreamedFields<ValueRef>) -> FieldsRef {
+    match fields {
+        StreamedFields::Named(named) => FieldsRef::Named(named),
+        StreamedFields::Unnamed(unnamed) => FieldsRef::Unnamed(unnamed),
+        StreamedFields::Unit => FieldsRef::Unit,
+    }
+}
+
+fn into_hashable_ref(value: ValueRef) -> Result<HashableValueRef, StreamError> {
+    let hashable = match value {
+        ValueRef::Primitive(p) => HashableValueRef::Primitive(match p {
+            PrimitiveRef::Integer(i) => HashablePrimitiveRef::Integer(i),
+            PrimitiveRef::String(s) => HashablePrimitiveRef::String(s),
+            PrimitiveRef::OsString(o) => HashablePrimitiveRef::OsString(o),
+            PrimitiveRef::Char(c) => HashablePrimitiveRef::Char(c),
+            PrimitiveRef::Bool(b) => HashablePrimitiveRef::Bool(b),
+            PrimitiveRef::Float(_) => return Err(StreamError::UnhashableKey),
+        }),
+        ValueRef::List(l) => HashableValueRef::List(
+            l.into_iter()
+                .map(into_hashable_ref)
+                .collect::<Result<_, _>>()?,
+        ),
+        ValueRef::None => HashableValueRef::None,
+        ValueRef::Struct(_) | ValueRef::Enum(_) | ValueRef::Map(_) => {
+            return Err(StreamError::UnhashableKey)
+        }
+    };
+
+    Ok(hashable)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    fn test_value() -> Value {
+        rusty_value!(struct Config {
+            name: "test",
+            ports: [80u16, 443u16],
+            hosts: { "a" => None, "b" => [1u8] },
+            mode: enum Mode::Slow { delay: 1u32 },
+            nested: enum Nested::Pair(1.5f64, struct Unit),
+        })
+    }
+
+    #[test]
+    fn it_borrows_strings() {
+        let value = test_value();
+        let value_ref = ValueRef::from_stream(&value).unwrap();
+
+        match &value_ref {
+            ValueRef::Struct(StructRef {
+                fields: FieldsRef::Named(named),
+                ..
+            }) => {
+                let (name, field) = &named[1];
+                assert_eq!(*name, "mode");
+                assert!(matches!(field, ValueRef::Enum(e) if e.variant == "Slow"));
+            }
+            _ => panic!("the value isn't a struct"),
+        }
+        assert_eq!(value_ref.into_owned(), value);
+    }
+
+    #[test]
+    fn it_converts_from_values() {
+        let value = test_value();
+        let value_ref = ValueRef::from(&value);
+
+        assert_eq!(value_ref, ValueRef::from_stream(&value).unwrap());
+        assert_eq!(value_ref.into_owned(), value);
+    }
+
+    #[test]
+    fn it_streams_borrowed_values() {
+        let value = test_value();
+        let value_ref = ValueRef::from(&value);
+
+        assert_eq!(Value::from_stream(&value_ref), Ok(value));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, ffi::OsString, path::PathBuf};
+use std::{borrow::Cow, collections::HashMap, ffi::OsString, path::PathBuf};
 
 use crate::{Fields, Float, HashablePrimitive, HashableValue, Primitive, Struct, Value};
 
@@ -101,7 +101,7 @@ impl HashableRustyValue for String {
     }
 }
 
-impl<'a> HashableRustyValue for &'a str {
+impl HashableRustyValue for &str {
     fn into_hashable_rusty_value(self) -> HashableValue {
         self.to_string().into_hashable_rusty_value()
     }
@@ -170,7 +170,7 @@ impl RustyValue for PathBuf {
     #[inline]
     fn into_rusty_value(self) -> Value {
         Value::Struct(Struct {
-            name: String::from("PathBuf"),
+            name: Cow::Borrowed("PathBuf"),
             fields: Fields::Unnamed(vec![self.into_os_string().into_rusty_value()]),
         })
     }
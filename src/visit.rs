@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use crate::{Enum, Fields, HashableValue, Path, PathSegment, Primitive, Struct, Value};
+
+/// The position of the value that is currently visited
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+    path: Path,
+}
+
+impl Context {
+    /// Returns the path from the root value to the current value
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns how deep the current value is nested. The root value has a depth of 0.
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+}
+
+/// Walks over a [crate::Value] tree.
+///
+/// Every method by default continues walking into the children of the node.
+/// Overriding methods can call the matching `walk_*` function to keep walking
+/// or return early to skip the children.
+/// Named fields and map entries are visited sorted by their keys.
+pub trait Visitor {
+    fn visit_value(&mut self, ctx: &mut Context, value: &Value) {
+        walk_value(self, ctx, value)
+    }
+
+    fn visit_primitive(&mut self, _ctx: &mut Context, _primitive: &Primitive) {}
+
+    fn visit_struct(&mut self, ctx: &mut Context, value: &Struct) {
+        walk_fields(self, ctx, &value.fields)
+    }
+
+    fn visit_enum(&mut self, ctx: &mut Context, value: &Enum) {
+        walk_fields(self, ctx, &value.fields)
+    }
+
+    /// Visits a named field. The path of the context already contains the field.
+    fn visit_field(&mut self, ctx: &mut Context, _name: &str, value: &Value) {
+        self.visit_value(ctx, value)
+    }
+
+    /// Visits an unnamed field. The path of the context already contains the index.
+    fn visit_unnamed_field(&mut self, ctx: &mut Context, _index: usize, value: &Value) {
+        self.visit_value(ctx, value)
+    }
+
+    fn visit_map(&mut self, ctx: &mut Context, map: &HashMap<HashableValue, Value>) {
+        walk_map(self, ctx, map)
+    }
+
+    /// Visits a map entry. The path of the context already contains the key.
+    fn visit_map_entry(&mut self, ctx: &mut Context, _key: &HashableValue, value: &Value) {
+        self.visit_value(ctx, value)
+    }
+
+    fn visit_list(&mut self, ctx: &mut Context, list: &[Value]) {
+        walk_list(self, ctx, list)
+    }
+
+    /// Visits a list item. The path of the context already contains the index.
+    fn visit_list_item(&mut self, ctx: &mut Context, _index: usize, value: &Value) {
+        self.visit_value(ctx, value)
+    }
+
+    fn visit_none(&mut self, _ctx: &mut Context) {}
+}
+
+/// Walks over a [crate::Value] tree and allows modifying it.
+///
+/// The methods behave like the ones of [Visitor].
+/// [VisitorMut::visit_value] can replace the whole value before or instead of walking into it.
+pub trait VisitorMut {
+    fn visit_value(&mut self, ctx: &mut Context, value: &mut Value) {
+        walk_value_mut(self, ctx, value)
+    }
+
+    fn visit_primitive(&mut self, _ctx: &mut Context, _primitive: &mut Primitive) {}
+
+    fn visit_struct(&mut self, ctx: &mut Context, value: &mut Struct) {
+        walk_fields_mut(self, ctx, &mut value.fields)
+    }
+
+    fn visit_enum(&mut self, ctx: &mut Context, value: &mut Enum) {
+        walk_fields_mut(self, ctx, &mut value.fields)
+    }
+
+    fn visit_field(&mut self, ctx: &mut Context, _name: &str, value: &mut Value) {
+        self.visit_value(ctx, value)
+    }
+
+    fn visit_unnamed_field(&mut self, ctx: &mut Context, _index: usize, value: &mut Value) {
+        self.visit_value(ctx, value)
+    }
+
+    fn visit_map(&mut self, ctx: &mut Context, map: &mut HashMap<HashableValue, Value>) {
+        walk_map_mut(self, ctx, map)
+    }
+
+    fn visit_map_entry(&mut self, ctx: &mut Context, _key: &HashableValue, value: &mut Value) {
+        self.visit_value(ctx, value)
+    }
+
+    fn visit_list(&mut self, ctx: &mut Context, list: &mut Vec<Value>) {
+        walk_list_mut(self, ctx, list)
+    }
+
+    fn visit_list_item(&mut self, ctx: &mut Context, _index: usize, value: &mut Value) {
+        self.visit_value(ctx, value)
+    }
+
+    fn visit_none(&mut self, _ctx: &mut Context) {}
+}
+
+/// Visits the value starting at the root
+pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, value: &Value) {
+    visitor.visit_value(&mut Context::default(), value)
+}
+
+/// Visits the value starting at the root and allows the visitor to modify it
+pub fn walk_mut<V: VisitorMut + ?Sized>(visitor: &mut V, value: &mut Value) {
+    visitor.visit_value(&mut Context::default(), value)
+}
+
+/// Calls the visitor method that matches the kind of the value
+pub fn walk_value<V: Visitor + ?Sized>(visitor: &mut V, ctx: &mut Context, value: &Value) {
+    match value {
+        Value::Primitive(p) => visitor.visit_primitive(ctx, p),
+        Value::Struct(s) => visitor.visit_struct(ctx, s),
+        Value::Enum(e) => visitor.visit_enum(ctx, e),
+        Value::Map(m) => visitor.visit_map(ctx, m),
+        Value::List(l) => visitor.visit_list(ctx, l),
+        Value::None => visitor.visit_none(ctx),
+    }
+}
+
+/// Visits every field of a struct or enum variant
+pub fn walk_fields<V: Visitor + ?Sized>(visitor: &mut V, ctx: &mut Context, fields: &Fields) {
+    match fields {
+        Fields::Named(named) => {
+            let mut named = named.iter().collect::<Vec<_>>();
+            named.sort_by_key(|(k, _)| *k);
+
+            for (name, value) in named {
+                ctx.path.push(PathSegment::Field(name.to_string()));
+                visitor.visit_field(ctx, name, value);
+                ctx.path.pop();
+            }
+        }
+        Fields::Unnamed(unnamed) => {
+            for (index, value) in unnamed.iter().enumerate() {
+                ctx.path.push(PathSegment::Index(index));
+                visitor.visit_unnamed_field(ctx, index, value);
+                ctx.path.pop();
+            }
+        }
+        Fields::Unit => {}
+    }
+}
+
+/// Visits every entry of a map
+pub fn walk_map<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    ctx: &mut Context,
+    map: &HashMap<HashableValue, Value>,
+) {
+    let mut entries = map.iter().collect::<Vec<_>>();
+    entries.sort_by_key(|(k, _)| *k);
+
+    for (key, value) in entries {
+        ctx.path.push(PathSegment::Key(key.clone()));
+        visitor.visit_map_entry(ctx, key, value);
+        ctx.path.pop();
+    }
+}
+
+/// Visits every item of a list
+pub fn walk_list<V: Visitor + ?Sized>(visitor: &mut V, ctx: &mut Context, list: &[Value]) {
+    for (index, value) in list.iter().enumerate() {
+        ctx.path.push(PathSegment::Index(index));
+        visitor.visit_list_item(ctx, index, value);
+        ctx.path.pop();
+    }
+}
+
+/// Calls the visitor method that matches the kind of the value
+pub fn walk_value_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    ctx: &mut Context,
+    value: &mut Value,
+) {
+    match value {
+        Value::Primitive(p) => visitor.visit_primitive(ctx, p),
+        Value::Struct(s) => visitor.visit_struct(ctx, s),
+        Value::Enum(e) => visitor.visit_enum(ctx, e),
+        Value::Map(m) => visitor.visit_map(ctx, m),
+        Value::List(l) => visitor.visit_list(ctx, l),
+        Value::None => visitor.visit_none(ctx),
+    }
+}
+
+/// Visits every field of a struct or enum variant
+pub fn walk_fields_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    ctx: &mut Context,
+    fields: &mut Fields,
+) {
+    match fields {
+        Fields::Named(named) => {
+            let mut named = named.iter_mut().collect::<Vec<_>>();
+            named.sort_by_key(|(k, _)| *k);
+
+            for (name, value) in named {
+                ctx.path.push(PathSegment::Field(name.to_string()));
+                visitor.visit_field(ctx, name, value);
+                ctx.path.pop();
+            }
+        }
+        Fields::Unnamed(unnamed) => {
+            for (index, value) in unnamed.iter_mut().enumerate() {
+                ctx.path.push(PathSegment::Index(index));
+                visitor.visit_unnamed_field(ctx, index, value);
+                ctx.path.pop();
+            }
+        }
+        Fields::Unit => {}
+    }
+}
+
+/// Visits every entry of a map
+pub fn walk_map_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    ctx: &mut Context,
+    map: &mut HashMap<HashableValue, Value>,
+) {
+    let mut entries = map.iter_mut().collect::<Vec<_>>();
+    entries.sort_by_key(|(k, _)| *k);
+
+    for (key, value) in entries {
+        ctx.path.push(PathSegment::Key(key.clone()));
+        visitor.visit_map_entry(ctx, key, value);
+        ctx.path.pop();
+    }
+}
+
+/// Visits every item of a list
+pub fn walk_list_mut<V: VisitorMut + ?Sized>(
+    visitor: &mut V,
+    ctx: &mut Context,
+    list: &mut [Value],
+) {
+    for (index, value) in list.iter_mut().enumerate() {
+        ctx.path.push(PathSegment::Index(index));
+        visitor.visit_list_item(ctx, index, value);
+        ctx.path.pop();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::*;
+
+    fn test_value() -> Value {
+        rusty_value!(struct User {
+            name: "admin",
+            password: "hunter2",
+            tokens: [struct Token { id: 1u8, secret: "abc" }],
+            meta: { "password" => "not a field" },
+            state: enum State::Active(None),
+        })
+    }
+
+    #[derive(Default)]
+    struct Stats {
+        primitives: usize,
+        nones: usize,
+        max_depth: usize,
+        paths: Vec<String>,
+    }
+
+    impl Visitor for Stats {
+        fn visit_value(&mut self, ctx: &mut Context, value: &Value) {
+            self.max_depth = self.max_depth.max(ctx.depth());
+            walk_value(self, ctx, value)
+        }
+
+        fn visit_primitive(&mut self, ctx: &mut Context, _primitive: &Primitive) {
+            self.primitives += 1;
+            self.paths.push(ctx.path().to_string());
+        }
+
+        fn visit_none(&mut self, _ctx: &mut Context) {
+            self.nones += 1;
+        }
+    }
+
+    #[test]
+    fn it_visits_values() {
+        let mut stats = Stats::default();
+        walk(&mut stats, &test_value());
+
+        assert_eq!(stats.primitives, 5);
+        assert_eq!(stats.nones, 1);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(
+            stats.paths,
+            vec![
+                r#"meta["password"]"#,
+                "name",
+                "password",
+                "tokens[0].id",
+                "tokens[0].secret",
+            ]
+        );
+    }
+
+    struct Redact;
+
+    impl VisitorMut for Redact {
+        fn visit_field(&mut self, ctx: &mut Context, name: &str, value: &mut Value) {
+            if name == "password" || name == "secret" {
+                *value = rusty_value!("***");
+            } else {
+                self.visit_value(ctx, value)
+            }
+        }
+    }
+
+    #[test]
+    fn it_modifies_values() {
+        let mut value = test_value();
+        walk_mut(&mut Redact, &mut value);
+
+        assert_eq!(
+            value,
+            rusty_value!(struct User {
+                name: "admin",
+                password: "***",
+                tokens: [struct Token { id: 1u8, secret: "***" }],
+                meta: { "password" => "not a field" },
+                state: enum State::Active(None),
+            })
+        );
+    }
+}
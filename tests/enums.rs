@@ -1,3 +1,4 @@
+#![allow(clippy::disallowed_names, clippy::assertions_on_constants)]
 use rusty_value::*;
 use rusty_value::{Fields, RustyValue, Value};
 
@@ -0,0 +1,240 @@
+#![allow(clippy::disallowed_names)]
+use std::collections::HashMap;
+
+use rusty_value::*;
+
+#[derive(RustyValue)]
+struct TestStruct {
+    foo: String,
+    bar: Vec<u8>,
+    baz: Option<TestEnum>,
+}
+
+#[derive(RustyValue)]
+struct TestTuple(u16, i64);
+
+#[derive(RustyValue)]
+struct TestUnit;
+
+#[derive(RustyValue)]
+enum TestEnum {
+    Foo,
+    Bar(u8),
+    Baz { value: String, other: bool },
+}
+
+#[test]
+fn it_creates_primitives() {
+    assert_eq!(rusty_value!(12u8), 12u8.into_rusty_value());
+    assert_eq!(rusty_value!(-1i128), (-1i128).into_rusty_value());
+    assert_eq!(rusty_value!(1.5f32), 1.5f32.into_rusty_value());
+    assert_eq!(rusty_value!("hi"), "hi".into_rusty_value());
+    assert_eq!(rusty_value!(None), Value::None);
+
+    let value = String::from("hello");
+    assert_eq!(rusty_value!(value.clone()), value.into_rusty_value());
+}
+
+#[test]
+fn it_creates_structs() {
+    let value = rusty_value!(struct TestStruct {
+        foo: "hello",
+        bar: [1u8, 2u8],
+        baz: enum TestEnum::Baz { value: "world", other: true },
+    });
+    let expected = TestStruct {
+        foo: String::from("hello"),
+        bar: vec![1, 2],
+        baz: Some(TestEnum::Baz {
+            value: String::from("world"),
+            other: true,
+        }),
+    }
+    .into_rusty_value();
+    assert_eq!(value, expected);
+
+    assert_eq!(
+        rusty_value!(struct TestTuple(1u16, -2i64)),
+        TestTuple(1, -2).into_rusty_value()
+    );
+    assert_eq!(rusty_value!(struct TestUnit), TestUnit.into_rusty_value());
+}
+
+#[test]
+fn it_creates_enums() {
+    assert_eq!(
+        rusty_value!(enum TestEnum::Foo),
+        TestEnum::Foo.into_rusty_value()
+    );
+    assert_eq!(
+        rusty_value!(enum TestEnum::Bar(3u8)),
+        TestEnum::Bar(3).into_rusty_value()
+    );
+}
+
+#[test]
+fn it_creates_collections() {
+    assert_eq!(
+        rusty_value!([1u8, 2u8, 3u8,]),
+        vec![1u8, 2, 3].into_rusty_value()
+    );
+    assert_eq!(rusty_value!([]), Value::List(Vec::new()));
+    assert_eq!(
+        rusty_value!({ "a" => [1u8], "b" => [] }),
+        HashMap::from([("a", vec![1u8]), ("b", vec![])]).into_rusty_value()
+    );
+    assert_eq!(
+        rusty_value!({ 1u32 => struct TestUnit, 1u32 + 1 => None }),
+        Value::Map(HashMap::from([
+            (
+                1u32.into_hashable_rusty_value(),
+                TestUnit.into_rusty_value()
+            ),
+            (2u32.into_hashable_rusty_value(), Value::None)
+        ]))
+    );
+}
+
+#[test]
+fn it_creates_large_values() {
+    let value = rusty_value!([
+        0u8,
+        1u8,
+        2u8,
+        3u8,
+        4u8,
+        5u8,
+        6u8,
+        7u8,
+        8u8,
+        9u8,
+        10u8,
+        11u8,
+        12u8,
+        13u8,
+        14u8,
+        15u8,
+        16u8,
+        17u8,
+        18u8,
+        19u8,
+        20u8,
+        21u8,
+        22u8,
+        23u8,
+        24u8,
+        25u8,
+        26u8,
+        27u8,
+        28u8,
+        29u8,
+        30u8,
+        31u8,
+        32u8,
+        33u8,
+        34u8,
+        35u8,
+        36u8,
+        37u8,
+        38u8,
+        39u8,
+        40u8,
+        41u8,
+        42u8,
+        43u8,
+        44u8,
+        45u8,
+        46u8,
+        47u8,
+        48u8,
+        49u8,
+        50u8,
+        51u8,
+        52u8,
+        53u8,
+        54u8,
+        55u8,
+        56u8,
+        57u8,
+        58u8,
+        59u8,
+        60u8,
+        61u8,
+        62u8,
+        63u8,
+        64u8,
+        65u8,
+        66u8,
+        67u8,
+        68u8,
+        69u8,
+        70u8,
+        71u8,
+        72u8,
+        73u8,
+        74u8,
+        75u8,
+        76u8,
+        77u8,
+        78u8,
+        79u8,
+        80u8,
+        81u8,
+        82u8,
+        83u8,
+        84u8,
+        85u8,
+        86u8,
+        87u8,
+        88u8,
+        89u8,
+        90u8,
+        91u8,
+        92u8,
+        93u8,
+        94u8,
+        95u8,
+        96u8,
+        97u8,
+        98u8,
+        99u8,
+        -1i8,
+        (2u8 + 1),
+        None,
+    ]);
+    let Value::List(items) = value else {
+        panic!("expected a list");
+    };
+    assert_eq!(items.len(), 103);
+    assert_eq!(items[99], 99u8.into_rusty_value());
+    assert_eq!(items[100], (-1i8).into_rusty_value());
+    assert_eq!(items[101], 3u8.into_rusty_value());
+    assert_eq!(items[102], Value::None);
+
+    assert_eq!(
+        rusty_value!(struct TestStruct {
+            foo: String::from("a") + "b",
+            bar: vec![1u8, 2],
+            baz: None,
+        }),
+        TestStruct {
+            foo: String::from("ab"),
+            bar: vec![1, 2],
+            baz: None,
+        }
+        .into_rusty_value()
+    );
+    assert_eq!(
+        rusty_value!({ "a" => struct TestTuple(1u16, -2i64), "b" => enum TestEnum::Bar(3u8) }),
+        Value::Map(HashMap::from([
+            (
+                "a".into_hashable_rusty_value(),
+                TestTuple(1, -2).into_rusty_value()
+            ),
+            (
+                "b".into_hashable_rusty_value(),
+                TestEnum::Bar(3).into_rusty_value()
+            )
+        ]))
+    );
+}
@@ -0,0 +1,124 @@
+#![allow(clippy::disallowed_names)]
+use std::{collections::HashMap, path::PathBuf};
+
+use rusty_value::*;
+
+#[derive(Clone, RustyValue)]
+#[rusty_value(stream)]
+struct TestStructNamed {
+    foo: String,
+    bar: u64,
+    none: (),
+    path: PathBuf,
+    map: HashMap<String, TestEnum>,
+}
+
+#[derive(Clone, RustyValue)]
+#[rusty_value(stream)]
+struct TestStructUnnamed(String, Option<u8>);
+
+#[derive(Clone, RustyValue)]
+#[rusty_value(stream)]
+struct TestStructUnit;
+
+#[derive(Clone, RustyValue)]
+#[rusty_value(stream)]
+enum TestEnum {
+    Unit,
+    Unnamed(TestStructUnnamed, TestStructUnit),
+    Named { foo: Vec<f32> },
+}
+
+#[derive(Clone, RustyValue)]
+#[rusty_value(stream)]
+struct GenericStruct<T: Clone> {
+    field: T,
+}
+
+fn assert_streams_equal<T: RustyValue + StreamRustyValue + Clone>(value: T) {
+    assert_eq!(
+        Value::from_stream(&value).unwrap(),
+        value.into_rusty_value()
+    );
+}
+
+#[test]
+fn it_streams_structs() {
+    let mut map = HashMap::new();
+    map.insert(String::from("unit"), TestEnum::Unit);
+    map.insert(
+        String::from("named"),
+        TestEnum::Named {
+            foo: vec![1.0, 2.5],
+        },
+    );
+
+    assert_streams_equal(TestStructNamed {
+        foo: String::from("Hello World"),
+        bar: 12,
+        none: (),
+        path: PathBuf::from("/tmp"),
+        map,
+    });
+    assert_streams_equal(TestStructUnnamed(String::from("Hello World"), None));
+    assert_streams_equal(TestStructUnit);
+    assert_streams_equal(GenericStruct { field: 12u8 });
+
+    let Ok(Value::Struct(s)) = Value::from_stream(&TestStructUnnamed(String::new(), None)) else {
+        panic!("expected a struct");
+    };
+    assert!(matches!(s.name, std::borrow::Cow::Borrowed(_)));
+}
+
+#[test]
+fn it_streams_enums() {
+    assert_streams_equal(TestEnum::Unit);
+    assert_streams_equal(TestEnum::Unnamed(
+        TestStructUnnamed(String::new(), Some(1)),
+        TestStructUnit,
+    ));
+    assert_streams_equal(TestEnum::Named { foo: vec![] });
+}
+
+#[derive(Clone)]
+struct ManualValue(u8);
+
+impl RustyValue for ManualValue {
+    fn into_rusty_value(self) -> Value {
+        self.0.into_rusty_value()
+    }
+}
+
+#[derive(Clone, RustyValue)]
+struct WithoutStream {
+    manual: ManualValue,
+}
+
+#[test]
+fn it_only_streams_opted_in_types() {
+    assert_eq!(
+        WithoutStream {
+            manual: ManualValue(1),
+        }
+        .into_rusty_value(),
+        rusty_value!(struct WithoutStream { manual: 1u8 })
+    );
+}
+
+mod qualified {
+    use rusty_value::{Fields, RustyValue, Struct, Value};
+
+    #[derive(RustyValue)]
+    #[rusty_value(stream)]
+    pub struct Qualified {
+        pub value: u8,
+    }
+}
+
+#[test]
+fn it_streams_without_importing_the_stream_types() {
+    assert_eq!(
+        Value::from_stream(&qualified::Qualified { value: 1 }).unwrap(),
+        rusty_value!(struct Qualified { value: 1u8 })
+    );
+}
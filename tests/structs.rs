@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+#![allow(clippy::disallowed_names, clippy::assertions_on_constants)]
+use std::{borrow::Cow, path::PathBuf};
 
 use rusty_value::*;
 use rusty_value::{Fields, RustyValue, Value};
@@ -101,3 +102,26 @@ fn it_handles_generics() {
         panic!("Struct wasn't serialized as struct");
     }
 }
+
+#[test]
+fn it_borrows_names() {
+    let value = TestStructNamed {
+        foo: String::new(),
+        bar: 0,
+        none: (),
+        path: PathBuf::new(),
+    }
+    .into_rusty_value();
+
+    if let Value::Struct(s) = value {
+        assert!(matches!(s.name, Cow::Borrowed("TestStructNamed")));
+
+        if let Fields::Named(fields) = s.fields {
+            assert!(fields.keys().all(|k| matches!(k, Cow::Borrowed(_))));
+        } else {
+            panic!("Struct wasn't serialized as named struct")
+        }
+    } else {
+        panic!("Struct wasn't serialized as struct");
+    }
+}